@@ -1,11 +1,14 @@
+//! Helpers shared by the experiment drivers.
+#![allow(dead_code)]
+
 use ndarray::{array, Array2};
 use std::fs::File;
 use std::io::Write;
 
-use crate::common::IntegratorDummy;
-use crate::{
+use simplex_subdivision_integration::common::IntegratorDummy;
+use simplex_subdivision_integration::{
     integration_2d::{
-        domain::{Simplex2D, Simplex2DIntegrator},
+        domain::{Simplex2D, SimplexIntegrator},
         functions::{Function2DHistory, RepeatedPyramidFunction},
         integrators::{
             hierarchic_integrator::{Hierarchic2DIntegrator, Hierarchic2DIntegratorData},
@@ -16,7 +19,7 @@ use crate::{
 };
 
 /// Creates the Figures for documentation
-pub fn create_figures(integrators: Vec<(String, Box<impl Simplex2DIntegrator<IntegratorDummy>>)>) {
+pub fn create_figures(integrators: Vec<(String, Box<impl SimplexIntegrator<2, IntegratorDummy>>)>) {
     let sim = Simplex2D::new_from_points(
         &array![0., 0.],
         &array![1., 0.],
@@ -32,7 +35,7 @@ pub fn create_figures(integrators: Vec<(String, Box<impl Simplex2DIntegrator<Int
             [0.5, 0.3, 0.2, 3.0],
         ])));
 
-        let result = inte.integrate_simplex(&func, &sim, &mut dummy);
+        inte.integrate_simplex(&func, &sim, &mut dummy).unwrap();
 
         let hist = func.get_history();
         let mut file = File::create(&format!("{}.tikz", name)).unwrap();
@@ -65,7 +68,7 @@ pub fn create_figures(integrators: Vec<(String, Box<impl Simplex2DIntegrator<Int
     }
 }
 
-fn get_diagonal_order(highest_index: usize) -> Vec<(usize, usize, usize)> {
+pub fn get_diagonal_order(highest_index: usize) -> Vec<(usize, usize, usize)> {
     let mut res_vec = Vec::new();
     {
         let col = highest_index;
@@ -106,7 +109,7 @@ pub fn froebenius_norm(first: Array2<f64>) -> f64 {
     return sum;
 }
 
-pub fn edge_refinement_test_2d<I: Simplex2DIntegrator<IntegratorDummy>>(
+pub fn edge_refinement_test_2d<I: SimplexIntegrator<2, IntegratorDummy>>(
     base_integrator: I,
 ) -> Vec<[f64; 3]> {
     let sim = Simplex2D::new_from_points(
@@ -125,7 +128,8 @@ pub fn edge_refinement_test_2d<I: Simplex2DIntegrator<IntegratorDummy>>(
     let mut res = Array2::<f64>::zeros([6, 6]);
     let res_vec = get_diagonal_order(5);
     loop {
-        let edge_integrator = EdgeSubdivisionIntegrator::new(base_integrator.dupe(), order);
+        let edge_integrator =
+            EdgeSubdivisionIntegrator::new(base_integrator.dupe(), order).unwrap();
         last_res = res.clone();
         for (_count, i, j) in &res_vec {
             let func = Box::new(Function2DHistory::new(PhaseFieldFuncDiff22D::new(
@@ -137,6 +141,7 @@ pub fn edge_refinement_test_2d<I: Simplex2DIntegrator<IntegratorDummy>>(
             )));
             res[[*i, *j]] = edge_integrator
                 .integrate_simplex(&func, &sim, &mut dummy)
+                .unwrap()
                 .get(); //&mut cache
 
             evals = func.function_evaluations();
@@ -163,7 +168,7 @@ pub fn edge_refinement_test_2d<I: Simplex2DIntegrator<IntegratorDummy>>(
     return konvergence_list;
 }
 
-fn hierarchic_integration_test_2d<I: Simplex2DIntegrator<IntegratorDummy>>(base_integrator: I) {
+pub fn hierarchic_integration_test_2d<I: SimplexIntegrator<2, IntegratorDummy>>(base_integrator: I) {
     let sim = Simplex2D::new_from_points(
         &array![1., 1.],
         &array![2., 1.],
@@ -192,6 +197,7 @@ fn hierarchic_integration_test_2d<I: Simplex2DIntegrator<IntegratorDummy>>(base_
         )));
         res[[i, j]] = hierarchic_inte
             .integrate_simplex(&func, &sim, &mut cache)
+            .unwrap()
             .get(); //&mut cache
         if count < 6 + 1 * 5 {
             // Wenn Diagonale und erste nebendiagonale durch sind
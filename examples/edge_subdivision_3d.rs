@@ -0,0 +1,30 @@
+//! Edge subdivision of a regular tetrahedron, prints the integral of the constant function next
+//! to the volume.
+use ndarray::array;
+
+use simplex_subdivision_integration::common::IntegratorDummy;
+use simplex_subdivision_integration::integration_3d::{
+    functions::Constant3DFunction,
+    integrators::{EdgeSubdivisionIntegrator, Quadrilateral3DIntegrator},
+    Simplex3D, SimplexIntegrator,
+};
+
+fn main() {
+    let sim = Simplex3D::new_from_points(
+        &array![(8.0f64 / 9.0).sqrt(), 0., -1.0 / 3.0],
+        &array![-(2.0f64 / 9.0).sqrt(), (2.0f64 / 3.0).sqrt(), -1.0 / 3.0],
+        &array![-(2.0f64 / 9.0).sqrt(), -(2.0f64 / 3.0).sqrt(), -1.0 / 3.0],
+        &array![0.0, 0.0, 1.0],
+    );
+
+    let basic_integrator = Quadrilateral3DIntegrator::new(1).unwrap();
+    let edge_inte = EdgeSubdivisionIntegrator::new(basic_integrator, 10).unwrap();
+
+    let func = Box::new(Constant3DFunction {});
+
+    let result = edge_inte
+        .integrate_simplex(&func, &sim, &mut IntegratorDummy::get())
+        .unwrap();
+
+    println!("{},{}", result.get(), sim.get_volume())
+}
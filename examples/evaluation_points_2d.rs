@@ -0,0 +1,52 @@
+//! Writes the evaluation points of the hierarchic 2D integrator to `output.csv`.
+//!
+//! The tree is built with the phase field function and then reused for a repeated pyramid.
+use ndarray::array;
+use std::fs::File;
+use std::io::Write;
+
+use simplex_subdivision_integration::integration_2d::{
+    functions::{Function2DHistory, RepeatedPyramidFunction},
+    integrators::{Hierarchic2DIntegrator, Hierarchic2DIntegratorData, Quadrilateral2DIntegrator},
+    Simplex2D, SimplexIntegrator,
+};
+use simplex_subdivision_integration::problems::PhaseField2DFunction;
+
+fn main() {
+    let sim = Simplex2D::new_from_points(
+        &array![1., 1.],
+        &array![1.5, 1. + (3.0f64).sqrt() / 2.],
+        &array![2., 1.],
+    );
+    let precision = 1e-7;
+    let inte1 = Quadrilateral2DIntegrator::new(1).unwrap();
+    let inte2 = Hierarchic2DIntegrator::new(inte1, false, precision);
+    let func = Box::new(Function2DHistory::new(PhaseField2DFunction {
+        weights: [10.0, 10.0, 10.0, 10.0, -0., -0.],
+    }));
+    let func2 = Box::new(Function2DHistory::new(RepeatedPyramidFunction::new(vec![
+        [0.2, 0.3, 0.5, 3.0],
+        [0.5, 0.3, 0.2, 3.0],
+    ])));
+
+    let mut cache = Hierarchic2DIntegratorData::new_cache();
+
+    inte2.integrate_simplex(&func, &sim, &mut cache).unwrap();
+
+    println!("Evaluations while building: {}", func.function_evaluations());
+    func.delete_history();
+
+    inte2.integrate_simplex(&func2, &sim, &mut cache).unwrap();
+
+    let hist = func2.get_history();
+
+    let mut f = File::create("output.csv").expect("Unable to create file");
+    let points = sim.get_points();
+    for i in 0..3 {
+        writeln!(f, "{} {} 0.0", points[[0, i]], points[[1, i]]).expect("Unable to write!");
+    }
+    for i in &hist {
+        let p = points.dot(i);
+        writeln!(f, "{} {}", p[0], p[1]).expect("Unable to write!");
+    }
+}
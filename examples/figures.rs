@@ -0,0 +1,79 @@
+//! Creates the TikZ figures of the evaluation points for the documentation and the convergence
+//! data of the edge subdivision integrator.
+mod common;
+
+use std::fs::File;
+use std::io::Write;
+
+use simplex_subdivision_integration::integration_2d::integrators::{
+    DunavantIntegrator, EdgeSubdivisionIntegrator, Quadrilateral2DIntegrator,
+};
+
+use common::{create_figures, edge_refinement_test_2d};
+
+fn all_figures() {
+    create_figures(vec![
+        (
+            String::from("Quad2D-1"),
+            Box::new(Quadrilateral2DIntegrator::new(1).unwrap()),
+        ),
+        (
+            String::from("Quad2D-2"),
+            Box::new(Quadrilateral2DIntegrator::new(2).unwrap()),
+        ),
+        (
+            String::from("Quad2D-3"),
+            Box::new(Quadrilateral2DIntegrator::new(3).unwrap()),
+        ),
+    ]);
+    create_figures(vec![
+        (
+            String::from("Dunavant2D-1"),
+            Box::new(DunavantIntegrator::new(1).unwrap()),
+        ),
+        (
+            String::from("Dunavant2D-2"),
+            Box::new(DunavantIntegrator::new(2).unwrap()),
+        ),
+        (
+            String::from("Dunavant2D-3"),
+            Box::new(DunavantIntegrator::new(3).unwrap()),
+        ),
+    ]);
+    create_figures(
+        (1..=6)
+            .map(|order| {
+                (
+                    format!("EdgeSubdivision2D-{}", order),
+                    Box::new(
+                        EdgeSubdivisionIntegrator::new(DunavantIntegrator::new(1).unwrap(), order)
+                            .unwrap(),
+                    ),
+                )
+            })
+            .collect(),
+    );
+}
+
+fn export_data(str: &str, data: Vec<[f64; 3]>) {
+    let mut file = File::create(format!("{}.csv", str)).unwrap();
+    for d in data {
+        writeln!(file, "{},{},{}", d[0], d[1], d[2]).unwrap();
+    }
+}
+
+fn edge_subdivision_stats() {
+    for order in 1..=3 {
+        let data = edge_refinement_test_2d(DunavantIntegrator::new(order).unwrap());
+        export_data(&format!("Edge-Dunavant-{}", order), data);
+    }
+    for order in 1..=3 {
+        let data = edge_refinement_test_2d(Quadrilateral2DIntegrator::new(order).unwrap());
+        export_data(&format!("Edge-Quad-{}", order), data);
+    }
+}
+
+fn main() {
+    all_figures();
+    edge_subdivision_stats();
+}
@@ -0,0 +1,79 @@
+//! Hierarchic integration over a tetrahedron.
+//!
+//! The first run reads a fixed subdivision from a vec tree and writes the evaluation points to
+//! `out.csv`, the second one builds the tree adaptively.
+use ndarray::array;
+use std::fs::File;
+use std::io::Write;
+
+use simplex_subdivision_integration::integration_3d::{
+    functions::{Constant3DFunction, Function3DHistory, Multiplicative3DFunction},
+    integrators::{Hierarchic3DIntegrator, Hierarchic3DIntegratorData, Quadrilateral3DIntegrator},
+    Simplex3D, SimplexIntegrator,
+};
+
+fn integration_testing() {
+    let sim = Simplex3D::new_from_points(
+        &array![0., 0., 0.],
+        &array![1., 0., 0.],
+        &array![0., 1., 0.],
+        &array![0., 0., 1.],
+    );
+
+    let func = Box::new(Function3DHistory::new(Constant3DFunction {}));
+
+    let inte = Quadrilateral3DIntegrator::new(2).unwrap();
+    let hierarchic_inte = Hierarchic3DIntegrator::new(inte, true, 1e-5);
+    let inte = hierarchic_inte;
+
+    //let hierarchy = vec![0, 20, 1, 20,1,2,3,4,13,21, 2, 3, 4, 13,21];
+    let hierarchy = vec![
+        0, 20, 1, 2, 3, 4, 13, 20, 5, 6, 7, 8, 9, 10, 11, 12, 14, 15, 16, 17, 18, 19, 21, 21,
+    ];
+    let mut cache = Hierarchic3DIntegratorData::new_cache_from_vec_tree(&hierarchy).unwrap();
+
+    let result = inte.integrate_simplex(&func, &sim, &mut cache).unwrap();
+
+    let hist = func.get_history();
+
+    println!("{}", hist.len());
+    let mut file = File::create("out.csv").unwrap();
+    for el in &hist {
+        let sim_points = sim.get_points();
+        let point = sim_points.dot(el);
+        writeln!(file, "{} {} {}", point[0], point[1], point[2]).unwrap();
+    }
+
+    println!("{}", result.get());
+}
+
+fn integration_3d_testing() {
+    let sim = Simplex3D::new_from_points(
+        &array![0., 0., 0.],
+        &array![1., 0., 0.],
+        &array![0., 1., 0.],
+        &array![0., 0., 1.],
+    );
+
+    let func = Box::new(Function3DHistory::new(Multiplicative3DFunction {}));
+
+    let inte = Quadrilateral3DIntegrator::new(3).unwrap();
+    let hierarchic_inte = Hierarchic3DIntegrator::new(inte, false, 1e-5);
+    let inte = hierarchic_inte;
+
+    let mut cache = Hierarchic3DIntegratorData::new_cache();
+    inte.integrate_simplex(&func, &sim, &mut cache).unwrap();
+
+    func.delete_history();
+
+    let result = inte.integrate_simplex(&func, &sim, &mut cache).unwrap();
+
+    let hist = func.get_history();
+
+    println!("{},{}", hist.len(), result.get_borrow());
+}
+
+fn main() {
+    integration_testing();
+    integration_3d_testing();
+}
@@ -0,0 +1,64 @@
+//! Integrates the 6 x 6 phase field stiffness matrix of a quadratic triangle with the hierarchic
+//! integrator, reusing the tree of the diagonal for the remaining entries.
+mod common;
+
+use ndarray::{array, Array2};
+use std::time::Instant;
+
+use simplex_subdivision_integration::integration_2d::{
+    functions::Function2DHistory,
+    integrators::{DunavantIntegrator, Hierarchic2DIntegrator, Hierarchic2DIntegratorData},
+    Simplex2D, SimplexIntegrator,
+};
+use simplex_subdivision_integration::problems::problem_definition::problem_2d_definition::PhaseFieldFuncDiff22D;
+
+use common::get_diagonal_order;
+
+fn main() {
+    let sim = Simplex2D::new_from_points(
+        &array![1., 1.],
+        &array![2., 1.],
+        &array![1.5, 1. + (3.0f64).sqrt() / 2.],
+    );
+
+    let mut res = Array2::<f64>::zeros([6, 6]);
+    let nodal_values = array![1.0, 1.0, 1.0, -1.0, 0.0, 0.0];
+
+    let basic_integrator = DunavantIntegrator::new(2).unwrap();
+    let hierarchic_inte = Hierarchic2DIntegrator::new(basic_integrator, false, 1e-3);
+
+    //let hierarchic_inte = EdgeSubdivisionIntegrator::new(basic_integrator, 100);
+    let mut cache = Hierarchic2DIntegratorData::new_cache();
+
+    // Zuerst die Hauptdiagonale, dann die Nebendiagonalen
+    let res_vec = get_diagonal_order(5);
+
+    for (count, i, j) in res_vec {
+        let func = Box::new(Function2DHistory::new(PhaseFieldFuncDiff22D::new(
+            nodal_values.clone(),
+            1e-6,
+            1.,
+            i,
+            j,
+        )));
+        let now = Instant::now();
+        res[[i, j]] = hierarchic_inte
+            .integrate_simplex(&func, &sim, &mut cache)
+            .unwrap()
+            .get();
+        let elapsed_time = now.elapsed();
+        if count < 6 + 5 {
+            // Wenn Diagonale und erste nebendiagonale durch sind
+            cache.make_leafs_unchecked();
+        }
+        println!(
+            "Running [{},{}] took {} milliseconds. Integration Points are: {}",
+            i,
+            j,
+            elapsed_time.as_millis(),
+            func.function_evaluations()
+        );
+        func.delete_history();
+    }
+    println!("{}", res);
+}
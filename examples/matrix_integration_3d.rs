@@ -0,0 +1,41 @@
+//! Integrates the 10 x 10 phase field stiffness matrix of a quadratic tetrahedron with the
+//! hierarchic integrator in a single call, all entries share one tree.
+use ndarray::array;
+use std::time::Instant;
+
+use simplex_subdivision_integration::integration_3d::{
+    integrators::{Hierarchic3DIntegrator, Hierarchic3DIntegratorData, Quadrilateral3DIntegrator},
+    Simplex3D, SimplexIntegrator,
+};
+use simplex_subdivision_integration::problems::problem_definition::problem_3d_definition::PhaseFieldFuncMatrix3D;
+
+fn main() {
+    let sim = Simplex3D::new_from_points(
+        &array![(8.0f64 / 9.0).sqrt(), 0., -1.0 / 3.0],
+        &array![-(2.0f64 / 9.0).sqrt(), (2.0f64 / 3.0).sqrt(), -1.0 / 3.0],
+        &array![-(2.0f64 / 9.0).sqrt(), -(2.0f64 / 3.0).sqrt(), -1.0 / 3.0],
+        &array![0.0, 0.0, 1.0],
+    );
+
+    let nodal_values = array![1.0, 1.0, 1.0, -1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+
+    let basic_integrator = Quadrilateral3DIntegrator::new(1).unwrap();
+    let hierarchic_inte = Hierarchic3DIntegrator::new(basic_integrator, false, 1e-3);
+
+    let mut cache = Hierarchic3DIntegratorData::new_cache();
+
+    // Die ganze Matrix auf einmal, verfeinert wird nach der Frobeniusnorm
+    let func = Box::new(PhaseFieldFuncMatrix3D::new(nodal_values, 1e-6, 1.));
+    let now = Instant::now();
+    let res = hierarchic_inte
+        .integrate_simplex(&func, &sim, &mut cache)
+        .unwrap()
+        .get();
+    let elapsed_time = now.elapsed();
+    println!(
+        "Running the whole matrix took {} milliseconds. Tree size is: {}",
+        elapsed_time.as_millis(),
+        cache.tree_size()
+    );
+    println!("{}", res);
+}
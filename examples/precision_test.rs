@@ -0,0 +1,53 @@
+//! Number of function evaluations of the hierarchic 2D integrator depending on the precision.
+//!
+//! Prints `result,precision,evaluations while building the tree,evaluations on the built tree`.
+use ndarray::array;
+
+use simplex_subdivision_integration::integration_2d::{
+    functions::Function2DHistory,
+    integrators::{Hierarchic2DIntegrator, Hierarchic2DIntegratorData, Quadrilateral2DIntegrator},
+    Simplex2D, SimplexIntegrator,
+};
+use simplex_subdivision_integration::problems::PhaseField2DFunction;
+
+fn precision_test(precision: f64) {
+    let sim = Simplex2D::new_from_points(
+        &array![1., 1.],
+        &array![1.5, 1. + (3.0f64).sqrt() / 2.],
+        &array![2., 1.],
+    );
+    let inte1 = Quadrilateral2DIntegrator::new(1).unwrap();
+    let inte2 = Hierarchic2DIntegrator::new(inte1, false, precision);
+    //let inte1 = Quadrilateral2DIntegrator::new(3).unwrap();
+
+    let func = Box::new(Function2DHistory::new(PhaseField2DFunction {
+        weights: [10.0, 10.0, 10.0, 10.0, -0., -0.],
+    }));
+    //let func = Box::new(Function2DHistory::new(Constant2DFunction));
+
+    let mut cache = Hierarchic2DIntegratorData::new_cache();
+
+    //let result1 = inte1.integrate_simplex(&func, &sim, &mut IntegratorDummy::get()).unwrap();
+    let _result2 = inte2.integrate_simplex(&func, &sim, &mut cache).unwrap();
+
+    let evals_build = func.function_evaluations();
+    func.delete_history();
+
+    let result = inte2.integrate_simplex(&func, &sim, &mut cache).unwrap();
+
+    let hist = func.get_history();
+
+    println!(
+        "{},{},{},{}",
+        result.get(),
+        precision,
+        evals_build,
+        hist.len()
+    );
+}
+
+fn main() {
+    for i in 1..=7 {
+        precision_test(10.0f64.powi(-i));
+    }
+}
@@ -0,0 +1,38 @@
+//! Number of sub-simplices of a regular subdivision of a triangle, counted by inclusion-exclusion.
+
+fn binomial(n: u64, k: u64) -> u64 {
+    let mut res = 1;
+    for i in 0..k {
+        res = (res * (n - i)) / (i + 1);
+    }
+    res
+}
+
+fn num_simplizes(highest_dim: u64, order: u64, dim_simplex: u64) -> i64 {
+    let mut result = 0;
+    for i in 0..=dim_simplex {
+        let c = binomial(highest_dim + (dim_simplex + 1 - i) * order, highest_dim);
+        if i % 2 == 0 {
+            result += (binomial(dim_simplex, i) * c) as i64
+        } else {
+            result -= (binomial(dim_simplex, i) * c) as i64
+        };
+    }
+    return result;
+}
+
+fn main() {
+    let dim = 2;
+    for i in 0..10 {
+        println!(
+            "3-Simplex in {}th Subdivision: {}",
+            i,
+            num_simplizes(dim, i, 2)
+        );
+        println!(
+            "1-Simplex in {}th Subdivision: {}",
+            i,
+            num_simplizes(dim, i, 0)
+        )
+    }
+}
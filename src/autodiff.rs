@@ -0,0 +1,176 @@
+//! Integrands written generically over [`DualNum`].
+//!
+//! A [`DualFunction`] is an energy density of the `N` nodal values of an element. Wrapped into
+//! [`Energy`], [`Residual`] or [`Tangent`] it becomes a [`SimplexFunction`], whose values are the
+//! density, its gradient or its Hessian with respect to the nodal values. The derivatives are
+//! computed exactly with the dual numbers of `num-dual`, no derivative has to be written by hand.
+
+use ndarray::{Array1, Array2};
+pub use num_dual::DualNum;
+use num_dual::{Dual2Vec64, DualVec64, StaticVec};
+
+use crate::common::MaybeSync;
+use crate::simplex::{ResultTypeWrapper, Simplex, SimplexFunction};
+
+/// An energy density over a simplex of dimension `D`, which depends on `N` nodal values.
+pub trait DualFunction<const D: usize, const N: usize>: MaybeSync {
+    /// The nodal values at which the derivatives are evaluated.
+    fn nodal_values(&self) -> [f64; N];
+
+    /// The density at the barycentric coordinates `xi` for the (dual) `nodal_values`.
+    fn density<T: DualNum<f64>>(
+        &self,
+        nodal_values: &[T; N],
+        xi: &Array1<f64>,
+        simplex: &Simplex<D>,
+    ) -> T;
+}
+
+/// Integrates the density itself.
+pub struct Energy<F, const N: usize>(F);
+
+/// Integrates the gradient of the density, a vector of length `N`.
+pub struct Residual<F, const N: usize>(F);
+
+/// Integrates the Hessian of the density, a symmetric `N x N` matrix.
+pub struct Tangent<F, const N: usize>(F);
+
+impl<F, const N: usize> Energy<F, N> {
+    pub fn new<const D: usize>(func: F) -> Self
+    where
+        F: DualFunction<D, N>,
+    {
+        Self(func)
+    }
+}
+
+impl<F, const N: usize> Residual<F, N> {
+    pub fn new<const D: usize>(func: F) -> Self
+    where
+        F: DualFunction<D, N>,
+    {
+        Self(func)
+    }
+}
+
+impl<F, const N: usize> Tangent<F, N> {
+    pub fn new<const D: usize>(func: F) -> Self
+    where
+        F: DualFunction<D, N>,
+    {
+        Self(func)
+    }
+}
+
+impl<const D: usize, const N: usize, F: DualFunction<D, N>> SimplexFunction<D> for Energy<F, N> {
+    type Return = ResultTypeWrapper<f64>;
+
+    fn function_vec(&self, xi: &Array1<f64>, simplex: &Simplex<D>) -> Self::Return {
+        ResultTypeWrapper::new(self.0.density(&self.0.nodal_values(), xi, simplex))
+    }
+}
+
+impl<const D: usize, const N: usize, F: DualFunction<D, N>> SimplexFunction<D> for Residual<F, N> {
+    type Return = ResultTypeWrapper<Array1<f64>>;
+
+    fn function_vec(&self, xi: &Array1<f64>, simplex: &Simplex<D>) -> Self::Return {
+        let values = StaticVec::new_vec(self.0.nodal_values())
+            .map(DualVec64::<N>::from_re)
+            .derive();
+        let density = self.0.density(values.raw_array(), xi, simplex);
+        ResultTypeWrapper::new(Array1::from_iter(density.eps.raw_array().iter().copied()))
+    }
+
+    fn additive_neutral_element(&self) -> Self::Return {
+        ResultTypeWrapper::new(Array1::zeros([N]))
+    }
+}
+
+impl<const D: usize, const N: usize, F: DualFunction<D, N>> SimplexFunction<D> for Tangent<F, N> {
+    type Return = ResultTypeWrapper<Array2<f64>>;
+
+    fn function_vec(&self, xi: &Array1<f64>, simplex: &Simplex<D>) -> Self::Return {
+        let values = StaticVec::new_vec(self.0.nodal_values())
+            .map(Dual2Vec64::<N>::from_re)
+            .derive();
+        let density = self.0.density(values.raw_array(), xi, simplex);
+        ResultTypeWrapper::new(Array2::from_shape_fn([N, N], |(i, j)| density.v2[(i, j)]))
+    }
+
+    fn additive_neutral_element(&self) -> Self::Return {
+        ResultTypeWrapper::new(Array2::zeros([N, N]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{array, Array1};
+
+    use super::{DualFunction, DualNum, Energy, Residual, Tangent};
+    use crate::common::IntegratorDummy;
+    use crate::integration_2d::domain::{Simplex2D, SimplexIntegrator};
+    use crate::integration_2d::integrators::DunavantIntegrator;
+
+    /// (u1 xi1 + u2 xi2 + u3 xi3)^3, die lineare Interpolation hoch drei
+    struct Cubic([f64; 3]);
+
+    impl DualFunction<2, 3> for Cubic {
+        fn nodal_values(&self) -> [f64; 3] {
+            self.0
+        }
+
+        fn density<T: DualNum<f64>>(
+            &self,
+            nodal_values: &[T; 3],
+            xi: &Array1<f64>,
+            _simplex: &Simplex2D,
+        ) -> T {
+            let u: T = (0..3).map(|i| nodal_values[i] * xi[i]).sum();
+            u.powi(3)
+        }
+    }
+
+    #[test]
+    fn derivatives_of_a_cubic() {
+        let sim = Simplex2D::new_from_points(&array![0., 0.], &array![1., 0.], &array![0., 1.]);
+        let inte = DunavantIntegrator::new(3).unwrap();
+        // Für u = c konstant: Energie c^3 / 2, Residuum 3 c^2 / 6, Tangente 6 c (1 + δij) / 24
+        let c = 2.;
+        let energy = inte
+            .integrate_simplex(
+                &Box::new(Energy::new(Cubic([c; 3]))),
+                &sim,
+                &mut IntegratorDummy::get(),
+            )
+            .unwrap()
+            .get();
+        assert!((energy - c.powi(3) / 2.).abs() < 1e-13);
+
+        let residual = inte
+            .integrate_simplex(
+                &Box::new(Residual::new(Cubic([c; 3]))),
+                &sim,
+                &mut IntegratorDummy::get(),
+            )
+            .unwrap()
+            .get();
+        for value in residual.iter() {
+            assert!((value - 3. * c.powi(2) / 6.).abs() < 1e-13);
+        }
+
+        let tangent = inte
+            .integrate_simplex(
+                &Box::new(Tangent::new(Cubic([c; 3]))),
+                &sim,
+                &mut IntegratorDummy::get(),
+            )
+            .unwrap()
+            .get();
+        for i in 0..3 {
+            for j in 0..3 {
+                let delta = if i == j { 1. } else { 0. };
+                assert!((tangent[[i, j]] - 6. * c * (1. + delta) / 24.).abs() < 1e-13);
+            }
+        }
+    }
+}
@@ -0,0 +1,429 @@
+//! Adaptive edge bisection inside a single simplex.
+//!
+//! The [`BisectionIntegrator`] splits only the subsimplices whose error estimate is too large,
+//! each into two halves at its longest edge, which gives a graded mesh along a layer, e.g. the
+//! crack of a phase field. The estimate compares a subsimplex with its two halves and with a check
+//! integrator, which has to be a rule of higher degree than the base integrator.
+//! The [`EmbeddedBisectionIntegrator`] takes the estimate from an [`EmbeddedIntegrator`] instead.
+
+use ndarray::Array2;
+
+use crate::common::{ordered_map, IntegratorDummy};
+use crate::embedded::{EmbeddedIntegrator, EmbeddedResult};
+use crate::error::{check_shape, Result};
+use crate::refinement::{AbsoluteCriterion, RefinementCriterion};
+use crate::simplex::{Simplex, SimplexFunction, SimplexIntegrator, SimplexResultType};
+
+/// Subsimplices of this level are not bisected any further, even if the criterion asks for it.
+pub const MAX_BISECTION_LEVEL: usize = 50;
+
+/// Bisects the subsimplices at the midpoint of their longest edge, measured in physical
+/// coordinates, as long as the [`RefinementCriterion`] `R` asks for it.
+///
+/// The error estimate is the larger distance between the integral over a subsimplex and either
+/// the sum over its two halves at the longest edge or the integral of the check integrator, a rule
+/// of higher degree, over the subsimplex. The halves alone are not enough, as a function which does
+/// not change along the longest edge has the same integral over both.
+/// Both halves keep the orientation, so the integral is signed like the one of the base integrator.
+pub struct BisectionIntegrator<I, R: RefinementCriterion = AbsoluteCriterion> {
+    base_integrator: I,
+    check_integrator: I,
+    criterion: R,
+}
+
+impl<I> BisectionIntegrator<I> {
+    /// Bisects a subsimplex if the distance to its halves or to the `check_integrator` is larger
+    /// than `precision`.
+    pub fn new(base_integrator: I, check_integrator: I, precision: f64) -> Self {
+        Self::new_with_criterion(
+            base_integrator,
+            check_integrator,
+            AbsoluteCriterion::new(precision),
+        )
+    }
+}
+
+impl<I, R: RefinementCriterion> BisectionIntegrator<I, R> {
+    pub fn new_with_criterion(base_integrator: I, check_integrator: I, criterion: R) -> Self {
+        Self {
+            base_integrator,
+            check_integrator,
+            criterion,
+        }
+    }
+}
+
+/// Bisects like the [`BisectionIntegrator`], but the estimate of a subsimplex is the distance
+/// between the base integrator and its embedded rule. It needs no further function evaluations,
+/// the halves are only integrated if the subsimplex is bisected.
+pub struct EmbeddedBisectionIntegrator<I, R: RefinementCriterion = AbsoluteCriterion> {
+    base_integrator: I,
+    criterion: R,
+}
+
+impl<I> EmbeddedBisectionIntegrator<I> {
+    /// Bisects a subsimplex if the distance to the embedded rule is larger than `precision`.
+    pub fn new(base_integrator: I, precision: f64) -> Self {
+        Self::new_with_criterion(base_integrator, AbsoluteCriterion::new(precision))
+    }
+}
+
+impl<I, R: RefinementCriterion> EmbeddedBisectionIntegrator<I, R> {
+    pub fn new_with_criterion(base_integrator: I, criterion: R) -> Self {
+        Self {
+            base_integrator,
+            criterion,
+        }
+    }
+}
+
+/// A subsimplex of the bisection, the columns of the transformation are its vertices.
+struct Leaf<T> {
+    transformation: Array2<f64>,
+    level: usize,
+    result: T,
+    checked: bool,
+}
+
+/// The two halves of a subsimplex at its longest edge, with their integrals, and the integral of
+/// the check integrator over the subsimplex.
+struct Bisection<T> {
+    halves: [(Array2<f64>, T); 2],
+    check: T,
+}
+
+impl<T: SimplexResultType> Bisection<T> {
+    /// The integral over both halves, starting from the `neutral` element.
+    fn sum(&self, mut neutral: T) -> T {
+        for (_, result) in self.halves.iter() {
+            SimplexResultType::add_assign(&mut neutral, result);
+        }
+        neutral
+    }
+}
+
+/// The two halves of the subdomain with the transformation `trans`, split at the midpoint of the
+/// edge between the vertices `edge`. Replacing one vertex by the midpoint keeps the orientation.
+fn bisect(trans: &Array2<f64>, edge: (usize, usize)) -> [Array2<f64>; 2] {
+    let midpoint = (&trans.column(edge.0) + &trans.column(edge.1)) / 2.;
+    let mut first = trans.clone();
+    first.column_mut(edge.1).assign(&midpoint);
+    let mut second = trans.clone();
+    second.column_mut(edge.0).assign(&midpoint);
+    [first, second]
+}
+
+/// The vertices of the longest edge of the subdomain with the transformation `trans`, measured in
+/// physical coordinates.
+fn longest_edge<const D: usize>(simplex: &Simplex<D>, trans: &Array2<f64>) -> (usize, usize) {
+    let points = simplex.get_points_borrow().dot(trans);
+    let mut longest = ((0, 1), f64::NEG_INFINITY);
+    for j in 0..=D {
+        for k in 0..j {
+            let length = (&points.column(j) - &points.column(k))
+                .mapv(|x| x.powi(2))
+                .sum();
+            if length > longest.1 {
+                longest = ((k, j), length);
+            }
+        }
+    }
+    longest.0
+}
+
+impl<const D: usize, I: SimplexIntegrator<D, IntegratorDummy>, R: RefinementCriterion>
+    SimplexIntegrator<D, IntegratorDummy> for BisectionIntegrator<I, R>
+{
+    fn dupe(&self) -> Self {
+        Self {
+            base_integrator: self.base_integrator.dupe(),
+            check_integrator: self.check_integrator.dupe(),
+            criterion: self.criterion.clone(),
+        }
+    }
+
+    fn integrate_over_domain<T: SimplexFunction<D>>(
+        &self,
+        transformation: &Array2<f64>,
+        func: &Box<T>,
+        simplex: &Simplex<D>,
+        _cached_data: &mut IntegratorDummy,
+    ) -> Result<T::Return> {
+        check_shape(transformation, [D + 1, D + 1])?;
+        let integrate = |trans: &Array2<f64>| {
+            self.base_integrator
+                .integrate_over_domain(trans, func, simplex, &mut IntegratorDummy)
+        };
+        let mut leafs = vec![Leaf {
+            transformation: transformation.clone(),
+            level: 0,
+            result: integrate(transformation)?,
+            checked: false,
+        }];
+
+        loop {
+            // Die ungeprüften Blätter werden zur Schätzung an der längsten Kante halbiert und mit
+            // der höheren Regel integriert
+            let work: Vec<&Array2<f64>> = leafs
+                .iter()
+                .filter(|leaf| !leaf.checked && leaf.level < MAX_BISECTION_LEVEL)
+                .map(|leaf| &leaf.transformation)
+                .collect();
+            if work.is_empty() {
+                break;
+            }
+            let mut bisections = ordered_map(work, |trans| -> Result<_> {
+                let [first, second] = bisect(trans, longest_edge(simplex, trans));
+                let first_result = integrate(&first)?;
+                let second_result = integrate(&second)?;
+                let check = self.check_integrator.integrate_over_domain(
+                    trans,
+                    func,
+                    simplex,
+                    &mut IntegratorDummy,
+                )?;
+                Ok(Bisection {
+                    halves: [(first, first_result), (second, second_result)],
+                    check,
+                })
+            })
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?
+            .into_iter();
+
+            // Die momentane Schätzung des Integrals für die relativen Kriterien
+            let mut total = func.additive_neutral_element();
+            for leaf in leafs.iter() {
+                total.add_assign(&leaf.result);
+            }
+
+            // Die Blätter werden in ihrer Reihenfolge durch ihre Hälften ersetzt
+            let mut next_leafs = Vec::with_capacity(2 * leafs.len());
+            for mut leaf in leafs {
+                if leaf.checked || leaf.level >= MAX_BISECTION_LEVEL {
+                    next_leafs.push(leaf);
+                    continue;
+                }
+                let bisection = bisections.next().unwrap();
+                // Die Schätzung ist der größere Unterschied, zu den Hälften oder zur höheren Regel
+                let halves_sum = bisection.sum(func.additive_neutral_element());
+                let fine =
+                    if leaf.result.distance(&bisection.check) > leaf.result.distance(&halves_sum) {
+                        &bisection.check
+                    } else {
+                        &halves_sum
+                    };
+                if self.criterion.needs_refinement(&leaf.result, fine, &total) {
+                    for (transformation, result) in bisection.halves {
+                        next_leafs.push(Leaf {
+                            transformation,
+                            level: leaf.level + 1,
+                            result,
+                            checked: false,
+                        });
+                    }
+                } else {
+                    leaf.checked = true;
+                    next_leafs.push(leaf);
+                }
+            }
+            leafs = next_leafs;
+        }
+
+        let mut result = func.additive_neutral_element();
+        for leaf in leafs {
+            result.add_assign(&leaf.result);
+        }
+        Ok(result)
+    }
+}
+
+impl<const D: usize, I: EmbeddedIntegrator<D>, R: RefinementCriterion>
+    SimplexIntegrator<D, IntegratorDummy> for EmbeddedBisectionIntegrator<I, R>
+{
+    fn dupe(&self) -> Self {
+        Self {
+            base_integrator: self.base_integrator.dupe(),
+            criterion: self.criterion.clone(),
+        }
+    }
+
+    fn integrate_over_domain<T: SimplexFunction<D>>(
+        &self,
+        transformation: &Array2<f64>,
+        func: &Box<T>,
+        simplex: &Simplex<D>,
+        _cached_data: &mut IntegratorDummy,
+    ) -> Result<T::Return> {
+        check_shape(transformation, [D + 1, D + 1])?;
+        let integrate = |trans: &Array2<f64>| {
+            self.base_integrator
+                .integrate_embedded(trans, func, simplex)
+        };
+        let mut leafs: Vec<Leaf<EmbeddedResult<T::Return>>> = vec![Leaf {
+            transformation: transformation.clone(),
+            level: 0,
+            result: integrate(transformation)?,
+            checked: false,
+        }];
+
+        loop {
+            // Die momentane Schätzung des Integrals für die relativen Kriterien
+            let mut total = func.additive_neutral_element();
+            for leaf in leafs.iter() {
+                total.add_assign(&leaf.result.result);
+            }
+
+            // Die Schätzung braucht keine Kinder, nur die zu ungenauen Blätter werden halbiert
+            let mut bisected = Vec::with_capacity(leafs.len());
+            let mut halves = Vec::new();
+            for leaf in leafs.iter_mut() {
+                let refine = !leaf.checked
+                    && leaf.level < MAX_BISECTION_LEVEL
+                    && self.criterion.needs_refinement(
+                        &leaf.result.embedded,
+                        &leaf.result.result,
+                        &total,
+                    );
+                if refine {
+                    let edge = longest_edge(simplex, &leaf.transformation);
+                    halves.extend(bisect(&leaf.transformation, edge));
+                } else {
+                    leaf.checked = true;
+                }
+                bisected.push(refine);
+            }
+            if halves.is_empty() {
+                break;
+            }
+            let mut results = ordered_map(halves, |trans| -> Result<_> {
+                let result = integrate(&trans)?;
+                Ok((trans, result))
+            })
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?
+            .into_iter();
+
+            // Die Blätter werden in ihrer Reihenfolge durch ihre Hälften ersetzt
+            let mut next_leafs = Vec::with_capacity(leafs.len() + results.len());
+            for (leaf, refine) in leafs.into_iter().zip(bisected) {
+                if !refine {
+                    next_leafs.push(leaf);
+                    continue;
+                }
+                for (transformation, result) in results.by_ref().take(2) {
+                    next_leafs.push(Leaf {
+                        transformation,
+                        level: leaf.level + 1,
+                        result,
+                        checked: false,
+                    });
+                }
+            }
+            leafs = next_leafs;
+        }
+
+        let mut result = func.additive_neutral_element();
+        for leaf in leafs {
+            result.add_assign(&leaf.result.result);
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BisectionIntegrator, EmbeddedBisectionIntegrator};
+    use crate::common::IntegratorDummy;
+    use crate::embedded::GrundmannMoellerIntegrator;
+    use crate::integration_2d::integrators::{DunavantIntegrator, Quadrilateral2DIntegrator};
+    use crate::integration_3d::integrators::TetrahedralIntegrator;
+    use crate::{integrator_tests, integrator_tests_3d};
+
+    integrator_tests! {
+        dunavant: BisectionIntegrator<DunavantIntegrator>: BisectionIntegrator::new(DunavantIntegrator::new(2).unwrap(),DunavantIntegrator::new(4).unwrap(),1e-3), IntegratorDummy: IntegratorDummy::get(),
+        quadrilaterial: BisectionIntegrator<Quadrilateral2DIntegrator>: BisectionIntegrator::new(Quadrilateral2DIntegrator::new(1).unwrap(),Quadrilateral2DIntegrator::new(2).unwrap(),1e-3), IntegratorDummy: IntegratorDummy::get(),
+        embedded: EmbeddedBisectionIntegrator<GrundmannMoellerIntegrator<2>>: EmbeddedBisectionIntegrator::new(GrundmannMoellerIntegrator::new(3).unwrap(),1e-3), IntegratorDummy: IntegratorDummy::get(),
+    }
+
+    integrator_tests_3d! {
+        tetrahedral: BisectionIntegrator<TetrahedralIntegrator>: BisectionIntegrator::new(TetrahedralIntegrator::new(2).unwrap(),TetrahedralIntegrator::new(4).unwrap(),1e-3), IntegratorDummy: IntegratorDummy::get(),
+        embedded_3d: EmbeddedBisectionIntegrator<GrundmannMoellerIntegrator<3>>: EmbeddedBisectionIntegrator::new(GrundmannMoellerIntegrator::new(3).unwrap(),1e-3), IntegratorDummy: IntegratorDummy::get(),
+    }
+
+    #[test]
+    fn graded_along_a_layer() {
+        use ndarray::array;
+
+        use crate::integration_2d::domain::{Simplex2D, SimplexIntegrator};
+
+        let sim = Simplex2D::new_from_points(&array![0., 0.], &array![1., 0.], &array![0., 1.]);
+        // Eine steile Schicht entlang x = a der Breite e, ∫ über das Dreieck = ∫ g(x) (1 - x) dx
+        let (a, e): (f64, f64) = (0.3, 0.01);
+        let atan = |x: f64| ((x - a) / e).atan();
+        let ln = |x: f64| (1. + ((x - a) / e).powi(2)).ln();
+        let exact = (1. - a) * e * (atan(1.) - atan(0.)) - e.powi(2) / 2. * (ln(1.) - ln(0.));
+        let func = Box::new(move |xi1: f64, xi2: f64, xi3: f64, simplex: &Simplex2D| {
+            let x = simplex.get_physical_point(&array![xi1, xi2, xi3])[0];
+            1. / (1. + ((x - a) / e).powi(2))
+        });
+
+        let dunavant = |order| DunavantIntegrator::new(order).unwrap();
+        let result = BisectionIntegrator::new(dunavant(2), dunavant(4), 1e-9)
+            .integrate_simplex(&func, &sim, &mut IntegratorDummy::get())
+            .unwrap()
+            .get();
+        assert!((result - exact).abs() < 1e-6, "{} {}", result, exact);
+
+        // Ohne die höhere Regel wäre der Fehler der Teildreiecke parallel zur Schicht null, das
+        // Integral wäre um fast die Hälfte zu klein.
+        let coarse = BisectionIntegrator::new(dunavant(2), dunavant(4), 1e-4)
+            .integrate_simplex(&func, &sim, &mut IntegratorDummy::get())
+            .unwrap()
+            .get();
+        assert!((coarse - exact).abs() < 1e-3, "{} {}", coarse, exact);
+    }
+
+    #[test]
+    fn embedded_estimate_along_a_layer() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use ndarray::array;
+
+        use crate::integration_2d::domain::{Simplex2D, SimplexIntegrator};
+
+        let sim = Simplex2D::new_from_points(&array![0., 0.], &array![1., 0.], &array![0., 1.]);
+        let (a, e): (f64, f64) = (0.3, 0.05);
+        let atan = |x: f64| ((x - a) / e).atan();
+        let ln = |x: f64| (1. + ((x - a) / e).powi(2)).ln();
+        let exact = (1. - a) * e * (atan(1.) - atan(0.)) - e.powi(2) / 2. * (ln(1.) - ln(0.));
+        let evaluations = AtomicUsize::new(0);
+        let func = Box::new(|xi1: f64, xi2: f64, xi3: f64, simplex: &Simplex2D| {
+            evaluations.fetch_add(1, Ordering::Relaxed);
+            let x = simplex.get_physical_point(&array![xi1, xi2, xi3])[0];
+            1. / (1. + ((x - a) / e).powi(2))
+        });
+
+        let result =
+            EmbeddedBisectionIntegrator::new(GrundmannMoellerIntegrator::new(5).unwrap(), 1e-8)
+                .integrate_simplex(&func, &sim, &mut IntegratorDummy::get())
+                .unwrap()
+                .get();
+        assert!((result - exact).abs() < 1e-6, "{} {}", result, exact);
+        let embedded = evaluations.swap(0, Ordering::Relaxed);
+
+        // Je geprüftem Teildreieck werden die beiden Hälften und die höhere Regel ausgewertet
+        let gm = |order| GrundmannMoellerIntegrator::<2>::new(order).unwrap();
+        let (base, check) = (gm(5).get_num_points(), gm(7).get_num_points());
+        let result = BisectionIntegrator::new(gm(5), gm(7), 1e-8)
+            .integrate_simplex(&func, &sim, &mut IntegratorDummy::get())
+            .unwrap()
+            .get();
+        assert!((result - exact).abs() < 1e-6, "{} {}", result, exact);
+        let bisection = evaluations.load(Ordering::Relaxed);
+        assert_eq!((bisection - base) % (2 * base + check), 0);
+        // Jedes Blatt und jedes halbierte Teildreieck wurde genau einmal geprüft
+        assert_eq!((bisection - base) / (2 * base + check) % 2, 1);
+        assert!(embedded < bisection);
+    }
+}
@@ -1,5 +1,11 @@
-use ndarray::Array2;
+use linfa_linalg::eigh::EighInto;
+use ndarray::{Array1, Array2};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
+use crate::error::{check_shape, IntegrationError, Result};
+
+#[derive(Debug)]
 pub struct IntegratorDummy;
 
 impl IntegratorDummy {
@@ -8,6 +14,87 @@ impl IntegratorDummy {
     }
 }
 
+/// `Send` with the feature `parallel`, otherwise implemented by every type.
+#[cfg(feature = "parallel")]
+pub trait MaybeSend: Send {}
+#[cfg(feature = "parallel")]
+impl<T: Send> MaybeSend for T {}
+
+/// `Send` with the feature `parallel`, otherwise implemented by every type.
+#[cfg(not(feature = "parallel"))]
+pub trait MaybeSend {}
+#[cfg(not(feature = "parallel"))]
+impl<T> MaybeSend for T {}
+
+/// `Sync` with the feature `parallel`, otherwise implemented by every type.
+#[cfg(feature = "parallel")]
+pub trait MaybeSync: Sync {}
+#[cfg(feature = "parallel")]
+impl<T: Sync> MaybeSync for T {}
+
+/// `Sync` with the feature `parallel`, otherwise implemented by every type.
+#[cfg(not(feature = "parallel"))]
+pub trait MaybeSync {}
+#[cfg(not(feature = "parallel"))]
+impl<T> MaybeSync for T {}
+
+/// Applies `func` to all items and returns the results in the order of the items.
+/// With the feature `parallel` the items are distributed over the rayon thread pool.
+/// The caller sums the results up in order, so both paths give bit-identical results.
+pub(crate) fn ordered_map<A, R, F>(items: Vec<A>, func: F) -> Vec<R>
+where
+    A: MaybeSend,
+    R: MaybeSend,
+    F: Fn(A) -> R + MaybeSync + MaybeSend,
+{
+    #[cfg(feature = "parallel")]
+    {
+        items.into_par_iter().map(func).collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        items.into_iter().map(func).collect()
+    }
+}
+
+/// Applies `func` to the indices `0..len` and returns the results in the order of the indices,
+/// for the small independent parts of a single integrand, e.g. the rows of a matrix.
+/// With the feature `parallel` the range is halved with `rayon::join`, the halves only run on
+/// another thread if one of the pool is idle, so the leafs around it are not oversubscribed.
+pub(crate) fn ordered_join<R, F>(len: usize, func: F) -> Vec<R>
+where
+    R: MaybeSend,
+    F: Fn(usize) -> R + MaybeSync,
+{
+    #[cfg(feature = "parallel")]
+    {
+        fn split<R: Send, F: Fn(usize) -> R + Sync>(
+            range: std::ops::Range<usize>,
+            func: &F,
+        ) -> Vec<R> {
+            if range.len() <= 1 {
+                return range.map(func).collect();
+            }
+            let middle = range.start + range.len() / 2;
+            let (mut first, second) = rayon::join(
+                || split(range.start..middle, func),
+                || split(middle..range.end, func),
+            );
+            first.extend(second);
+            first
+        }
+        split(0..len, &func)
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        (0..len).map(func).collect()
+    }
+}
+
+pub fn det2x2(mat2x2: &Array2<f64>) -> f64 {
+    mat2x2[[0, 0]] * mat2x2[[1, 1]] - mat2x2[[0, 1]] * mat2x2[[1, 0]]
+}
+
 pub fn det3x3(mat3x3: &Array2<f64>) -> f64 {
     let mut sum = 0.0;
     for j in 0..3 {
@@ -45,3 +132,162 @@ pub fn det4x4(mat4x4: &Array2<f64>) -> f64 {
     }
     return sum;
 }
+
+/// Determinant of a square matrix of arbitrary dimension.
+/// Up to 4 x 4 the explicit formulas are used, above by gaussian elimination with partial pivoting.
+/// A matrix which is not square is a [`IntegrationError::DimensionMismatch`].
+pub(crate) fn determinant(mat: &Array2<f64>) -> Result<f64> {
+    let n = mat.nrows();
+    check_shape(mat, [n, n])?;
+    Ok(match n {
+        0 => 1.,
+        1 => mat[[0, 0]],
+        2 => det2x2(mat),
+        3 => det3x3(mat),
+        4 => det4x4(mat),
+        _ => {
+            let mut mat = mat.clone();
+            let mut det = 1.;
+            for i in 0..n {
+                let pivot = (i..n)
+                    .max_by(|&a, &b| mat[[a, i]].abs().total_cmp(&mat[[b, i]].abs()))
+                    .unwrap();
+                if mat[[pivot, i]] == 0. {
+                    return Ok(0.);
+                }
+                if pivot != i {
+                    for k in 0..n {
+                        mat.swap([i, k], [pivot, k]);
+                    }
+                    det = -det;
+                }
+                det *= mat[[i, i]];
+                for j in (i + 1)..n {
+                    let factor = mat[[j, i]] / mat[[i, i]];
+                    for k in i..n {
+                        mat[[j, k]] -= factor * mat[[i, k]];
+                    }
+                }
+            }
+            det
+        }
+    })
+}
+
+/// Gauss-Legendre points and weights on [-1,1] with `degree` points, determined by the
+/// Golub-Welsch Algorithm: The points are the eigenvalues of the symmetric Jacobi matrix of the
+/// Legendre recurrence, the weights follow from the first components of the eigenvectors.
+/// The points are sorted in ascending order. At least one point is needed.
+pub fn gauss_legendre_golub_welsch(degree: usize) -> Result<(Array1<f64>, Array1<f64>)> {
+    if degree == 0 {
+        return Err(IntegrationError::UnsupportedOrder {
+            rule: "Gauss-Legendre",
+            order: degree,
+        });
+    }
+    let mut jacobi = Array2::<f64>::zeros([degree, degree]);
+    for k in 1..degree {
+        let k_fl = k as f64;
+        let beta = k_fl / (4. * k_fl.powi(2) - 1.).sqrt();
+        jacobi[[k - 1, k]] = beta;
+        jacobi[[k, k - 1]] = beta;
+    }
+    let (eigenvalues, eigenvectors) = jacobi.eigh_into().map_err(|error| {
+        IntegrationError::NumericalFailure(format!(
+            "the eigenproblem of the Jacobi matrix failed: {}",
+            error
+        ))
+    })?;
+
+    let mut order: Vec<usize> = (0..degree).collect();
+    order.sort_by(|&a, &b| eigenvalues[a].total_cmp(&eigenvalues[b]));
+
+    let points = order.iter().map(|&i| eigenvalues[i]).collect();
+    // Das Integral der Gewichtsfunktion über [-1,1] ist 2
+    let weights = order
+        .iter()
+        .map(|&i| 2. * eigenvectors[[0, i]].powi(2))
+        .collect();
+    Ok((points, weights))
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{array, Array2};
+
+    use super::{det4x4, determinant, gauss_legendre_golub_welsch};
+    use crate::error::IntegrationError;
+
+    #[test]
+    fn determinant_by_elimination() {
+        let mat = array![
+            [2., 1., 0., 3., 1.],
+            [1., 4., 1., 0., 2.],
+            [0., 1., 5., 1., 0.],
+            [3., 0., 1., 6., 1.],
+            [1., 2., 0., 1., 7.]
+        ];
+        // Entwicklung nach der ersten Zeile
+        let mut expected = 0.;
+        for j in 0..5 {
+            let mut minor = Array2::<f64>::zeros([4, 4]);
+            for r in 1..5 {
+                let mut c = 0;
+                for k in 0..5 {
+                    if k != j {
+                        minor[[r - 1, c]] = mat[[r, k]];
+                        c += 1;
+                    }
+                }
+            }
+            expected += (-1.0_f64).powi(j as i32) * mat[[0, j]] * det4x4(&minor);
+        }
+        assert!((determinant(&mat).unwrap() - expected).abs() < 1e-10 * expected.abs());
+        assert!(matches!(
+            determinant(&Array2::zeros([3, 4])),
+            Err(IntegrationError::DimensionMismatch {
+                expected: [3, 3],
+                found: [3, 4]
+            })
+        ));
+    }
+
+    #[test]
+    fn gauss_legendre_known_points() {
+        let (points, weights) = gauss_legendre_golub_welsch(3).unwrap();
+        let true_points = [-(3. / 5.0_f64).sqrt(), 0., (3. / 5.0_f64).sqrt()];
+        let true_weights = [5. / 9., 8. / 9., 5. / 9.];
+        for i in 0..3 {
+            assert!((points[i] - true_points[i]).abs() < 1e-14);
+            assert!((weights[i] - true_weights[i]).abs() < 1e-14);
+        }
+        assert!(gauss_legendre_golub_welsch(0).is_err());
+    }
+
+    #[test]
+    fn gauss_legendre_polynomial_exactness() {
+        for degree in 1..=30 {
+            let (points, weights) = gauss_legendre_golub_welsch(degree).unwrap();
+            for power in 0..2 * degree as i32 {
+                let result: f64 = points
+                    .iter()
+                    .zip(weights.iter())
+                    .map(|(x, w)| w * x.powi(power))
+                    .sum();
+                let exact = if power % 2 == 0 {
+                    2. / (power as f64 + 1.)
+                } else {
+                    0.
+                };
+                assert!(
+                    (result - exact).abs() < 1e-13,
+                    "Degree {}: x^{}, Expected: {}, Actual: {}",
+                    degree,
+                    power,
+                    exact,
+                    result
+                );
+            }
+        }
+    }
+}
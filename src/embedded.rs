@@ -0,0 +1,315 @@
+//! Quadrature rules with an embedded rule of lower degree.
+//!
+//! An [`EmbeddedIntegrator`] returns the integral of both rules from the same function
+//! evaluations, their distance estimates the error of a subdomain without integrating its
+//! children. The [`EmbeddedBisectionIntegrator`](crate::bisection::EmbeddedBisectionIntegrator)
+//! and the hierarchic integrators created with `new_embedded()`, e.g.
+//! [`Hierarchic2DIntegrator::new_embedded()`](crate::integration_2d::integrators::Hierarchic2DIntegrator::new_embedded),
+//! refine with this estimate.
+
+use ndarray::{Array1, Array2};
+
+use crate::common::{determinant, IntegratorDummy};
+use crate::error::{check_shape, IntegrationError, Result};
+use crate::simplex::{Simplex, SimplexFunction, SimplexIntegrator, SimplexResultType};
+
+/// The integrals of a rule and of its embedded rule over the same subdomain.
+pub struct EmbeddedResult<T> {
+    pub result: T,
+    pub embedded: T,
+}
+
+/// A base integrator whose points contain the points of a rule of lower degree.
+/// [`SimplexIntegrator::integrate_over_domain()`] returns the integral of the higher rule,
+/// [`SimplexIntegrator::integrate_over_domain_embedded()`] the same as [`Self::integrate_embedded()`].
+pub trait EmbeddedIntegrator<const D: usize>: SimplexIntegrator<D, IntegratorDummy> {
+    /// The integrals of both rules over the subdomain with the `transformation`.
+    fn integrate_embedded<T: SimplexFunction<D>>(
+        &self,
+        transformation: &Array2<f64>,
+        func: &Box<T>,
+        simplex: &Simplex<D>,
+    ) -> Result<EmbeddedResult<T::Return>>;
+}
+
+/// The highest supported degree of the [`GrundmannMoellerIntegrator`], above the negative weights
+/// cancel too much.
+pub const MAX_GRUNDMANN_MOELLER_DEGREE: usize = 15;
+
+/// The rules of A. Grundmann and H. M. Möller, "Invariant integration formulas for the n-simplex
+/// by combinatorial methods", 1978, in any dimension.
+///
+/// The rule of degree `2s + 1` contains all points of the rule of degree `2s - 1`, which is the
+/// embedded rule. The weights are scaled with the signed volume like the other integrators.
+pub struct GrundmannMoellerIntegrator<const D: usize> {
+    // M(D+1,n), die Punkte der eingebetteten Regel haben dort ein Gewicht ungleich null
+    integration_points: Array2<f64>,
+    integration_weights: Array1<f64>,
+    embedded_weights: Array1<f64>,
+}
+
+/// All `parts` tuples of nonnegative integers whose sum is `total`.
+fn compositions(parts: usize, total: usize) -> Vec<Vec<usize>> {
+    if parts == 1 {
+        return vec![vec![total]];
+    }
+    let mut result = Vec::new();
+    for first in 0..=total {
+        for mut rest in compositions(parts - 1, total - first) {
+            rest.insert(0, first);
+            result.push(rest);
+        }
+    }
+    result
+}
+
+fn factorial(n: usize) -> f64 {
+    (1..=n).map(|i| i as f64).product()
+}
+
+/// The weight of the points with the denominator `m` in the rule with the index `s`, normalized to
+/// the volume of the simplex of dimension `d`.
+fn grundmann_moeller_weight(d: usize, s: usize, m: usize) -> f64 {
+    let i = (d + 2 * s + 1 - m) / 2;
+    (-1_f64).powi(i as i32)
+        * 2_f64.powi(-2 * s as i32)
+        * (m as f64).powi(2 * s as i32 + 1)
+        * factorial(d)
+        / (factorial(i) * factorial(m + i))
+}
+
+impl<const D: usize> GrundmannMoellerIntegrator<D> {
+    /// The degree must be odd, between 3 and [`MAX_GRUNDMANN_MOELLER_DEGREE`], otherwise
+    /// [`IntegrationError::UnsupportedOrder`]. The embedded rule has the degree `order - 2`.
+    pub fn new(order: usize) -> Result<Self> {
+        if order < 3 || order.is_multiple_of(2) || order > MAX_GRUNDMANN_MOELLER_DEGREE {
+            return Err(IntegrationError::UnsupportedOrder {
+                rule: "Grundmann-Möller",
+                order,
+            });
+        }
+        let s = order / 2;
+        let mut points = Vec::new();
+        let mut integration_weights = Vec::new();
+        let mut embedded_weights = Vec::new();
+        // Die Gruppe k hat die Punkte (2 beta + 1) / m mit |beta| = k, die Gruppen bis s - 1 bilden
+        // die eingebettete Regel
+        for k in 0..=s {
+            let m = D + 2 * k + 1;
+            let weight = grundmann_moeller_weight(D, s, m);
+            let embedded_weight = if k < s {
+                grundmann_moeller_weight(D, s - 1, m)
+            } else {
+                0.
+            };
+            for beta in compositions(D + 1, k) {
+                points.extend(beta.iter().map(|&b| (2 * b + 1) as f64 / m as f64));
+                integration_weights.push(weight);
+                embedded_weights.push(embedded_weight);
+            }
+        }
+        let num_points = integration_weights.len();
+        let integration_points = Array2::from_shape_vec([num_points, D + 1], points)
+            .unwrap()
+            .reversed_axes();
+        Ok(Self {
+            integration_points,
+            integration_weights: Array1::from(integration_weights),
+            embedded_weights: Array1::from(embedded_weights),
+        })
+    }
+
+    /// The number of function evaluations per simplex, shared by both rules.
+    pub fn get_num_points(&self) -> usize {
+        self.integration_weights.len()
+    }
+
+    fn integrate<T: SimplexFunction<D>>(
+        &self,
+        transformation: &Array2<f64>,
+        func: &Box<T>,
+        simplex: &Simplex<D>,
+        with_embedded: bool,
+    ) -> Result<EmbeddedResult<T::Return>> {
+        check_shape(transformation, [D + 1, D + 1])?;
+        // Die Determinante der baryzentrischen Transformation ist das Verhältnis der Volumen
+        let volume = simplex.get_volume() * determinant(transformation)?;
+
+        // M(D+1,n) = M(D+1,D+1) x M(D+1,n)
+        let integration_points = transformation.dot(&self.integration_points);
+
+        let mut result = func.additive_neutral_element();
+        let mut embedded = func.additive_neutral_element();
+        for (n, integration_point) in integration_points.columns().into_iter().enumerate() {
+            let mut point = func.function_vec(&integration_point.into_owned(), simplex);
+            let embedded_weight = self.embedded_weights[n];
+            if with_embedded && embedded_weight != 0. {
+                // Der Funktionswert wird nur einmal berechnet und danach umskaliert
+                point *= volume * embedded_weight;
+                embedded.add_assign(&point);
+                point *= self.integration_weights[n] / embedded_weight;
+            } else {
+                point *= volume * self.integration_weights[n];
+            }
+            result.add_assign(&point);
+        }
+        Ok(EmbeddedResult { result, embedded })
+    }
+}
+
+impl<const D: usize> SimplexIntegrator<D, IntegratorDummy> for GrundmannMoellerIntegrator<D> {
+    fn dupe(&self) -> Self {
+        Self {
+            integration_points: self.integration_points.clone(),
+            integration_weights: self.integration_weights.clone(),
+            embedded_weights: self.embedded_weights.clone(),
+        }
+    }
+
+    fn integrate_over_domain<T: SimplexFunction<D>>(
+        &self,
+        transformation: &Array2<f64>,
+        func: &Box<T>,
+        simplex: &Simplex<D>,
+        _cache_data: &mut IntegratorDummy,
+    ) -> Result<T::Return> {
+        Ok(self.integrate(transformation, func, simplex, false)?.result)
+    }
+
+    fn integrate_over_domain_embedded<T: SimplexFunction<D>>(
+        &self,
+        transformation: &Array2<f64>,
+        func: &Box<T>,
+        simplex: &Simplex<D>,
+        _cache_data: &mut IntegratorDummy,
+    ) -> Result<Option<EmbeddedResult<T::Return>>> {
+        self.integrate_embedded(transformation, func, simplex)
+            .map(Some)
+    }
+}
+
+impl<const D: usize> EmbeddedIntegrator<D> for GrundmannMoellerIntegrator<D> {
+    fn integrate_embedded<T: SimplexFunction<D>>(
+        &self,
+        transformation: &Array2<f64>,
+        func: &Box<T>,
+        simplex: &Simplex<D>,
+    ) -> Result<EmbeddedResult<T::Return>> {
+        self.integrate(transformation, func, simplex, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{Array1, Array2};
+
+    use super::{compositions, factorial, EmbeddedIntegrator, GrundmannMoellerIntegrator};
+    use crate::common::IntegratorDummy;
+    use crate::error::IntegrationError;
+    use crate::simplex::{ResultTypeWrapper, Simplex, SimplexFunction};
+    use crate::{integrator_tests, integrator_tests_3d};
+
+    integrator_tests! {
+        order3: GrundmannMoellerIntegrator<2>: GrundmannMoellerIntegrator::new(3).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order7: GrundmannMoellerIntegrator<2>: GrundmannMoellerIntegrator::new(7).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+    }
+
+    integrator_tests_3d! {
+        order3_3d: GrundmannMoellerIntegrator<3>: GrundmannMoellerIntegrator::new(3).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order5_3d: GrundmannMoellerIntegrator<3>: GrundmannMoellerIntegrator::new(5).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+    }
+
+    /// Das Produkt der baryzentrischen Koordinaten hoch den Exponenten
+    struct BarycentricMonomial(Vec<usize>);
+
+    impl<const D: usize> SimplexFunction<D> for BarycentricMonomial {
+        type Return = ResultTypeWrapper<f64>;
+
+        fn function_vec(&self, xi: &Array1<f64>, _simplex: &Simplex<D>) -> Self::Return {
+            ResultTypeWrapper::new(
+                xi.iter()
+                    .zip(self.0.iter())
+                    .map(|(x, &e)| x.powi(e as i32))
+                    .product(),
+            )
+        }
+    }
+
+    /// Prüft beide Regeln an allen Monomen bis zum Grad der Regel
+    fn check_degrees<const D: usize>(simplex: &Simplex<D>) {
+        let volume = simplex.get_volume();
+        for order in (3..=11).step_by(2) {
+            let inte = GrundmannMoellerIntegrator::<D>::new(order).unwrap();
+            for degree in 0..=order {
+                for exponents in compositions(D + 1, degree) {
+                    // ∫ xi^alpha = D! alpha! / (D + |alpha|)! * Volumen
+                    let exact = factorial(D)
+                        * exponents.iter().map(|&e| factorial(e)).product::<f64>()
+                        / factorial(D + degree)
+                        * volume;
+                    let func = Box::new(BarycentricMonomial(exponents));
+                    let both = inte
+                        .integrate_embedded(&Array2::eye(D + 1), &func, simplex)
+                        .unwrap();
+                    let (result, embedded) = (both.result.get(), both.embedded.get());
+                    assert!((result - exact).abs() < 1e-12, "{} {}", order, degree);
+                    if degree + 2 <= order {
+                        assert!((embedded - exact).abs() < 1e-12, "{} {}", order, degree);
+                    }
+                }
+            }
+            // Beim Grad der Regel ist die eingebettete Regel nicht mehr exakt
+            let mut exponents = vec![0; D + 1];
+            exponents[0] = order;
+            let func = Box::new(BarycentricMonomial(exponents));
+            let both = inte
+                .integrate_embedded(&Array2::eye(D + 1), &func, simplex)
+                .unwrap();
+            assert!((both.result.get() - both.embedded.get()).abs() > 1e-6);
+        }
+    }
+
+    #[test]
+    fn exact_degrees() {
+        use ndarray::array;
+
+        check_degrees(&Simplex::<2>::new_from_points(
+            &array![0.5, 0.],
+            &array![2., 1.],
+            &array![-1., 3.],
+        ));
+        check_degrees(&Simplex::<3>::new_from_points(
+            &array![0., 0., 0.],
+            &array![1., 0.2, 0.],
+            &array![0., 1., 0.3],
+            &array![0.1, 0., 2.],
+        ));
+    }
+
+    #[test]
+    fn number_of_points() {
+        // Die Regel mit dem Index s hat sum_{k<=s} binom(k + D, D) Punkte
+        let expected_2d = [4, 10, 20, 35, 56, 84, 120];
+        let expected_3d = [5, 15, 35, 70, 126, 210, 330];
+        for (n, order) in (3..=15).step_by(2).enumerate() {
+            assert_eq!(
+                GrundmannMoellerIntegrator::<2>::new(order)
+                    .unwrap()
+                    .get_num_points(),
+                expected_2d[n]
+            );
+            assert_eq!(
+                GrundmannMoellerIntegrator::<3>::new(order)
+                    .unwrap()
+                    .get_num_points(),
+                expected_3d[n]
+            );
+        }
+        for order in [0, 1, 4, 17] {
+            assert!(matches!(
+                GrundmannMoellerIntegrator::<2>::new(order),
+                Err(IntegrationError::UnsupportedOrder { .. })
+            ));
+        }
+    }
+}
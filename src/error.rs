@@ -0,0 +1,111 @@
+use std::fmt;
+use std::io;
+
+/// The errors of the constructors and integrators.
+/// Within a FE simulation a bad element is reported instead of aborting the whole job.
+#[derive(Debug)]
+pub enum IntegrationError {
+    /// The coordinates do not sum up to one.
+    InvalidBarycentric {
+        coordinates: Vec<f64>,
+    },
+    /// The quadrature rule is not tabulated for this order.
+    UnsupportedOrder {
+        rule: &'static str,
+        order: usize,
+    },
+    /// A refinement tree could not be decoded.
+    MalformedTree(String),
+    /// The simplex has no volume, see [`Simplex::is_degenerate()`](crate::simplex::Simplex::is_degenerate).
+    DegenerateSimplex {
+        volume: f64,
+    },
+    /// The simplex is negatively oriented and
+    /// [`OrientationPolicy::Reject`](crate::simplex::OrientationPolicy::Reject) was requested.
+    InvertedSimplex {
+        volume: f64,
+    },
+    /// A point or transformation matrix has the wrong shape.
+    DimensionMismatch {
+        expected: [usize; 2],
+        found: [usize; 2],
+    },
+    /// An element of a mesh refers to missing nodes or has the wrong number of nodes.
+    InvalidElement {
+        element: usize,
+        message: String,
+    },
+    /// A numerical building block failed, e.g. the eigenproblem of a quadrature rule.
+    NumericalFailure(String),
+    Io(io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, IntegrationError>;
+
+impl fmt::Display for IntegrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidBarycentric { coordinates } => write!(
+                f,
+                "illegal barycentric coordinates {:?}, the sum is {}",
+                coordinates,
+                coordinates.iter().sum::<f64>()
+            ),
+            Self::UnsupportedOrder { rule, order } => {
+                write!(f, "there is no {} rule of order {}", rule, order)
+            }
+            Self::MalformedTree(message) => write!(f, "malformed refinement tree: {}", message),
+            Self::DegenerateSimplex { volume } => {
+                write!(f, "the simplex is degenerate, its volume is {}", volume)
+            }
+            Self::InvertedSimplex { volume } => {
+                write!(f, "the simplex is inverted, its volume is {}", volume)
+            }
+            Self::DimensionMismatch { expected, found } => write!(
+                f,
+                "expected a {} x {} matrix, found {} x {}",
+                expected[0], expected[1], found[0], found[1]
+            ),
+            Self::InvalidElement { element, message } => {
+                write!(f, "element {}: {}", element, message)
+            }
+            Self::NumericalFailure(message) => write!(f, "numerical failure: {}", message),
+            Self::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for IntegrationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for IntegrationError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Checks that the barycentric coordinates sum up to one.
+pub(crate) fn check_barycentric(coordinates: &[f64]) -> Result<()> {
+    const TOLERANCE: f64 = 1e-10;
+    if (coordinates.iter().sum::<f64>() - 1.0).abs() >= TOLERANCE {
+        return Err(IntegrationError::InvalidBarycentric {
+            coordinates: coordinates.to_vec(),
+        });
+    }
+    Ok(())
+}
+
+/// Checks the shape of a matrix.
+pub(crate) fn check_shape(matrix: &ndarray::Array2<f64>, expected: [usize; 2]) -> Result<()> {
+    let found = [matrix.nrows(), matrix.ncols()];
+    if found != expected {
+        return Err(IntegrationError::DimensionMismatch { expected, found });
+    }
+    Ok(())
+}
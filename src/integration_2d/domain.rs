@@ -1,141 +1,36 @@
-use std::ops::{Add, AddAssign, Mul, MulAssign};
+use ndarray::Array1;
 
-use ndarray::{array, Array1, Array2, Axis};
+pub use crate::common::det3x3;
+use crate::common::MaybeSync;
+pub use crate::simplex::{
+    OrientationPolicy, PhysicalFunction, ResultTypeWrapper, Simplex, SimplexFunction,
+    SimplexIntegrator, SimplexResultType,
+};
 
-type Point2D = Array1<f64>;
+/// A simplex on the Euclidean 2D Plane
+pub type Simplex2D = Simplex<2>;
 
-pub fn det3x3(mat3x3: &Array2<f64>) -> f64 {
-    let mut sum = 0.0;
-    for j in 0..3 {
-        let mut prod = 1.0;
-        for i in 0..3 {
-            prod = prod * mat3x3[[i, (i + j) % 3]]
-        }
-        sum = sum + prod;
-        let mut prod = -1.0;
-        for i in 0..3 {
-            prod = prod * mat3x3[[(2 - i), (i + j) % 3]]
-        }
-        sum = sum + prod;
-    }
-    return sum;
-}
-
-/// A struct representing a simplex on the Euclidean 2D Plane
-pub struct Simplex2D {
-    points: Array2<f64>,
-}
-
-impl Simplex2D {
-    pub fn new_from_points(p1: &Point2D, p2: &Point2D, p3: &Point2D) -> Self {
-        Self {
-            points: array![[p1[0], p2[0], p3[0]], [p1[1], p2[1], p3[1]]],
-        }
-    }
+/// The former name of [`SimplexIntegrator<2, C>`], implemented by every such integrator.
+pub trait Simplex2DIntegrator<C>: SimplexIntegrator<2, C> {}
 
-    pub fn new_from_array(points: Array2<f64>) -> Self {
-        Self { points }
-    }
-
-    pub fn get_points(&self) -> Array2<f64> {
-        return self.points.clone();
-    }
+impl<T: SimplexIntegrator<2, C>, C> Simplex2DIntegrator<C> for T {}
 
-    pub fn get_area(&self) -> f64 {
-        let mut points = self.points.clone();
-        points
-            .append(Axis(0), array![[1.0_f64, 1., 1.]].view())
-            .unwrap();
+/// The former name of [`SimplexResultType`], implemented by every result type.
+pub trait Simplex2DResultType: SimplexResultType {}
 
-        //let points = points.reversed_axes();
-        println!("{}", points);
+impl<T: SimplexResultType> Simplex2DResultType for T {}
 
-        return det3x3(&points) / 2.;
-    }
-}
-
-pub trait Simplex2DResultType: MulAssign<f64> + AddAssign<f64> {
-    fn add_assign(&mut self, other: &Self);
-
-    fn distance(&self, other: &Self) -> f64;
-
-    fn additive_neutral_element() -> Self;
-}
+/// The former name of [`ResultTypeWrapper`].
+pub type ResultTypeWrapper2D<T> = ResultTypeWrapper<T>;
 
-#[derive(Debug)]
-pub struct ResultTypeWrapper<T>(T);
-
-impl<T> ResultTypeWrapper<T> {
-    pub fn new(t: T) -> Self {
-        Self(t)
-    }
-
-    pub fn get(self) -> T {
-        self.0
-    }
-
-    pub fn get_borrow(&self) -> &T {
-        &self.0
-    }
-}
-
-impl MulAssign<f64> for ResultTypeWrapper<f64> {
-    fn mul_assign(&mut self, rhs: f64) {
-        self.0 *= rhs
-    }
-}
-
-impl AddAssign<f64> for ResultTypeWrapper<f64> {
-    fn add_assign(&mut self, rhs: f64) {
-        self.0 += rhs
-    }
-}
-
-impl Simplex2DResultType for ResultTypeWrapper<f64> {
-    fn add_assign(&mut self, other: &Self) {
-        self.0 += other.0
-    }
-
-    fn distance(&self, other: &Self) -> f64 {
-        (self.0 - other.0).abs()
-    }
+/// A function of the physical coordinates `x` and `y`, see [`PhysicalFunction`].
+pub type PhysicalFunction2D<F> = PhysicalFunction<2, F>;
 
-    fn additive_neutral_element() -> Self {
-        Self(0.)
-    }
-}
-
-impl MulAssign<f64> for ResultTypeWrapper<Array2<f64>> {
-    fn mul_assign(&mut self, rhs: f64) {
-        self.0 *= rhs
-    }
-}
-
-impl AddAssign<f64> for ResultTypeWrapper<Array2<f64>> {
-    fn add_assign(&mut self, rhs: f64) {
-        self.0 += rhs
-    }
-}
-
-impl Simplex2DResultType for ResultTypeWrapper<Array2<f64>> {
-    fn add_assign(&mut self, other: &Self) {
-        self.0 = &self.0 + &other.0
-    }
-
-    fn distance(&self, other: &Self) -> f64 {
-        let diff = &self.0 - &other.0;
-        diff.iter().map(|f| f.powi(2)).sum::<f64>().sqrt()
-    }
-
-    fn additive_neutral_element() -> Self {
-        Self(Array2::zeros([6, 6]))
-    }
-}
-
-/// A general trait implemented by types which supply a function to integrate over.
+/// A general trait implemented by types which supply a function to integrate over a triangle.
 /// Inputs must be expressed in barycentric coordinates.
-pub trait Simplex2DFunction {
-    type Return: Simplex2DResultType;
+/// Every implementor is a [`SimplexFunction<2>`].
+pub trait Simplex2DFunction: MaybeSync {
+    type Return: SimplexResultType;
 
     fn additive_neutral_element(&self) -> Self::Return {
         Self::Return::additive_neutral_element()
@@ -148,39 +43,32 @@ pub trait Simplex2DFunction {
     }
 }
 
-/// A general trait implemented by types which supply an integration scheme for a single Simplex.
-/// Allows for easy substitution of simplex integration schemes.
-pub trait Simplex2DIntegrator<D> {
-    fn dupe(&self) -> Self;
-
-    /// This function will be called on a single simplex, given in the third argument.
-    fn integrate_simplex<T: Simplex2DFunction>(
-        self: &Self,
-        func: &Box<T>,
-        simplex: &Simplex2D,
-        cache_data: &mut D,
-    ) -> T::Return {
-        self.integrate_over_domain(
-            &array![[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
-            func,
-            simplex,
-            cache_data,
-        )
+/// Closures `|xi1, xi2, xi3, simplex| ...` are integrands as well, their result is wrapped into a
+/// [`ResultTypeWrapper`].
+impl<F, T> Simplex2DFunction for F
+where
+    F: Fn(f64, f64, f64, &Simplex2D) -> T + MaybeSync,
+    ResultTypeWrapper<T>: SimplexResultType,
+{
+    type Return = ResultTypeWrapper<T>;
+
+    fn function(&self, xi1: f64, xi2: f64, xi3: f64, simplex: &Simplex2D) -> Self::Return {
+        ResultTypeWrapper::new(self(xi1, xi2, xi3, simplex))
+    }
+}
+
+impl<F: Simplex2DFunction> SimplexFunction<2> for F {
+    type Return = F::Return;
+
+    fn function_vec(&self, xi: &Array1<f64>, simplex: &Simplex2D) -> Self::Return {
+        Simplex2DFunction::function_vec(self, xi, simplex)
     }
 
-    /// A more general function which takes a transformation matrix to map to the initial subdomain of the simplex
-    fn integrate_over_domain<T: Simplex2DFunction>(
-        self: &Self,
-        transformation: &Array2<f64>,
-        func: &Box<T>,
-        simplex: &Simplex2D,
-        cache_data: &mut D,
-    ) -> T::Return;
+    fn additive_neutral_element(&self) -> Self::Return {
+        Simplex2DFunction::additive_neutral_element(self)
+    }
 }
 
-//fn usage(sim: &Simplex2D, func: &Box<dyn Simplex2DFunction>, inte: &Box<dyn Simplex2DIntegrator>) {
-//    let val = inte.integrate(func, sim);
-//}
 #[macro_export]
 macro_rules! integrator_tests {
     ($($name:ident: $type:ty: $init:expr, $typecache:ty: $initcache:expr,)*) => {
@@ -212,7 +100,7 @@ macro_rules! integrator_tests {
                     &array![0.,1.],
                 );
 
-                let result = inte.integrate_simplex(&func, &sim, &mut cache);
+                let result = inte.integrate_simplex(&func, &sim, &mut cache).unwrap();
 
                 let true_result = ResultTypeWrapper::new(0.5_f64);
                 let approx_eq = true_result.distance(&result);
@@ -233,7 +121,7 @@ macro_rules! integrator_tests {
                     &array![0.,1.],
                 );
 
-                let result = inte.integrate_simplex(&func, &sim, &mut cache);
+                let result = inte.integrate_simplex(&func, &sim, &mut cache).unwrap();
 
                 let true_result = ResultTypeWrapper::new(0.5_f64);
                 let approx_eq = true_result.distance(&result);
@@ -254,13 +142,54 @@ macro_rules! integrator_tests {
                     &array![0.,1.],
                 );
 
-                let result = inte.integrate_simplex(&func, &sim, &mut cache);
+                let result = inte.integrate_simplex(&func, &sim, &mut cache).unwrap();
 
                 let true_result = ResultTypeWrapper::new(0.5_f64);
                 let approx_eq = result.get_borrow().signum() + true_result.get_borrow().signum();
 
                 assert!(approx_eq == 2.0, "Expected: {:?}, Actual: {:?}",result,true_result);
             }
+
+            #[test]
+            fn inverted_orientation() {
+                let inte = get_instance();
+                // Linear und nicht symmetrisch, eine falsche Umordnung ändert das Integral
+                let linear = |x: [f64; 2]| 1. + 2. * x[0] - 3. * x[1];
+                let func = Box::new(move |xi1: f64, xi2: f64, xi3: f64, simplex: &Simplex2D| {
+                    linear(simplex.get_physical_point(&array![xi1, xi2, xi3]))
+                });
+                // Im Uhrzeigersinn, verschoben und gedreht
+                let simplices = [
+                    Simplex2D::new_from_points(&array![0.0_f64,0.], &array![0.,1.], &array![1.,0.]),
+                    Simplex2D::new_from_points(&array![2.0_f64,1.], &array![1.,3.], &array![4.,2.]),
+                    Simplex2D::new_from_points(&array![-1.0_f64,0.5], &array![0.5,-1.5], &array![-2.,-1.]),
+                ];
+                for sim in simplices.iter() {
+                    let volume = sim.get_volume();
+                    assert!(volume < 0.);
+                    // Exakt ist das Volumen mal dem Wert im Schwerpunkt
+                    let signed = volume * linear(sim.get_physical_point(&array![1. / 3., 1. / 3., 1. / 3.]));
+
+                    let result = inte.integrate_simplex(&func, sim, &mut get_integrator_cache()).unwrap();
+                    assert!((result.get() - signed).abs() <= 1e-12 * signed.abs(), "Signed: {}", signed);
+
+                    let result = inte.integrate_simplex_oriented(&func, sim, &mut get_integrator_cache(), OrientationPolicy::Reorder).unwrap();
+                    assert!((result.get() + signed).abs() <= 1e-12 * signed.abs(), "Reorder: {}", -signed);
+
+                    let result = inte.integrate_simplex_oriented(&func, sim, &mut get_integrator_cache(), OrientationPolicy::Reject);
+                    assert!(matches!(result, Err(crate::error::IntegrationError::InvertedSimplex { .. })));
+                }
+            }
+
+            #[test]
+            fn degenerate_simplex_is_rejected() {
+                let inte = get_instance();
+                let func = Box::new(Constant2DFunction {});
+                let sim = Simplex2D::new_from_points(&array![0.0_f64,0.], &array![1.,1.], &array![3.,3. + 1e-14]);
+
+                let result = inte.integrate_simplex(&func, &sim, &mut get_integrator_cache());
+                assert!(matches!(result, Err(crate::error::IntegrationError::DegenerateSimplex { .. })));
+            }
         }
     )*
     }
@@ -1,8 +1,9 @@
 use crate::integration_2d::domain::*;
 use ndarray::{array, Array1};
-use std::cell::RefCell;
+use num_complex::Complex64;
+use std::sync::Mutex;
 
-const TOLERANCE: f64 = 1e-10;
+use crate::error::{check_barycentric, Result};
 
 pub struct PyramidFunction {
     xi1p: f64,
@@ -12,22 +13,15 @@ pub struct PyramidFunction {
 }
 
 impl PyramidFunction {
-    pub fn new(xi1p: f64, xi2p: f64, xi3p: f64, height: f64) -> Self {
-        if (xi1p + xi2p + xi3p - 1.0).abs() >= TOLERANCE {
-            panic!(
-                "Illegal Barycentric Coordinates, {},{},{} = {} > 1.0!",
-                xi1p,
-                xi2p,
-                xi3p,
-                xi1p + xi2p + xi3p
-            );
-        };
-        PyramidFunction {
+    /// The peak `(xi1p, xi2p, xi3p)` must be valid barycentric coordinates.
+    pub fn new(xi1p: f64, xi2p: f64, xi3p: f64, height: f64) -> Result<Self> {
+        check_barycentric(&[xi1p, xi2p, xi3p])?;
+        Ok(PyramidFunction {
             xi1p,
             xi2p,
             xi3p,
             height,
-        }
+        })
     }
 }
 
@@ -98,32 +92,85 @@ impl Constant2DFunctionHistory {
     }
 }
 
+/// The plane wave `e^{i k·x}` with the wave vector `k` at the real point `x`.
+pub struct PlaneWave2DFunction {
+    wave_vector: Array1<f64>,
+}
+
+impl PlaneWave2DFunction {
+    pub fn new(wave_vector: [f64; 2]) -> Self {
+        Self {
+            wave_vector: Array1::from_vec(wave_vector.to_vec()),
+        }
+    }
+
+    fn value(&self, xi: &Array1<f64>, simplex: &Simplex2D) -> Complex64 {
+        Complex64::cis(self.wave_vector.dot(&simplex.get_points_borrow().dot(xi)))
+    }
+}
+
+impl Simplex2DFunction for PlaneWave2DFunction {
+    type Return = ResultTypeWrapper<Complex64>;
+    fn function(&self, xi1: f64, xi2: f64, xi3: f64, simplex: &Simplex2D) -> Self::Return {
+        ResultTypeWrapper::new(self.value(&array![xi1, xi2, xi3], simplex))
+    }
+}
+
+/// The load vector `e^{i k·x} N_i` of the linear shape functions `N_i = xi_i`.
+pub struct PlaneWaveLoad2DFunction {
+    wave: PlaneWave2DFunction,
+}
+
+impl PlaneWaveLoad2DFunction {
+    pub fn new(wave_vector: [f64; 2]) -> Self {
+        Self {
+            wave: PlaneWave2DFunction::new(wave_vector),
+        }
+    }
+}
+
+impl Simplex2DFunction for PlaneWaveLoad2DFunction {
+    type Return = ResultTypeWrapper<Array1<Complex64>>;
+
+    fn additive_neutral_element(&self) -> Self::Return {
+        ResultTypeWrapper::new(Array1::zeros([3]))
+    }
+
+    fn function(&self, xi1: f64, xi2: f64, xi3: f64, simplex: &Simplex2D) -> Self::Return {
+        let xi = array![xi1, xi2, xi3];
+        let value = self.wave.value(&xi, simplex);
+        ResultTypeWrapper::new(xi.mapv(|f| value * f))
+    }
+}
+
 /// A struct which will record all function evaluations of the given [`Simplex2DFunction`]
+/// The history is guarded by a mutex, with the feature `parallel` the order of the recorded
+/// points depends on the scheduling of the threads.
 pub struct Function2DHistory<F: Simplex2DFunction> {
-    history: RefCell<Vec<Array1<f64>>>,
+    history: Mutex<Vec<Array1<f64>>>,
     function: F,
 }
 
 impl<F: Simplex2DFunction> Function2DHistory<F> {
     pub fn new(func: F) -> Self {
         return Self {
-            history: RefCell::new(Vec::new()),
+            history: Mutex::new(Vec::new()),
             function: func,
         };
     }
     /// A history of all function evaluations will be returned.
     /// Drops the struct upon after this function.
     pub fn get_history(self) -> Vec<Array1<f64>> {
-        return self.history.take();
+        return self.history.into_inner().unwrap();
     }
 
     pub fn function_evaluations(&self) -> usize {
-        return self.history.borrow().len();
+        return self.history.lock().unwrap().len();
     }
 
     /// Deletes the history of the function evaluations.
     pub fn delete_history(&self) {
-        self.history.borrow_mut().clear();
+        self.history.lock().unwrap().clear();
     }
 }
 
@@ -132,9 +179,110 @@ impl<F: Simplex2DFunction> Simplex2DFunction for Function2DHistory<F> {
     fn function(&self, xi1: f64, xi2: f64, xi3: f64, simplex: &Simplex2D) -> F::Return {
         let result = self.function.function(xi1, xi2, xi3, simplex);
         {
-            let mut history = self.history.borrow_mut();
+            let mut history = self.history.lock().unwrap();
             history.push(array![xi1, xi2, xi3]);
         }
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ndarray::array;
+    use num_complex::Complex64;
+
+    use super::{PlaneWave2DFunction, PlaneWaveLoad2DFunction};
+    use crate::common::IntegratorDummy;
+    use crate::integration_2d::domain::{PhysicalFunction2D, Simplex2D, SimplexIntegrator};
+    use crate::integration_2d::integrators::{
+        DunavantIntegrator, EdgeSubdivisionIntegrator, Hierarchic2DIntegrator,
+        Hierarchic2DIntegratorData, Quadrilateral2DIntegrator,
+    };
+
+    #[test]
+    fn plane_wave_with_all_integrators() {
+        let (a, b) = (3., -2.);
+        let sim = Simplex2D::new_from_points(&array![0., 0.], &array![1., 0.], &array![0., 1.]);
+        // Geschlossene Form über dem Referenzdreieck
+        let i = Complex64::i();
+        let exact = ((i * b).exp() * ((i * (a - b)).exp() - 1.) / (i * (a - b))
+            - ((i * a).exp() - 1.) / (i * a))
+            / (i * b);
+        let func = Box::new(PlaneWave2DFunction::new([a, b]));
+
+        let results = [
+            DunavantIntegrator::new(20).unwrap().integrate_simplex(
+                &func,
+                &sim,
+                &mut IntegratorDummy::get(),
+            ),
+            Quadrilateral2DIntegrator::new(8)
+                .unwrap()
+                .integrate_simplex(&func, &sim, &mut IntegratorDummy::get()),
+            EdgeSubdivisionIntegrator::new(DunavantIntegrator::new(10).unwrap(), 3)
+                .unwrap()
+                .integrate_simplex(&func, &sim, &mut IntegratorDummy::get()),
+            Hierarchic2DIntegrator::new(DunavantIntegrator::new(10).unwrap(), false, 1e-12)
+                .integrate_simplex(&func, &sim, &mut Hierarchic2DIntegratorData::new_cache()),
+        ];
+        for result in results {
+            let result = result.unwrap().get();
+            assert!(
+                (result - exact).norm() < 1e-10,
+                "Expected: {}, Actual: {}",
+                exact,
+                result
+            );
+        }
+
+        // Die linearen Formfunktionen sind eine Zerlegung der Eins
+        let load = Box::new(PlaneWaveLoad2DFunction::new([a, b]));
+        let load = Hierarchic2DIntegrator::new(DunavantIntegrator::new(10).unwrap(), false, 1e-12)
+            .integrate_simplex(&load, &sim, &mut Hierarchic2DIntegratorData::new_cache())
+            .unwrap()
+            .get();
+        assert_eq!(load.len(), 3);
+        assert!((load.sum() - exact).norm() < 1e-10);
+    }
+
+    #[test]
+    fn physical_and_closure_integrands() {
+        let sim = Simplex2D::new_from_points(&array![1., 0.], &array![3., 0.], &array![1., 2.]);
+        let inte = DunavantIntegrator::new(2).unwrap();
+        // ∫ x y = A / 12 (Σ xi yi + Σ xi Σ yi) = 2 / 12 (2 + 5 * 2)
+        let physical = inte
+            .integrate_simplex(
+                &Box::new(PhysicalFunction2D::new(|x: &[f64; 2]| x[0] * x[1])),
+                &sim,
+                &mut IntegratorDummy::get(),
+            )
+            .unwrap()
+            .get();
+        assert!((physical - 2.).abs() < 1e-13);
+
+        let closure = inte
+            .integrate_simplex(
+                &Box::new(|xi1: f64, xi2: f64, xi3: f64, _simplex: &Simplex2D| {
+                    (xi1 + 3. * xi2 + xi3) * 2. * xi3
+                }),
+                &sim,
+                &mut IntegratorDummy::get(),
+            )
+            .unwrap()
+            .get();
+        assert!((closure - 2.).abs() < 1e-13);
+
+        // Fläche und statische Momente
+        let moments = inte
+            .integrate_simplex(
+                &Box::new(PhysicalFunction2D::new(|x: &[f64; 2]| [1., x[0], x[1]])),
+                &sim,
+                &mut IntegratorDummy::get(),
+            )
+            .unwrap()
+            .get();
+        for (moment, expected) in moments.iter().zip([2., 10. / 3., 4. / 3.]) {
+            assert!((moment - expected).abs() < 1e-13);
+        }
+    }
+}
@@ -1,175 +1,436 @@
 use ndarray::{array, Array1, Array2};
 
-use crate::common::IntegratorDummy;
+use crate::common::{det2x2, IntegratorDummy};
+use crate::error::{check_shape, IntegrationError, Result};
 use crate::integration_2d::domain::{
-    Simplex2D, Simplex2DFunction, Simplex2DIntegrator, Simplex2DResultType,
+    Simplex2D, SimplexFunction, SimplexIntegrator, SimplexResultType,
 };
 
-fn det2x2(mat2x2: &Array2<f64>) -> f64 {
-    mat2x2[[0, 0]] * mat2x2[[1, 1]] - mat2x2[[0, 1]] * mat2x2[[1, 0]]
+/// One symmetric orbit of a Dunavant rule: multiplicity (1, 3 or 6), the generating point in
+/// barycentric coordinates and the weight of every point in the orbit.
+pub(crate) type Orbit = (usize, [f64; 3], f64);
+
+const ONE_THIRD: f64 = 1. / 3.;
+
+/// The symmetric rules of D. A. Dunavant, "High degree efficient symmetrical Gaussian quadrature
+/// rules for the triangle", 1985, for the degrees 1 to 20.
+/// The weights are normalized to the area of the triangle.
+/// Note that the rules of degree 11, 15, 16, 18 and 20 contain points outside of the triangle.
+#[rustfmt::skip]
+fn dunavant_orbits(order: usize) -> Result<&'static [Orbit]> {
+    Ok(match order {
+        1 => &[
+            (1, [ONE_THIRD, ONE_THIRD, ONE_THIRD], 1.0),
+        ],
+        2 => &[
+            (3, [0.666_666_666_666_667, 0.166_666_666_666_667, 0.166_666_666_666_667], 0.333_333_333_333_333),
+        ],
+        3 => &[
+            (1, [ONE_THIRD, ONE_THIRD, ONE_THIRD], -0.5625),
+            (3, [0.6, 0.2, 0.2], 0.520_833_333_333_333),
+        ],
+        4 => &[
+            (3, [0.108_103_018_168_070, 0.445_948_490_915_965, 0.445_948_490_915_965], 0.223_381_589_678_011),
+            (3, [0.816_847_572_980_459, 0.091_576_213_509_771, 0.091_576_213_509_771], 0.109_951_743_655_322),
+        ],
+        5 => &[
+            (1, [ONE_THIRD, ONE_THIRD, ONE_THIRD], 0.225),
+            (3, [0.059_715_871_789_770, 0.470_142_064_105_115, 0.470_142_064_105_115], 0.132_394_152_788_506),
+            (3, [0.797_426_985_353_087, 0.101_286_507_323_456, 0.101_286_507_323_456], 0.125_939_180_544_827),
+        ],
+        6 => &[
+            (3, [0.501_426_509_658_179, 0.249_286_745_170_910, 0.249_286_745_170_910], 0.116_786_275_726_379),
+            (3, [0.873_821_971_016_996, 0.063_089_014_491_502, 0.063_089_014_491_502], 0.050_844_906_370_207),
+            (6, [0.053_145_049_844_817, 0.310_352_451_033_784, 0.636_502_499_121_399], 0.082_851_075_618_374),
+        ],
+        7 => &[
+            (1, [ONE_THIRD, ONE_THIRD, ONE_THIRD], -0.149_570_044_467_682),
+            (3, [0.479_308_067_841_920, 0.260_345_966_079_040, 0.260_345_966_079_040], 0.175_615_257_433_208),
+            (3, [0.869_739_794_195_568, 0.065_130_102_902_216, 0.065_130_102_902_216], 0.053_347_235_608_838),
+            (6, [0.048_690_315_425_316, 0.312_865_496_004_874, 0.638_444_188_569_810], 0.077_113_760_890_257),
+        ],
+        8 => &[
+            (1, [ONE_THIRD, ONE_THIRD, ONE_THIRD], 0.144_315_607_677_787),
+            (3, [0.081_414_823_414_554, 0.459_292_588_292_723, 0.459_292_588_292_723], 0.095_091_634_267_285),
+            (3, [0.658_861_384_496_480, 0.170_569_307_751_760, 0.170_569_307_751_760], 0.103_217_370_534_718),
+            (3, [0.898_905_543_365_938, 0.050_547_228_317_031, 0.050_547_228_317_031], 0.032_458_497_623_198),
+            (6, [0.008_394_777_409_958, 0.263_112_829_634_638, 0.728_492_392_955_404], 0.027_230_314_174_435),
+        ],
+        9 => &[
+            (1, [ONE_THIRD, ONE_THIRD, ONE_THIRD], 0.097_135_796_282_799),
+            (3, [0.020_634_961_602_525, 0.489_682_519_198_738, 0.489_682_519_198_738], 0.031_334_700_227_139),
+            (3, [0.125_820_817_014_127, 0.437_089_591_492_937, 0.437_089_591_492_937], 0.077_827_541_004_774),
+            (3, [0.623_592_928_761_935, 0.188_203_535_619_033, 0.188_203_535_619_033], 0.079_647_738_927_210),
+            (3, [0.910_540_973_211_095, 0.044_729_513_394_453, 0.044_729_513_394_453], 0.025_577_675_658_698),
+            (6, [0.036_838_412_054_736, 0.221_962_989_160_766, 0.741_198_598_784_498], 0.043_283_539_377_289),
+        ],
+        10 => &[
+            (1, [ONE_THIRD, ONE_THIRD, ONE_THIRD], 0.090_817_990_382_754),
+            (3, [0.028_844_733_232_685, 0.485_577_633_383_657, 0.485_577_633_383_657], 0.036_725_957_756_467),
+            (3, [0.781_036_849_029_926, 0.109_481_575_485_037, 0.109_481_575_485_037], 0.045_321_059_435_528),
+            (6, [0.141_707_219_414_880, 0.307_939_838_764_121, 0.550_352_941_820_999], 0.072_757_916_845_420),
+            (6, [0.025_003_534_762_686, 0.246_672_560_639_903, 0.728_323_904_597_411], 0.028_327_242_531_057),
+            (6, [0.009_540_815_400_299, 0.066_803_251_012_200, 0.923_655_933_587_501], 0.009_421_666_963_733),
+        ],
+        11 => &[
+            (3, [-0.069_222_096_541_517, 0.534_611_048_270_758, 0.534_611_048_270_758], 0.000_927_006_328_961),
+            (3, [0.202_061_394_068_290, 0.398_969_302_965_855, 0.398_969_302_965_855], 0.077_149_534_914_813),
+            (3, [0.593_380_199_137_435, 0.203_309_900_431_282, 0.203_309_900_431_282], 0.059_322_977_380_774),
+            (3, [0.761_298_175_434_837, 0.119_350_912_282_581, 0.119_350_912_282_581], 0.036_184_540_503_418),
+            (3, [0.935_270_103_777_448, 0.032_364_948_111_276, 0.032_364_948_111_276], 0.013_659_731_002_678),
+            (6, [0.050_178_138_310_495, 0.356_620_648_261_293, 0.593_201_213_428_212], 0.052_337_111_962_204),
+            (6, [0.021_022_016_536_166, 0.171_488_980_304_042, 0.807_489_003_159_792], 0.020_707_659_639_141),
+        ],
+        12 => &[
+            (3, [0.023_565_220_452_390, 0.488_217_389_773_805, 0.488_217_389_773_805], 0.025_731_066_440_455),
+            (3, [0.120_551_215_411_079, 0.439_724_392_294_460, 0.439_724_392_294_460], 0.043_692_544_538_038),
+            (3, [0.457_579_229_975_768, 0.271_210_385_012_116, 0.271_210_385_012_116], 0.062_858_224_217_885),
+            (3, [0.744_847_708_916_828, 0.127_576_145_541_586, 0.127_576_145_541_586], 0.034_796_112_930_709),
+            (3, [0.957_365_299_093_579, 0.021_317_350_453_210, 0.021_317_350_453_210], 0.006_166_261_051_559),
+            (6, [0.115_343_494_534_698, 0.275_713_269_685_514, 0.608_943_235_779_788], 0.040_371_557_766_381),
+            (6, [0.022_838_332_222_257, 0.281_325_580_989_940, 0.695_836_086_787_803], 0.022_356_773_202_303),
+            (6, [0.025_734_050_548_330, 0.116_251_915_907_597, 0.858_014_033_544_073], 0.017_316_231_108_659),
+        ],
+        13 => &[
+            (1, [ONE_THIRD, ONE_THIRD, ONE_THIRD], 0.052_520_923_400_802),
+            (3, [0.009_903_630_120_591, 0.495_048_184_939_705, 0.495_048_184_939_705], 0.011_280_145_209_330),
+            (3, [0.062_566_729_780_852, 0.468_716_635_109_574, 0.468_716_635_109_574], 0.031_423_518_362_454),
+            (3, [0.170_957_326_397_447, 0.414_521_336_801_277, 0.414_521_336_801_277], 0.047_072_502_504_194),
+            (3, [0.541_200_855_914_337, 0.229_399_572_042_831, 0.229_399_572_042_831], 0.047_363_586_536_355),
+            (3, [0.771_151_009_607_340, 0.114_424_495_196_330, 0.114_424_495_196_330], 0.031_167_529_045_794),
+            (3, [0.950_377_217_273_082, 0.024_811_391_363_459, 0.024_811_391_363_459], 0.007_975_771_465_074),
+            (6, [0.094_853_828_379_579, 0.268_794_997_058_761, 0.636_351_174_561_660], 0.036_848_402_728_732),
+            (6, [0.018_100_773_278_807, 0.291_730_066_734_288, 0.690_169_159_986_905], 0.017_401_463_303_822),
+            (6, [0.022_233_076_674_090, 0.126_357_385_491_669, 0.851_409_537_834_241], 0.015_521_786_839_045),
+        ],
+        14 => &[
+            (3, [0.022_072_179_275_643, 0.488_963_910_362_179, 0.488_963_910_362_179], 0.021_883_581_369_429),
+            (3, [0.164_710_561_319_092, 0.417_644_719_340_454, 0.417_644_719_340_454], 0.032_788_353_544_125),
+            (3, [0.453_044_943_382_323, 0.273_477_528_308_839, 0.273_477_528_308_839], 0.051_774_104_507_292),
+            (3, [0.645_588_935_174_913, 0.177_205_532_412_543, 0.177_205_532_412_543], 0.042_162_588_736_993),
+            (3, [0.876_400_233_818_255, 0.061_799_883_090_873, 0.061_799_883_090_873], 0.014_433_699_669_777),
+            (3, [0.961_218_077_502_598, 0.019_390_961_248_701, 0.019_390_961_248_701], 0.004_923_403_602_400),
+            (6, [0.057_124_757_403_648, 0.172_266_687_821_356, 0.770_608_554_774_996], 0.024_665_753_212_564),
+            (6, [0.092_916_249_356_972, 0.336_861_459_796_345, 0.570_222_290_846_683], 0.038_571_510_787_061),
+            (6, [0.014_646_950_055_654, 0.298_372_882_136_258, 0.686_980_167_808_088], 0.014_436_308_113_534),
+            (6, [0.001_268_330_932_872, 0.118_974_497_696_957, 0.879_757_171_370_171], 0.005_010_228_838_501),
+        ],
+        15 => &[
+            (3, [-0.013_945_833_716_486, 0.506_972_916_858_243, 0.506_972_916_858_243], 0.001_916_875_642_849),
+            (3, [0.137_187_291_433_955, 0.431_406_354_283_023, 0.431_406_354_283_023], 0.044_249_027_271_145),
+            (3, [0.444_612_710_305_711, 0.277_693_644_847_144, 0.277_693_644_847_144], 0.051_186_548_718_852),
+            (3, [0.747_070_217_917_492, 0.126_464_891_041_254, 0.126_464_891_041_254], 0.023_687_735_870_688),
+            (3, [0.858_383_228_050_628, 0.070_808_385_974_686, 0.070_808_385_974_686], 0.013_289_775_690_021),
+            (3, [0.962_069_659_517_853, 0.018_965_170_241_073, 0.018_965_170_241_073], 0.004_748_916_608_192),
+            (6, [0.133_734_161_966_621, 0.261_311_371_140_087, 0.604_954_466_893_292], 0.038_550_072_599_593),
+            (6, [0.036_366_677_396_917, 0.388_046_767_090_269, 0.575_586_555_512_814], 0.027_215_814_320_624),
+            (6, [-0.010_174_883_126_571, 0.285_712_220_049_916, 0.724_462_663_076_655], 0.002_182_077_366_797),
+            (6, [0.036_843_869_875_878, 0.215_599_664_072_284, 0.747_556_466_051_838], 0.021_505_319_847_731),
+            (6, [0.012_459_809_331_199, 0.103_575_616_576_386, 0.883_964_574_092_415], 0.007_673_942_631_049),
+        ],
+        16 => &[
+            (1, [ONE_THIRD, ONE_THIRD, ONE_THIRD], 0.046_875_697_427_642),
+            (3, [0.005_238_916_103_123, 0.497_380_541_948_438, 0.497_380_541_948_438], 0.006_405_878_578_585),
+            (3, [0.173_061_122_901_295, 0.413_469_438_549_352, 0.413_469_438_549_352], 0.041_710_296_739_387),
+            (3, [0.059_082_801_866_017, 0.470_458_599_066_991, 0.470_458_599_066_991], 0.026_891_484_250_064),
+            (3, [0.518_892_500_060_958, 0.240_553_749_969_521, 0.240_553_749_969_521], 0.042_132_522_761_650),
+            (3, [0.704_068_411_554_854, 0.147_965_794_222_573, 0.147_965_794_222_573], 0.030_000_266_842_773),
+            (3, [0.849_069_624_685_052, 0.075_465_187_657_474, 0.075_465_187_657_474], 0.014_200_098_925_024),
+            (3, [0.966_807_194_753_950, 0.016_596_402_623_025, 0.016_596_402_623_025], 0.003_582_462_351_273),
+            (6, [0.103_575_692_245_252, 0.296_555_596_579_887, 0.599_868_711_174_861], 0.032_773_147_460_627),
+            (6, [0.020_083_411_655_416, 0.337_723_063_403_079, 0.642_193_524_941_505], 0.015_298_306_248_441),
+            (6, [-0.004_341_002_614_139, 0.204_748_281_642_812, 0.799_592_720_971_327], 0.002_386_244_192_839),
+            (6, [0.041_941_786_468_010, 0.189_358_492_130_623, 0.768_699_721_401_367], 0.019_084_792_755_899),
+            (6, [0.014_317_320_230_681, 0.085_283_615_682_657, 0.900_399_064_086_662], 0.006_850_054_546_542),
+        ],
+        17 => &[
+            (1, [ONE_THIRD, ONE_THIRD, ONE_THIRD], 0.033_437_199_290_803),
+            (3, [0.005_658_918_886_452, 0.497_170_540_556_774, 0.497_170_540_556_774], 0.005_093_415_440_507),
+            (3, [0.035_647_354_750_751, 0.482_176_322_624_625, 0.482_176_322_624_625], 0.014_670_864_527_638),
+            (3, [0.099_520_061_958_437, 0.450_239_969_020_782, 0.450_239_969_020_782], 0.024_350_878_353_672),
+            (3, [0.199_467_521_245_206, 0.400_266_239_377_397, 0.400_266_239_377_397], 0.031_107_550_868_969),
+            (3, [0.495_717_464_058_095, 0.252_141_267_970_953, 0.252_141_267_970_953], 0.031_257_111_218_620),
+            (3, [0.675_905_990_683_077, 0.162_047_004_658_461, 0.162_047_004_658_461], 0.024_815_654_339_665),
+            (3, [0.848_248_235_478_508, 0.075_875_882_260_746, 0.075_875_882_260_746], 0.014_056_073_070_557),
+            (3, [0.968_690_546_064_356, 0.015_654_726_967_822, 0.015_654_726_967_822], 0.003_194_676_173_779),
+            (6, [0.010_186_928_826_919, 0.334_319_867_363_658, 0.655_493_203_809_423], 0.008_119_655_318_993),
+            (6, [0.135_440_871_671_036, 0.292_221_537_796_944, 0.572_337_590_532_020], 0.026_805_742_283_163),
+            (6, [0.054_423_924_290_583, 0.319_574_885_423_190, 0.626_001_190_286_227], 0.018_459_993_210_822),
+            (6, [0.012_868_560_833_637, 0.190_704_224_192_292, 0.796_427_214_974_071], 0.008_476_868_534_328),
+            (6, [0.067_165_782_413_524, 0.180_483_211_648_746, 0.752_351_005_937_730], 0.018_292_796_770_025),
+            (6, [0.014_663_182_224_828, 0.080_711_313_679_564, 0.904_625_504_095_608], 0.006_665_632_004_165),
+        ],
+        18 => &[
+            (1, [ONE_THIRD, ONE_THIRD, ONE_THIRD], 0.030_809_939_937_647),
+            (3, [0.013_310_382_738_157, 0.493_344_808_630_921, 0.493_344_808_630_921], 0.009_072_436_679_404),
+            (3, [0.061_578_811_516_086, 0.469_210_594_241_957, 0.469_210_594_241_957], 0.018_761_316_939_594),
+            (3, [0.127_437_208_225_989, 0.436_281_395_887_006, 0.436_281_395_887_006], 0.019_441_097_985_477),
+            (3, [0.210_307_658_653_168, 0.394_846_170_673_416, 0.394_846_170_673_416], 0.027_753_948_610_810),
+            (3, [0.500_410_862_393_686, 0.249_794_568_803_157, 0.249_794_568_803_157], 0.032_256_225_351_457),
+            (3, [0.677_135_612_512_315, 0.161_432_193_743_843, 0.161_432_193_743_843], 0.025_074_032_616_922),
+            (3, [0.846_803_545_029_257, 0.076_598_227_485_371, 0.076_598_227_485_371], 0.015_271_927_971_832),
+            (3, [0.951_495_121_293_100, 0.024_252_439_353_450, 0.024_252_439_353_450], 0.006_793_922_022_963),
+            (3, [0.913_707_265_566_071, 0.043_146_367_216_965, 0.043_146_367_216_965], -0.002_223_098_729_920),
+            (6, [0.008_430_536_202_420, 0.358_911_494_940_944, 0.632_657_968_856_636], 0.006_331_914_076_406),
+            (6, [0.131_186_551_737_188, 0.294_402_476_751_957, 0.574_410_971_510_855], 0.027_257_538_049_138),
+            (6, [0.050_203_151_565_675, 0.325_017_801_641_814, 0.624_779_046_792_511], 0.017_676_785_649_465),
+            (6, [0.066_329_263_810_916, 0.184_737_559_666_046, 0.748_933_176_523_038], 0.018_379_484_638_070),
+            (6, [0.011_996_194_566_236, 0.218_796_800_013_321, 0.769_207_005_420_443], 0.008_104_732_808_192),
+            (6, [0.014_858_100_590_125, 0.101_179_597_136_408, 0.883_962_302_273_467], 0.007_634_129_070_725),
+            (6, [-0.035_222_015_287_949, 0.020_874_755_282_586, 1.014_347_260_005_363], 0.000_046_187_660_794),
+        ],
+        19 => &[
+            (1, [ONE_THIRD, ONE_THIRD, ONE_THIRD], 0.032_906_331_388_919),
+            (3, [0.020_780_025_853_987, 0.489_609_987_073_006, 0.489_609_987_073_006], 0.010_330_731_891_272),
+            (3, [0.090_926_214_604_215, 0.454_536_892_697_893, 0.454_536_892_697_893], 0.022_387_247_263_016),
+            (3, [0.197_166_638_701_138, 0.401_416_680_649_431, 0.401_416_680_649_431], 0.030_266_125_869_468),
+            (3, [0.488_896_691_193_805, 0.255_551_654_403_098, 0.255_551_654_403_098], 0.030_490_967_802_198),
+            (3, [0.645_844_115_695_741, 0.177_077_942_152_130, 0.177_077_942_152_130], 0.024_159_212_741_641),
+            (3, [0.779_877_893_544_096, 0.110_061_053_227_952, 0.110_061_053_227_952], 0.016_050_803_586_801),
+            (3, [0.888_942_751_496_321, 0.055_528_624_251_840, 0.055_528_624_251_840], 0.008_084_580_261_784),
+            (3, [0.974_756_272_445_543, 0.012_621_863_777_229, 0.012_621_863_777_229], 0.002_079_362_027_485),
+            (6, [0.003_611_417_848_412, 0.395_754_787_356_943, 0.600_633_794_794_645], 0.003_884_876_904_981),
+            (6, [0.134_466_754_530_780, 0.307_929_983_880_436, 0.557_603_261_588_784], 0.025_574_160_612_022),
+            (6, [0.014_446_025_776_115, 0.264_566_948_406_520, 0.720_987_025_817_365], 0.008_880_903_573_338),
+            (6, [0.046_933_578_838_178, 0.358_539_352_205_951, 0.594_527_068_955_871], 0.016_124_546_761_731),
+            (6, [0.002_861_120_350_567, 0.157_807_405_968_595, 0.839_331_473_680_838], 0.002_491_941_817_491),
+            (6, [0.223_861_424_097_916, 0.075_050_596_975_911, 0.701_087_978_926_173], 0.018_242_840_118_951),
+            (6, [0.034_647_074_816_760, 0.142_421_601_113_383, 0.822_931_324_069_857], 0.010_258_563_736_199),
+            (6, [0.010_161_119_296_278, 0.065_494_628_082_938, 0.924_344_252_620_784], 0.003_799_928_855_302),
+        ],
+        20 => &[
+            (1, [ONE_THIRD, ONE_THIRD, ONE_THIRD], 0.033_057_055_541_624),
+            (3, [-0.001_900_928_704_400, 0.500_950_464_352_200, 0.500_950_464_352_200], 0.000_867_019_185_663),
+            (3, [0.023_574_084_130_543, 0.488_212_957_934_729, 0.488_212_957_934_729], 0.011_660_052_716_448),
+            (3, [0.089_726_636_099_435, 0.455_136_681_950_283, 0.455_136_681_950_283], 0.022_876_936_356_421),
+            (3, [0.196_007_481_363_421, 0.401_996_259_318_289, 0.401_996_259_318_289], 0.030_448_982_673_938),
+            (3, [0.488_214_180_481_157, 0.255_892_909_759_421, 0.255_892_909_759_421], 0.030_624_891_725_355),
+            (3, [0.647_023_488_009_788, 0.176_488_255_995_106, 0.176_488_255_995_106], 0.024_368_057_676_800),
+            (3, [0.791_658_289_326_483, 0.104_170_855_336_758, 0.104_170_855_336_758], 0.015_997_432_032_024),
+            (3, [0.893_862_072_318_140, 0.053_068_963_840_930, 0.053_068_963_840_930], 0.007_698_301_815_602),
+            (3, [0.916_762_569_607_942, 0.041_618_715_196_029, 0.041_618_715_196_029], -0.000_632_060_497_488),
+            (3, [0.976_836_157_186_356, 0.011_581_921_406_822, 0.011_581_921_406_822], 0.001_751_134_301_193),
+            (6, [0.048_741_583_664_839, 0.344_855_770_229_001, 0.606_402_646_106_160], 0.016_465_839_189_576),
+            (6, [0.006_314_115_948_605, 0.377_843_269_594_854, 0.615_842_614_456_541], 0.004_839_033_540_485),
+            (6, [0.134_316_520_547_348, 0.306_635_479_062_357, 0.559_048_000_390_295], 0.025_804_906_534_650),
+            (6, [0.013_973_893_962_392, 0.249_419_362_774_742, 0.736_606_743_262_866], 0.008_471_091_054_441),
+            (6, [0.075_549_132_909_764, 0.212_775_724_802_802, 0.711_675_142_287_434], 0.018_354_914_106_280),
+            (6, [-0.008_368_153_208_227, 0.146_965_436_053_239, 0.861_402_717_154_988], 0.000_704_404_677_908),
+            (6, [0.026_686_063_258_714, 0.137_726_978_828_923, 0.835_586_957_912_363], 0.010_112_684_927_462),
+            (6, [0.010_547_719_294_141, 0.059_696_109_149_007, 0.929_756_171_556_852], 0.003_573_909_385_950),
+        ],
+        _ => {
+            return Err(IntegrationError::UnsupportedOrder { rule: "Dunavant", order })
+        }
+    })
 }
 
+/// Integrates polynomials of degree `order` exactly using the rules in [`dunavant_orbits()`]
+///
+/// The weights are scaled with the signed area, so clockwise triangles give negative integrals.
 pub struct DunavantIntegrator {
     integration_order: usize,
 }
 
 impl DunavantIntegrator {
-    pub fn new(order: usize) -> Self {
-        Self {
+    /// The order must be between 1 and 20, otherwise [`IntegrationError::UnsupportedOrder`].
+    pub fn new(order: usize) -> Result<Self> {
+        dunavant_orbits(order)?;
+        Ok(Self {
             integration_order: order,
-        }
-    }
-    fn get_integration_points_multiplicativity(&self) -> Array1<usize> {
-        match self.integration_order {
-            1 => {
-                array![1]
-            }
-            2 => {
-                array![3]
-            }
-            3 => {
-                array![1, 3]
-            }
-            _ => {
-                unimplemented!("See the corresponding paper")
-            }
-        }
+        })
     }
-    fn get_integration_points(&self) -> Array2<f64> {
-        match self.integration_order {
+}
+
+/// Integrates over the domain given by `transformation` with a symmetric rule given as orbits.
+/// Every orbit is expanded into its 1, 3 or 6 permutations of the generating point.
+pub(crate) fn integrate_orbits<T: SimplexFunction<2>>(
+    orbits: &[Orbit],
+    transformation: &Array2<f64>,
+    func: &Box<T>,
+    simplex: &Simplex2D,
+) -> Result<T::Return> {
+    check_shape(transformation, [3, 3])?;
+    let jacobi = array![[1., 0.], [0., 1.], [-1., -1.]];
+
+    let real_jacobi = simplex.get_points().dot(transformation);
+    let real_jacobi = real_jacobi.dot(&jacobi);
+    // Gleiches Vorzeichen wie Simplex2D::get_volume()
+    let determinant = 0.5 * det2x2(&real_jacobi);
+    let mut result = func.additive_neutral_element();
+    for &(multiplicativity, point, weight) in orbits {
+        match multiplicativity {
             1 => {
-                array![[
-                    0.333_333_333_333_333,
-                    0.333_333_333_333_333,
-                    1. - 2. * 0.333_333_333_333_333
-                ]]
-            }
-            2 => {
-                array![[
-                    0.666_666_666_666_667,
-                    0.166_666_666_666_667,
-                    1. - 0.666_666_666_666_667 - 0.166_666_666_666_667
-                ]]
+                let integration_point = transformation.dot(&Array1::from(point.to_vec()));
+                let mut point = func.function_vec(&integration_point, simplex);
+                point *= determinant * weight;
+                result.add_assign(&point);
             }
             3 => {
-                array![
-                    [
-                        0.333_333_333_333_333,
-                        0.333_333_333_333_333,
-                        1. - 2. * 0.333_333_333_333_333
-                    ],
-                    [0.6, 0.2, 0.2]
-                ]
-            }
-            _ => {
-                unimplemented!("See the corresponding paper")
-            }
-        }
-    }
-    fn get_integration_weights(&self) -> Array1<f64> {
-        match self.integration_order {
-            1 => {
-                array![1.]
-            }
-            2 => {
-                array![0.333_333_333_333_333]
+                let perm_points = array![
+                    [point[0], point[1], point[2]],
+                    [point[2], point[0], point[1]],
+                    [point[1], point[2], point[0]]
+                ];
+                let integration_points = transformation.dot(&perm_points);
+                for integration_point in integration_points.columns() {
+                    let mut point = func.function_vec(&integration_point.into_owned(), simplex);
+                    point *= determinant * weight;
+                    result.add_assign(&point);
+                }
             }
-            3 => {
-                array![-0.562_5, 0.520_833_333_333_333]
+            6 => {
+                let perm_points = array![
+                    [point[0], point[1], point[2]],
+                    [point[1], point[0], point[2]],
+                    [point[2], point[1], point[0]],
+                    [point[1], point[2], point[0]],
+                    [point[0], point[2], point[1]],
+                    [point[2], point[0], point[1]]
+                ];
+                let integration_points = transformation.dot(&perm_points.t());
+                for integration_point in integration_points.columns() {
+                    let mut point = func.function_vec(&integration_point.into_owned(), simplex);
+                    point *= determinant * weight;
+                    result.add_assign(&point);
+                }
             }
             _ => {
-                unimplemented!("See the corresponding paper")
+                unreachable!("an orbit has 1, 3 or 6 points")
             }
         }
     }
+    return Ok(result);
 }
 
-impl Simplex2DIntegrator<IntegratorDummy> for DunavantIntegrator {
+impl SimplexIntegrator<2, IntegratorDummy> for DunavantIntegrator {
     fn dupe(&self) -> Self {
         Self {
             integration_order: self.integration_order,
         }
     }
 
-    fn integrate_over_domain<T: Simplex2DFunction>(
+    fn integrate_over_domain<T: SimplexFunction<2>>(
         &self,
         transformation: &Array2<f64>,
         func: &Box<T>,
         simplex: &Simplex2D,
         _cache_data: &mut IntegratorDummy,
-    ) -> T::Return {
-        //let order = self.integration_order;
-        let points = self.get_integration_points();
-        let multiplicativities = self.get_integration_points_multiplicativity();
-        let weights = self.get_integration_weights();
-
-        let num_points = points.nrows();
-
-        let jacobi = array![[1., 0.], [0., 1.], [-1., -1.]];
-
-        let real_jacobi = simplex.get_points().dot(transformation);
-        let real_jacobi = real_jacobi.dot(&jacobi);
-        let determinant = 0.5 * det2x2(&real_jacobi);
-        let mut result = func.additive_neutral_element();
-        for i in 0..num_points {
-            let point = points.row(i);
-            let multiplicativity = multiplicativities[i];
-            let weight = weights[i];
-
-            match multiplicativity {
-                1 => {
-                    let integration_point = transformation.dot(&point);
-                    let mut point = func.function_vec(&integration_point, simplex);
-                    point *= determinant * weight;
-                    result.add_assign(&point);
-                }
-                3 => {
-                    let perm_points = array![
-                        [point[0], point[1], point[2]],
-                        [point[2], point[0], point[1]],
-                        [point[1], point[2], point[0]]
-                    ];
-                    let integration_points = transformation.dot(&perm_points);
-                    for integration_point in integration_points.columns() {
-                        let mut point = func.function_vec(&integration_point.into_owned(), simplex);
-                        point *= determinant * weight;
-                        result.add_assign(&point);
-                    }
-                }
-                6 => {
-                    let perm_points = array![
-                        [point[0], point[1], point[2]],
-                        [point[1], point[0], point[2]],
-                        [point[2], point[1], point[0]],
-                        [point[1], point[2], point[0]],
-                        [point[0], point[2], point[1]],
-                        [point[2], point[0], point[1]]
-                    ];
-                    let integration_points = transformation.dot(&perm_points);
-                    for integration_point in integration_points.columns() {
-                        let mut point = func.function_vec(&integration_point.into_owned(), simplex);
-                        point *= determinant * weight;
-                        result.add_assign(&point);
-                    }
-                }
-                _ => {
-                    panic!()
-                }
-            }
-        }
-        return result;
+    ) -> Result<T::Return> {
+        integrate_orbits(
+            dunavant_orbits(self.integration_order)?,
+            transformation,
+            func,
+            simplex,
+        )
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use ndarray::array;
+
     use crate::common::IntegratorDummy;
+    use crate::integration_2d::domain::{
+        ResultTypeWrapper, Simplex2D, Simplex2DFunction, SimplexIntegrator,
+    };
     use crate::integration_2d::integrators::DunavantIntegrator;
     use crate::integrator_tests;
 
     integrator_tests! {
-        order1: DunavantIntegrator: DunavantIntegrator::new(1), IntegratorDummy: IntegratorDummy::get(),
-        order2: DunavantIntegrator: DunavantIntegrator::new(2), IntegratorDummy: IntegratorDummy::get(),
-        order3: DunavantIntegrator: DunavantIntegrator::new(2), IntegratorDummy: IntegratorDummy::get(),
+        order1: DunavantIntegrator: DunavantIntegrator::new(1).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order2: DunavantIntegrator: DunavantIntegrator::new(2).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order3: DunavantIntegrator: DunavantIntegrator::new(3).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order6: DunavantIntegrator: DunavantIntegrator::new(6).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order10: DunavantIntegrator: DunavantIntegrator::new(10).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order20: DunavantIntegrator: DunavantIntegrator::new(20).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+    }
+
+    /// xi1^i * xi2^j * xi3^k
+    struct BarycentricMonomial(i32, i32, i32);
+
+    impl Simplex2DFunction for BarycentricMonomial {
+        type Return = ResultTypeWrapper<f64>;
+        fn function(&self, xi1: f64, xi2: f64, xi3: f64, _simplex: &Simplex2D) -> Self::Return {
+            ResultTypeWrapper::new(xi1.powi(self.0) * xi2.powi(self.1) * xi3.powi(self.2))
+        }
+    }
+
+    fn factorial(n: i32) -> f64 {
+        (1..=n).map(|i| i as f64).product()
+    }
+
+    #[test]
+    fn monomials_exact_up_to_order() {
+        let sim = Simplex2D::new_from_points(&array![1., 1.], &array![3., 1.5], &array![1.5, 2.]);
+        // Doppelte Fläche des Simplex
+        let area2 = 2. * 1. - 0.5 * 0.5;
+        for order in 1..=20 {
+            let inte = DunavantIntegrator::new(order).unwrap();
+            for degree in 0..=order as i32 {
+                for i in 0..=degree {
+                    for j in 0..=(degree - i) {
+                        let k = degree - i - j;
+                        let func = Box::new(BarycentricMonomial(i, j, k));
+                        let result = inte
+                            .integrate_simplex(&func, &sim, &mut IntegratorDummy::get())
+                            .unwrap()
+                            .get();
+                        let exact = area2 * factorial(i) * factorial(j) * factorial(k)
+                            / factorial(degree + 2);
+                        assert!(
+                            ((result - exact) / exact).abs() < 1e-12,
+                            "Order {}: xi1^{} xi2^{} xi3^{}, Expected: {}, Actual: {}",
+                            order,
+                            i,
+                            j,
+                            k,
+                            exact,
+                            result
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reorder_keeps_barycentric_coordinates() {
+        use crate::integration_2d::domain::OrientationPolicy;
+
+        // Im Uhrzeigersinn, xi1 gehört weiterhin zur ersten Ecke
+        let sim = Simplex2D::new_from_points(&array![0., 0.], &array![0., 1.], &array![1., 0.]);
+        let inte = DunavantIntegrator::new(4).unwrap();
+        let func = Box::new(BarycentricMonomial(3, 1, 0));
+        let exact = factorial(3) / factorial(6);
+        let signed = inte
+            .integrate_simplex(&func, &sim, &mut IntegratorDummy::get())
+            .unwrap()
+            .get();
+        assert!((signed + exact).abs() < 1e-14);
+        let reordered = inte
+            .integrate_simplex_oriented(
+                &func,
+                &sim,
+                &mut IntegratorDummy::get(),
+                OrientationPolicy::Reorder,
+            )
+            .unwrap()
+            .get();
+        assert!((reordered - exact).abs() < 1e-14);
+    }
+
+    #[test]
+    fn unsupported_order() {
+        use crate::error::IntegrationError;
+
+        assert!(matches!(
+            DunavantIntegrator::new(21),
+            Err(IntegrationError::UnsupportedOrder {
+                rule: "Dunavant",
+                order: 21
+            })
+        ));
+        assert!(DunavantIntegrator::new(0).is_err());
     }
 }
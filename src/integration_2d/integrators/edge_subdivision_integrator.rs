@@ -1,25 +1,37 @@
 use ndarray::{array, Array2};
 
-use crate::common::IntegratorDummy;
+use crate::common::{ordered_map, IntegratorDummy};
+use crate::error::{check_shape, IntegrationError, Result};
 use crate::integration_2d::domain::{
-    det3x3, Simplex2D, Simplex2DFunction, Simplex2DIntegrator, Simplex2DResultType,
+    Simplex2D, SimplexFunction, SimplexIntegrator, SimplexResultType,
 };
+use crate::simplex::{CompositeCache, IntegratorCache};
 
-pub struct EdgeSubdivisionIntegrator<I: Simplex2DIntegrator<IntegratorDummy>> {
+/// Divides every edge into `order` parts and integrates the subtriangles with the base integrator.
+/// The subtriangles keep the orientation of the triangle, so the sign is the one of the base
+/// integrator.
+pub struct EdgeSubdivisionIntegrator<I> {
     base_integrator: I,
     order: usize,
 }
 
-impl<I: Simplex2DIntegrator<IntegratorDummy>> EdgeSubdivisionIntegrator<I> {
-    pub fn new(base_integrator: I, order: usize) -> Self {
-        return EdgeSubdivisionIntegrator {
+impl<I> EdgeSubdivisionIntegrator<I> {
+    /// Every edge is divided into `order` parts, so the order must be at least 1.
+    pub fn new(base_integrator: I, order: usize) -> Result<Self> {
+        if order == 0 {
+            return Err(IntegrationError::UnsupportedOrder {
+                rule: "edge subdivision",
+                order,
+            });
+        }
+        return Ok(EdgeSubdivisionIntegrator {
             base_integrator,
             order,
-        };
+        });
     }
 }
 
-impl<I: Simplex2DIntegrator<IntegratorDummy>> Simplex2DIntegrator<IntegratorDummy>
+impl<I: SimplexIntegrator<2, IntegratorDummy>> SimplexIntegrator<2, IntegratorDummy>
     for EdgeSubdivisionIntegrator<I>
 {
     fn dupe(&self) -> Self {
@@ -29,63 +41,86 @@ impl<I: Simplex2DIntegrator<IntegratorDummy>> Simplex2DIntegrator<IntegratorDumm
         };
     }
 
-    fn integrate_over_domain<T: Simplex2DFunction>(
+    fn integrate_over_domain<T: SimplexFunction<2>>(
         &self,
         transformation: &Array2<f64>,
         func: &Box<T>,
         simplex: &Simplex2D,
-        cached_data: &mut IntegratorDummy,
-    ) -> T::Return {
-        let order = self.order;
-        let order_fl = order as f64;
+        _cached_data: &mut IntegratorDummy,
+    ) -> Result<T::Return> {
+        // Der Basisintegrator hat keinen Zustand, die Caches der Kinder sind leer
+        SimplexIntegrator::<2, CompositeCache<IntegratorDummy>>::integrate_over_domain(
+            self,
+            transformation,
+            func,
+            simplex,
+            &mut CompositeCache::new_cache(),
+        )
+    }
+}
 
+/// With a [`CompositeCache`] the base integrator keeps its cache per subsimplex, so it may be
+/// e.g. a hierarchic integrator.
+impl<C: IntegratorCache, I: SimplexIntegrator<2, C>> SimplexIntegrator<2, CompositeCache<C>>
+    for EdgeSubdivisionIntegrator<I>
+{
+    fn dupe(&self) -> Self {
+        Self {
+            base_integrator: self.base_integrator.dupe(),
+            order: self.order,
+        }
+    }
+
+    fn integrate_over_domain<T: SimplexFunction<2>>(
+        &self,
+        transformation: &Array2<f64>,
+        func: &Box<T>,
+        simplex: &Simplex2D,
+        cached_data: &mut CompositeCache<C>,
+    ) -> Result<T::Return> {
+        check_shape(transformation, [3, 3])?;
+        let sub_transformations = sub_transformations(self.order, transformation);
+        let sub_caches = cached_data.sub_caches_mut(sub_transformations.len());
+        // Die Teilsimplizes werden (evtl. parallel) integriert und in ihrer Reihenfolge aufsummiert
+        let sub_results = ordered_map(
+            sub_transformations
+                .into_iter()
+                .zip(sub_caches.iter_mut())
+                .collect(),
+            |(transformation, sub_cache)| {
+                self.base_integrator.integrate_over_domain(
+                    &transformation,
+                    func,
+                    simplex,
+                    sub_cache,
+                )
+            },
+        )
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?;
         let mut result = func.additive_neutral_element();
+        for sub_result in sub_results {
+            result.add_assign(&sub_result);
+        }
+        Ok(result)
+    }
+}
 
-        let order = order - 1;
-        for i in 0..=order {
-            for j in 0..=(order - i) {
-                let k = order - i - j;
-                //println!("{},{},{}", j, i, k);
-                let (i0, i1, i2) = (j, i, k);
-                // 0,-1,1
-                let i0 = i0 + 1;
-                if i1 != 0 {
-                    let ch_transformation = array![
-                        [
-                            i0 as f64 / order_fl,
-                            i1 as f64 / order_fl,
-                            i2 as f64 / order_fl
-                        ],
-                        [
-                            (i0 - 1) as f64 / order_fl,
-                            i1 as f64 / order_fl,
-                            (i2 + 1) as f64 / order_fl
-                        ],
-                        [
-                            i0 as f64 / order_fl,
-                            (i1 - 1) as f64 / order_fl,
-                            (i2 + 1) as f64 / order_fl
-                        ],
-                    ];
-                    let ch_transformation = ch_transformation.reversed_axes();
-                    let transformation = transformation.dot(&ch_transformation);
-
-                    result.add_assign(&{
-                        let r = self.base_integrator.integrate_over_domain(
-                            &transformation,
-                            func,
-                            simplex,
-                            &mut IntegratorDummy,
-                        );
-                        //println!("0: {}", det3x3(&transformation));
-                        r
-                    })
-                    //println!("{},{},{}", i0,i1,i2);
-                    //println!("{},{},{}", i0,i1-1,i2+1);
-                    ////
-                    //println!("{},{},{}", i0-1,i1,i2+1);
-                }
-                // -1,1,0
+/// The transformations of the `order^2` subtriangles relative to the `transformation`.
+pub(crate) fn sub_transformations(order: usize, transformation: &Array2<f64>) -> Vec<Array2<f64>> {
+    let order_fl = order as f64;
+
+    // Zuerst werden alle Teilsimplizes bestimmt
+    let mut sub_transformations = Vec::new();
+
+    let order = order - 1;
+    for i in 0..=order {
+        for j in 0..=(order - i) {
+            let k = order - i - j;
+            let (i0, i1, i2) = (j, i, k);
+            // 0,-1,1
+            let i0 = i0 + 1;
+            if i1 != 0 {
                 let ch_transformation = array![
                     [
                         i0 as f64 / order_fl,
@@ -94,54 +129,126 @@ impl<I: Simplex2DIntegrator<IntegratorDummy>> Simplex2DIntegrator<IntegratorDumm
                     ],
                     [
                         (i0 - 1) as f64 / order_fl,
-                        (i1 + 1) as f64 / order_fl,
-                        (i2) as f64 / order_fl
+                        i1 as f64 / order_fl,
+                        (i2 + 1) as f64 / order_fl
                     ],
                     [
-                        (i0 - 1) as f64 / order_fl,
-                        i1 as f64 / order_fl,
+                        i0 as f64 / order_fl,
+                        (i1 - 1) as f64 / order_fl,
                         (i2 + 1) as f64 / order_fl
                     ],
                 ];
                 let ch_transformation = ch_transformation.reversed_axes();
                 let transformation = transformation.dot(&ch_transformation);
-                result.add_assign(&{
-                    let r = self.base_integrator.integrate_over_domain(
-                        &transformation,
-                        func,
-                        simplex,
-                        &mut IntegratorDummy,
-                    );
-                    //println!("0: {}", det3x3(&transformation));
-                    r
-                })
-                //println!("{},{},{}", i0,i1,i2);
-                //println!("{},{},{}", i0-1,i1+1,i2+0);
-
-                //println!("{},{},{}", i0-1,i1,i2+1);
+
+                sub_transformations.push(transformation);
             }
+            // -1,1,0
+            let ch_transformation = array![
+                [
+                    i0 as f64 / order_fl,
+                    i1 as f64 / order_fl,
+                    i2 as f64 / order_fl
+                ],
+                [
+                    (i0 - 1) as f64 / order_fl,
+                    (i1 + 1) as f64 / order_fl,
+                    (i2) as f64 / order_fl
+                ],
+                [
+                    (i0 - 1) as f64 / order_fl,
+                    i1 as f64 / order_fl,
+                    (i2 + 1) as f64 / order_fl
+                ],
+            ];
+            let ch_transformation = ch_transformation.reversed_axes();
+            let transformation = transformation.dot(&ch_transformation);
+            sub_transformations.push(transformation);
         }
-        return result;
     }
+    sub_transformations
 }
 
 #[cfg(test)]
 mod tests {
     use crate::common::IntegratorDummy;
     use crate::integration_2d::integrators::{
-        DunavantIntegrator, EdgeSubdivisionIntegrator, Quadrilateral2DIntegrator,
+        DunavantIntegrator, EdgeSubdivisionIntegrator, Hierarchic2DIntegrator,
+        Hierarchic2DIntegratorData, LynessIntegrator, Quadrilateral2DIntegrator,
     };
     use crate::integrator_tests;
+    use crate::simplex::CompositeCache;
 
     integrator_tests! {
-        order2_quad: EdgeSubdivisionIntegrator<Quadrilateral2DIntegrator>: EdgeSubdivisionIntegrator::new(Quadrilateral2DIntegrator::new(1),2), IntegratorDummy: IntegratorDummy::get(),
-        order3_quad: EdgeSubdivisionIntegrator<Quadrilateral2DIntegrator>: EdgeSubdivisionIntegrator::new(Quadrilateral2DIntegrator::new(1),3), IntegratorDummy: IntegratorDummy::get(),
-        order4_quad: EdgeSubdivisionIntegrator<Quadrilateral2DIntegrator>: EdgeSubdivisionIntegrator::new(Quadrilateral2DIntegrator::new(1),4), IntegratorDummy: IntegratorDummy::get(),
-
-        order2_dunavant: EdgeSubdivisionIntegrator<DunavantIntegrator>: EdgeSubdivisionIntegrator::new(DunavantIntegrator::new(1),2), IntegratorDummy: IntegratorDummy::get(),
-        order3_dunavant: EdgeSubdivisionIntegrator<DunavantIntegrator>: EdgeSubdivisionIntegrator::new(DunavantIntegrator::new(2),3), IntegratorDummy: IntegratorDummy::get(),
-        order4_dunavant: EdgeSubdivisionIntegrator<DunavantIntegrator>: EdgeSubdivisionIntegrator::new(DunavantIntegrator::new(3),4), IntegratorDummy: IntegratorDummy::get(),
-        order5_dunavant: EdgeSubdivisionIntegrator<DunavantIntegrator>: EdgeSubdivisionIntegrator::new(DunavantIntegrator::new(3),5), IntegratorDummy: IntegratorDummy::get(),
-        order6_dunavant: EdgeSubdivisionIntegrator<DunavantIntegrator>: EdgeSubdivisionIntegrator::new(DunavantIntegrator::new(3),6), IntegratorDummy: IntegratorDummy::get(),
+        order2_quad: EdgeSubdivisionIntegrator<Quadrilateral2DIntegrator>: EdgeSubdivisionIntegrator::new(Quadrilateral2DIntegrator::new(1).unwrap(),2).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order3_quad: EdgeSubdivisionIntegrator<Quadrilateral2DIntegrator>: EdgeSubdivisionIntegrator::new(Quadrilateral2DIntegrator::new(1).unwrap(),3).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order4_quad: EdgeSubdivisionIntegrator<Quadrilateral2DIntegrator>: EdgeSubdivisionIntegrator::new(Quadrilateral2DIntegrator::new(1).unwrap(),4).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+
+        order2_dunavant: EdgeSubdivisionIntegrator<DunavantIntegrator>: EdgeSubdivisionIntegrator::new(DunavantIntegrator::new(1).unwrap(),2).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order3_dunavant: EdgeSubdivisionIntegrator<DunavantIntegrator>: EdgeSubdivisionIntegrator::new(DunavantIntegrator::new(2).unwrap(),3).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order4_dunavant: EdgeSubdivisionIntegrator<DunavantIntegrator>: EdgeSubdivisionIntegrator::new(DunavantIntegrator::new(3).unwrap(),4).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order5_dunavant: EdgeSubdivisionIntegrator<DunavantIntegrator>: EdgeSubdivisionIntegrator::new(DunavantIntegrator::new(3).unwrap(),5).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order6_dunavant: EdgeSubdivisionIntegrator<DunavantIntegrator>: EdgeSubdivisionIntegrator::new(DunavantIntegrator::new(3).unwrap(),6).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+
+        order2_lyness: EdgeSubdivisionIntegrator<LynessIntegrator>: EdgeSubdivisionIntegrator::new(LynessIntegrator::new(2).unwrap(),2).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order3_lyness: EdgeSubdivisionIntegrator<LynessIntegrator>: EdgeSubdivisionIntegrator::new(LynessIntegrator::new(3).unwrap(),3).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order4_lyness: EdgeSubdivisionIntegrator<LynessIntegrator>: EdgeSubdivisionIntegrator::new(LynessIntegrator::new(5).unwrap(),4).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+
+        nested: EdgeSubdivisionIntegrator<EdgeSubdivisionIntegrator<DunavantIntegrator>>: EdgeSubdivisionIntegrator::new(EdgeSubdivisionIntegrator::new(DunavantIntegrator::new(1).unwrap(),2).unwrap(),2).unwrap(), CompositeCache<IntegratorDummy>: CompositeCache::new_cache(),
+        hierarchic_children: EdgeSubdivisionIntegrator<Hierarchic2DIntegrator<DunavantIntegrator>>: EdgeSubdivisionIntegrator::new(Hierarchic2DIntegrator::new(DunavantIntegrator::new(1).unwrap(),false,1e-2),2).unwrap(), CompositeCache<Hierarchic2DIntegratorData>: CompositeCache::new_cache(),
+    }
+
+    #[test]
+    fn children_keep_their_caches() {
+        use ndarray::array;
+
+        use crate::integration_2d::domain::{Simplex2D, SimplexIntegrator};
+        use crate::integration_2d::functions::PyramidFunction;
+
+        let sim = Simplex2D::new_from_points(&array![0., 0.], &array![1., 0.], &array![0., 1.]);
+        let func = Box::new(PyramidFunction::new(0.2, 0.3, 0.5, 1.).unwrap());
+
+        // Zweimal halbiert ist dasselbe wie einmal geviertelt
+        let nested = EdgeSubdivisionIntegrator::new(
+            EdgeSubdivisionIntegrator::new(DunavantIntegrator::new(2).unwrap(), 2).unwrap(),
+            2,
+        )
+        .unwrap()
+        .integrate_simplex(
+            &func,
+            &sim,
+            &mut CompositeCache::<IntegratorDummy>::new_cache(),
+        )
+        .unwrap()
+        .get();
+        let direct = EdgeSubdivisionIntegrator::new(DunavantIntegrator::new(2).unwrap(), 4)
+            .unwrap()
+            .integrate_simplex(&func, &sim, &mut IntegratorDummy::get())
+            .unwrap()
+            .get();
+        assert!((nested - direct).abs() < 1e-14);
+
+        // Jedes Teildreieck hat seinen eigenen Baum, der beim nächsten Aufruf wiederverwendet wird
+        let inte = EdgeSubdivisionIntegrator::new(
+            Hierarchic2DIntegrator::new(DunavantIntegrator::new(2).unwrap(), false, 1e-6),
+            2,
+        )
+        .unwrap();
+        let mut cache = CompositeCache::<Hierarchic2DIntegratorData>::new_cache();
+        let result = inte
+            .integrate_simplex(&func, &sim, &mut cache)
+            .unwrap()
+            .get();
+        assert_eq!(cache.sub_caches().len(), 4);
+        let sizes: Vec<usize> = cache.sub_caches().iter().map(|c| c.tree_size()).collect();
+        assert!(sizes.iter().all(|&size| size > 1));
+        let cached = inte
+            .integrate_simplex(&func, &sim, &mut cache)
+            .unwrap()
+            .get();
+        assert_eq!(cached, result);
+        for (sub_cache, size) in cache.sub_caches().iter().zip(sizes) {
+            assert_eq!(sub_cache.tree_size(), size);
+        }
     }
 }
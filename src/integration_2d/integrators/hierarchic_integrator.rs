@@ -1,94 +1,437 @@
+use std::collections::{BinaryHeap, HashMap};
+use std::io::{Read, Write};
+
 use indextree::{Arena, NodeEdge, NodeId};
 use ndarray::prelude::*;
 
-use crate::common::IntegratorDummy;
+use crate::common::{ordered_map, IntegratorDummy};
+use crate::embedded::{EmbeddedIntegrator, EmbeddedResult};
+use crate::error::{check_shape, Result};
 use crate::integration_2d::domain::{
-    Simplex2D, Simplex2DFunction, Simplex2DIntegrator, Simplex2DResultType,
+    Simplex2D, SimplexFunction, SimplexIntegrator, SimplexResultType,
+};
+use crate::refinement::{
+    AbsoluteCriterion, EvaluationBudget, GlobalRefinement, LeafError, RefinementCriterion,
+    MAX_REFINEMENT_LEVEL,
 };
+use crate::simplex::{CompositeCache, IntegratorCache};
+use crate::tree_format::{TreeData, TreeNode};
 
-pub struct Hierarchic2DIntegrator<I: Simplex2DIntegrator<IntegratorDummy>> {
+/// The leafs are refined by the [`RefinementCriterion`] `R`, or globally, see [`Self::new_global()`].
+/// The subdomains keep the orientation, so the integral is signed like the one of the base integrator.
+/// With a [`Hierarchic2DIntegratorData<C>`] every node of the tree keeps a cache `C` of the base
+/// integrator, so the base integrator may itself be e.g. a hierarchic integrator.
+pub struct Hierarchic2DIntegrator<I, R: RefinementCriterion = AbsoluteCriterion> {
     base_integrator: I,
     consolidated: bool,
-    precision: f64,
+    criterion: R,
+    global: Option<GlobalRefinement>,
+    embedded: bool,
 }
 
-impl<I: Simplex2DIntegrator<IntegratorDummy>> Hierarchic2DIntegrator<I> {
+impl<I> Hierarchic2DIntegrator<I> {
+    /// Refines a leaf if the distance to its refinement is larger than `precision`.
     pub fn new(base_integrator: I, consolidated: bool, precision: f64) -> Self {
+        Self::new_with_criterion(
+            base_integrator,
+            consolidated,
+            AbsoluteCriterion::new(precision),
+        )
+    }
+
+    /// Always splits the leaf with the largest error estimate, see [`GlobalRefinement`].
+    /// A cached tree is reused as the starting point.
+    pub fn new_global(base_integrator: I, global: GlobalRefinement) -> Self {
+        Self {
+            base_integrator,
+            consolidated: false,
+            criterion: AbsoluteCriterion::new(global.tolerance),
+            global: Some(global),
+            embedded: false,
+        }
+    }
+}
+
+impl<I, R: RefinementCriterion> Hierarchic2DIntegrator<I, R> {
+    pub fn new_with_criterion(base_integrator: I, consolidated: bool, criterion: R) -> Self {
         Self {
             base_integrator,
             consolidated,
-            precision,
+            criterion,
+            global: None,
+            embedded: false,
+        }
+    }
+
+    /// Decides the refinement of a leaf with the embedded rule of the base integrator, so the
+    /// children are only integrated if the leaf is refined, see [`EmbeddedIntegrator`].
+    pub fn new_embedded(base_integrator: I, consolidated: bool, criterion: R) -> Self
+    where
+        I: EmbeddedIntegrator<2>,
+    {
+        Self {
+            base_integrator,
+            consolidated,
+            criterion,
+            global: None,
+            embedded: true,
+        }
+    }
+
+    /// The integral over the subdomain with the transformation `trans` relative to the simplex,
+    /// with the `cache` of the subdomain.
+    fn integrate_subdomain<C: IntegratorCache, T: SimplexFunction<2>>(
+        &self,
+        transformation: &Array2<f64>,
+        trans: &Array2<f64>,
+        func: &Box<T>,
+        simplex: &Simplex2D,
+        cache: &mut CompositeCache<C>,
+    ) -> Result<T::Return>
+    where
+        I: SimplexIntegrator<2, C>,
+    {
+        let child_transform = transformation.dot(trans);
+        self.base_integrator.integrate_over_domain(
+            &child_transform,
+            func,
+            simplex,
+            &mut cache.sub_caches_mut(1)[0],
+        )
+    }
+
+    /// [`Self::integrate_subdomain()`] with the integral of the embedded rule, if there is one.
+    fn integrate_subdomain_embedded<C: IntegratorCache, T: SimplexFunction<2>>(
+        &self,
+        transformation: &Array2<f64>,
+        trans: &Array2<f64>,
+        func: &Box<T>,
+        simplex: &Simplex2D,
+        cache: &mut CompositeCache<C>,
+    ) -> Result<Option<EmbeddedResult<T::Return>>>
+    where
+        I: SimplexIntegrator<2, C>,
+    {
+        let child_transform = transformation.dot(trans);
+        self.base_integrator.integrate_over_domain_embedded(
+            &child_transform,
+            func,
+            simplex,
+            &mut cache.sub_caches_mut(1)[0],
+        )
+    }
+
+    /// The integrals over the four children of the subdomain, in the order of [`CHILD_NUMBERS`],
+    /// each with a new cache.
+    fn integrate_children<C: IntegratorCache, T: SimplexFunction<2>>(
+        &self,
+        transformation: &Array2<f64>,
+        trans: &Array2<f64>,
+        func: &Box<T>,
+        simplex: &Simplex2D,
+    ) -> Result<Children<T::Return, C>>
+    where
+        I: SimplexIntegrator<2, C>,
+    {
+        let mut children = Children {
+            results: Vec::with_capacity(CHILD_NUMBERS.len()),
+            caches: Vec::with_capacity(CHILD_NUMBERS.len()),
+        };
+        for number in CHILD_NUMBERS {
+            // die temporäre transformationshierachie
+            let child_trans = child_transformation(trans, number);
+            let mut cache = CompositeCache::new_cache();
+            children.results.push(self.integrate_subdomain(
+                transformation,
+                &child_trans,
+                func,
+                simplex,
+                &mut cache,
+            )?);
+            children.caches.push(cache);
         }
+        Ok(children)
     }
-    fn subdivision_transformations() -> [Array2<f64>; 4] {
-        return [
-            array![
-                // S1
-                [1., 0.5, 0.5],
-                [0., 0.5, 0.],
-                [0., 0., 0.5]
-            ],
-            array![
-                // S2
-                [0., 0.0, 0.5],
-                [1., 0.5, 0.5],
-                [0., 0.5, 0.0]
-            ],
-            array![
-                // S3
-                [0., 0.5, 0.0],
-                [0., 0.0, 0.5],
-                [1., 0.5, 0.5]
-            ],
-            array![
-                // S4
-                [0.5, 0.5, 0.0],
-                [0., 0.5, 0.5],
-                [0.5, 0.0, 0.5]
-            ],
-        ];
-    }
-
-    fn get_transformation(parent_vector: &Vec<u8>) -> Array2<f64> {
-        let transformations = Hierarchic2DIntegrator::<I>::subdivision_transformations();
-        let mut result = array![[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
-        for i in 0..parent_vector.len() - 1 {
-            let current = parent_vector[i];
-            let current_transformation = &transformations[(current - 1) as usize];
-            result = current_transformation.dot(&result);
+
+    /// Globally adaptive integration: the leaf with the largest error estimate is split first.
+    /// Every leaf keeps the integrals over its children, the result is the sum of these.
+    fn integrate_global<C: IntegratorCache, T: SimplexFunction<2>>(
+        &self,
+        global: &GlobalRefinement,
+        transformation: &Array2<f64>,
+        func: &Box<T>,
+        simplex: &Simplex2D,
+        cached_data: &mut Hierarchic2DIntegratorData<C>,
+    ) -> Result<T::Return>
+    where
+        I: SimplexIntegrator<2, C>,
+    {
+        let tree = &mut cached_data.arena;
+        let root_node_id = cached_data.root_node_id;
+        let estimate = |coarse: &T::Return, children: &Children<T::Return, C>| {
+            let mut fine = func.additive_neutral_element();
+            for child in &children.results {
+                fine.add_assign(child);
+            }
+            coarse.distance(&fine)
+        };
+
+        // Zuerst werden alle vorhandenen Blätter geschätzt
+        let leafs = collect_leafs(tree, root_node_id);
+        let work = leafs
+            .iter()
+            .zip(take_caches(tree, &leafs))
+            .map(|(&id, cache)| (&tree[id].get().transformation, cache))
+            .collect();
+        let leaf_results = ordered_map(work, |(trans, mut cache)| {
+            let coarse =
+                self.integrate_subdomain(transformation, trans, func, simplex, &mut cache)?;
+            let children = self.integrate_children(transformation, trans, func, simplex)?;
+            Ok((coarse, children, cache))
+        })
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?;
+        let mut budget = EvaluationBudget::new(global);
+        budget.spend(leafs.len() * (1 + CHILD_NUMBERS.len()));
+        let mut children_results = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        let mut error_sum = 0.;
+        for (id, (coarse, children, cache)) in leafs.into_iter().zip(leaf_results) {
+            tree[id].get_mut().cache = cache;
+            let error = estimate(&coarse, &children);
+            error_sum += error;
+            queue.push(LeafError { error, id });
+            children_results.insert(id, children);
+        }
+
+        // Eine Teilung kostet die Integration aller Enkel
+        let split_evaluations = CHILD_NUMBERS.len() * CHILD_NUMBERS.len();
+        while error_sum > global.tolerance {
+            let Some(worst) = queue.pop() else {
+                break;
+            };
+            if tree[worst.id].get().level >= MAX_REFINEMENT_LEVEL {
+                // Das Blatt wird nicht mehr geteilt, sein Fehler bleibt in der Summe
+                continue;
+            }
+            if !budget.try_spend(split_evaluations) {
+                break;
+            }
+            error_sum -= worst.error;
+            let children = children_results.remove(&worst.id).unwrap();
+            let child_ids: Vec<NodeId> = CHILD_NUMBERS
+                .iter()
+                .zip(children.caches)
+                .map(|(&number, cache)| {
+                    let mut child = tree[worst.id].get().new_child(number);
+                    child.cache = cache;
+                    let child_id = tree.new_node(child);
+                    worst.id.append(child_id, tree);
+                    child_id
+                })
+                .collect();
+            let grandchildren_results = ordered_map(
+                child_ids
+                    .iter()
+                    .map(|&id| &tree[id].get().transformation)
+                    .collect(),
+                |trans| self.integrate_children(transformation, trans, func, simplex),
+            )
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+
+            // Die Kinder werden zu Blättern, ihr Integral ist schon bekannt
+            for ((id, coarse), grandchildren) in child_ids
+                .into_iter()
+                .zip(children.results)
+                .zip(grandchildren_results)
+            {
+                let error = estimate(&coarse, &grandchildren);
+                error_sum += error;
+                queue.push(LeafError { error, id });
+                children_results.insert(id, grandchildren);
+            }
+        }
+
+        // Aufsummiert wird in DFS Reihenfolge
+        let mut result = func.additive_neutral_element();
+        for id in collect_leafs(tree, root_node_id) {
+            for child_result in &children_results[&id].results {
+                result.add_assign(child_result);
+            }
         }
-        return result;
+        Ok(result)
     }
 }
 
-struct NodeData {
+/// The integrals over the children of a leaf, which are not yet in the tree, and the caches of
+/// the base integrator for them.
+struct Children<R, C> {
+    results: Vec<R>,
+    caches: Vec<CompositeCache<C>>,
+}
+
+/// What the refinement of a leaf is decided with.
+enum Estimate<R, C> {
+    Children(Children<R, C>),
+    /// The integral of the embedded rule over the leaf.
+    Embedded(R),
+}
+
+fn subdivision_transformations() -> [Array2<f64>; 4] {
+    return [
+        array![
+            // S1
+            [1., 0.5, 0.5],
+            [0., 0.5, 0.],
+            [0., 0., 0.5]
+        ],
+        array![
+            // S2
+            [0., 0.0, 0.5],
+            [1., 0.5, 0.5],
+            [0., 0.5, 0.0]
+        ],
+        array![
+            // S3
+            [0., 0.5, 0.0],
+            [0., 0.0, 0.5],
+            [1., 0.5, 0.5]
+        ],
+        array![
+            // S4
+            [0.5, 0.5, 0.0],
+            [0., 0.5, 0.5],
+            [0.5, 0.0, 0.5]
+        ],
+    ];
+}
+
+/// The transformation of the child `number` of the subdomain with the transformation `trans`.
+fn child_transformation(trans: &Array2<f64>, number: u8) -> Array2<f64> {
+    let transformations = subdivision_transformations();
+    trans.dot(&transformations[(number - 1) as usize])
+}
+
+/// The numbers of the four children of a subdomain.
+const CHILD_NUMBERS: [u8; 4] = [1, 2, 3, 4];
+
+struct NodeData<C> {
     checked: bool,
     number: u8,
+    /// Die Wurzel hat die Stufe 0
+    level: usize,
+    /// Die Transformation von der Wurzel bis zu diesem Knoten, sie wird nur einmal berechnet.
+    transformation: Array2<f64>,
+    /// Der Cache des Basisintegrators für diesen Knoten, er wird nicht gespeichert.
+    cache: CompositeCache<C>,
+}
+
+/// All leafs in DFS order.
+fn collect_leafs<C>(tree: &Arena<NodeData<C>>, root_node_id: NodeId) -> Vec<NodeId> {
+    root_node_id
+        .descendants(tree)
+        .filter(|&id| tree[id].first_child().is_none())
+        .collect()
+}
+
+/// Takes the caches out of the nodes, so the nodes can be integrated in parallel.
+fn take_caches<C: IntegratorCache>(
+    tree: &mut Arena<NodeData<C>>,
+    ids: &[NodeId],
+) -> Vec<CompositeCache<C>> {
+    ids.iter()
+        .map(|&id| std::mem::replace(&mut tree[id].get_mut().cache, CompositeCache::new_cache()))
+        .collect()
+}
+
+impl<C: IntegratorCache> NodeData<C> {
+    fn new_root() -> Self {
+        Self {
+            checked: false,
+            number: 0,
+            level: 0,
+            transformation: Array2::eye(3),
+            cache: CompositeCache::new_cache(),
+        }
+    }
+
+    fn new_child(&self, number: u8) -> Self {
+        Self {
+            checked: false,
+            number,
+            level: self.level + 1,
+            transformation: child_transformation(&self.transformation, number),
+            cache: CompositeCache::new_cache(),
+        }
+    }
 }
 
-impl NodeData {
-    fn new(checked: bool, number: u8) -> Self {
-        return Self { checked, number };
+impl<C: IntegratorCache> TreeData for NodeData<C> {
+    const DIMENSION: u8 = 2;
+
+    fn new_root() -> Self {
+        Self::new_root()
+    }
+
+    fn new_child(&self, number: u8) -> Self {
+        self.new_child(number)
+    }
+
+    fn number(&self) -> u8 {
+        self.number
+    }
+
+    fn checked(&self) -> bool {
+        self.checked
+    }
+
+    fn set_checked(&mut self, checked: bool) {
+        self.checked = checked;
+    }
+
+    fn child_numbers(_number: u8) -> Vec<u8> {
+        CHILD_NUMBERS.to_vec()
     }
 }
 
-pub struct Hierarchic2DIntegratorData {
+/// The refinement tree, every node keeps a cache `C` of the base integrator.
+pub struct Hierarchic2DIntegratorData<C = IntegratorDummy> {
     cached: bool,
     root_node_id: NodeId,
-    arena: Arena<NodeData>,
+    arena: Arena<NodeData<C>>,
 }
 
 impl Hierarchic2DIntegratorData {
+    /// The cache for a base integrator without state, see [`IntegratorCache::new_cache()`] for
+    /// other base integrators.
     pub fn new_cache() -> Self {
-        let mut arena = Arena::new();
-        let root = arena.new_node(NodeData::new(false, 0));
-        Self {
-            cached: false,
-            arena: arena,
-            root_node_id: root,
-        }
+        IntegratorCache::new_cache()
     }
 
+    /// The node numbers in DFS order, the children of a node are enclosed by 20 and 21.
+    /// E.g. `[0, 20, 1, 2, 3, 4, 21]` is the first refinement.
+    pub fn from_vec_tree(vec_tree: &[u8]) -> Result<Self> {
+        Self::from_tree_node(&TreeNode::from_vec_tree(vec_tree)?)
+    }
+
+    pub fn read_binary<R: Read>(reader: R) -> Result<Self> {
+        Self::from_tree_node(&TreeNode::read_binary(
+            NodeData::<IntegratorDummy>::DIMENSION,
+            reader,
+        )?)
+    }
+
+    pub fn read_json<R: Read>(reader: R) -> Result<Self> {
+        Self::from_tree_node(&TreeNode::read_json(
+            NodeData::<IntegratorDummy>::DIMENSION,
+            reader,
+        )?)
+    }
+}
+
+/// The caches of the base integrator are not stored with the tree.
+impl<C: IntegratorCache> Hierarchic2DIntegratorData<C> {
     pub fn make_leafs_unchecked(&mut self) {
         let tree = &mut self.arena;
         let root_node_id = self.root_node_id;
@@ -111,39 +454,85 @@ impl Hierarchic2DIntegratorData {
     pub fn tree_size(&self) -> usize {
         return self.arena.count();
     }
+
+    /// The tree as magic numbers, see [`Self::from_vec_tree()`]. The checked flags are lost.
+    pub fn to_vec_tree(&self) -> Vec<u8> {
+        TreeNode::from_arena(&self.arena, self.root_node_id).to_vec_tree()
+    }
+
+    /// Writes the tree with the checked flags in the versioned binary format.
+    pub fn write_binary<W: Write>(&self, writer: W) -> Result<()> {
+        TreeNode::from_arena(&self.arena, self.root_node_id)
+            .write_binary(NodeData::<C>::DIMENSION, writer)
+    }
+
+    /// Writes the tree with the checked flags in the versioned, readable JSON format.
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<()> {
+        TreeNode::from_arena(&self.arena, self.root_node_id)
+            .write_json(NodeData::<C>::DIMENSION, writer)
+    }
+
+    fn from_tree_node(root: &TreeNode) -> Result<Self> {
+        let (arena, root_node_id) = root.to_arena()?;
+        Ok(Self {
+            cached: true,
+            arena,
+            root_node_id,
+        })
+    }
+}
+
+impl<C: IntegratorCache> IntegratorCache for Hierarchic2DIntegratorData<C> {
+    fn new_cache() -> Self {
+        let mut arena = Arena::new();
+        let root = arena.new_node(NodeData::new_root());
+        Self {
+            cached: false,
+            arena: arena,
+            root_node_id: root,
+        }
+    }
 }
 
-impl<I: Simplex2DIntegrator<IntegratorDummy>> Simplex2DIntegrator<Hierarchic2DIntegratorData>
-    for Hierarchic2DIntegrator<I>
+impl<C: IntegratorCache, I: SimplexIntegrator<2, C>, R: RefinementCriterion>
+    SimplexIntegrator<2, Hierarchic2DIntegratorData<C>> for Hierarchic2DIntegrator<I, R>
 {
     fn dupe(&self) -> Self {
         Self {
             base_integrator: self.base_integrator.dupe(),
-            precision: self.precision,
+            criterion: self.criterion.clone(),
             consolidated: self.consolidated,
+            global: self.global,
+            embedded: self.embedded,
         }
     }
 
-    fn integrate_over_domain<T: Simplex2DFunction>(
+    fn integrate_over_domain<T: SimplexFunction<2>>(
         &self,
         transformation: &Array2<f64>,
         func: &Box<T>,
         simplex: &Simplex2D,
-        cached_data: &mut Hierarchic2DIntegratorData,
-    ) -> T::Return {
+        cached_data: &mut Hierarchic2DIntegratorData<C>,
+    ) -> Result<T::Return> {
+        check_shape(transformation, [3, 3])?;
         // Sollte cached_data noch nicht initialisiert worden sein, dann wirds zeit
         // Danach ist der Cache grundsätzlich gültig.
         if !cached_data.cached {
             cached_data.arena = Arena::new();
-            cached_data.root_node_id = cached_data.arena.new_node(NodeData::new(false, 0));
+            cached_data.root_node_id = cached_data.arena.new_node(NodeData::new_root());
             cached_data.cached = true;
         }
+        if let Some(global) = &self.global {
+            return self.integrate_global(global, transformation, func, simplex, cached_data);
+        }
         // It all begins with a tree!
         let tree = &mut cached_data.arena;
         let root_node_id = cached_data.root_node_id;
 
+        // Die Integrale der Blätter werden je Aufruf nur einmal berechnet, denn sie hängen von der
+        // Funktion ab, der Baum aber nicht.
+        let mut leaf_results = HashMap::new();
         let mut state_changed = true;
-        let precision_threshold = self.precision;
         let mut result = func.additive_neutral_element();
 
         while state_changed {
@@ -151,94 +540,127 @@ impl<I: Simplex2DIntegrator<IntegratorDummy>> Simplex2DIntegrator<Hierarchic2DIn
             state_changed = false;
             // Das Integral wird von vorn Integriert.
             result = func.additive_neutral_element();
-            // Alle Knoten DFS durchgehen
-            let mut next_edge = Some(NodeEdge::Start(root_node_id));
-            while let Some(current_edge) = next_edge {
-                next_edge = current_edge.next_traverse(&tree);
-                let current_id = match current_edge {
-                    NodeEdge::Start(_) => continue,
-                    NodeEdge::End(id) => id,
-                };
-
-                // Wenn der mometane Knoten ein Blattknoten ist, dann....
-                if tree[current_id].first_child().is_none() {
-                    // Ermittle alle Vorfahren
-                    let mut vec = Vec::new();
-                    vec.push(tree[current_id].get().number);
-
-                    let mut par = current_id;
-                    while let Some(parent) = tree[par].parent() {
-                        vec.push(tree[parent].get().number);
-                        par = parent;
+            // Alle Blätter DFS ermitteln
+            let leafs = collect_leafs(tree, root_node_id);
+
+            // Integriert werden nur neue Blätter und die Kinder der ungeprüften Blätter
+            // Wenn das Blatt noch nicht überprüft worden ist und noch nicht consolidiert ist.
+            let work = leafs
+                .iter()
+                .zip(take_caches(tree, &leafs))
+                .map(|(id, cache)| {
+                    let node = tree[*id].get();
+                    let refine =
+                        !node.checked && !self.consolidated && node.level < MAX_REFINEMENT_LEVEL;
+                    let unknown = !leaf_results.contains_key(id);
+                    (&node.transformation, cache, unknown, refine)
+                })
+                .collect();
+            // Die Blätter sind unabhängig voneinander und werden (evtl. parallel) integriert
+            let new_results = ordered_map(work, |(trans, mut cache, unknown, refine)| {
+                let mut current_result = None;
+                let mut estimate = None;
+                if unknown && refine && self.embedded {
+                    let both = self.integrate_subdomain_embedded(
+                        transformation,
+                        trans,
+                        func,
+                        simplex,
+                        &mut cache,
+                    )?;
+                    if let Some(both) = both {
+                        current_result = Some(both.result);
+                        estimate = Some(Estimate::Embedded(both.embedded));
                     }
-                    // Alle Elternknoten wurden ermittelt.
-                    // `vec` soll nicht mehr bearbeitet werden
-                    // Vec fängt beim Kind an, hört bei root auf
-                    let vec = vec;
-                    //println!("{:?}", vec);
-
-                    // Jetzt wird das Integral des Blatts bestimmt.
-                    let trans = Hierarchic2DIntegrator::<I>::get_transformation(&vec);
-                    let child_transform = transformation.dot(&trans);
-                    let mut current_result = self.base_integrator.integrate_over_domain(
-                        &child_transform,
+                }
+                if unknown && current_result.is_none() {
+                    current_result = Some(self.integrate_subdomain(
+                        transformation,
+                        trans,
                         func,
                         simplex,
-                        &mut IntegratorDummy::get(),
-                    );
-
-                    // Wenn das Blatt noch nicht überprüft worden ist und noch nicht consolidiert ist.
-                    if !tree[current_id].get().checked && !self.consolidated {
-                        // Dann wird eine Verfeinerungsstufe mehr eingebaut.
-                        let mut child_result = func.additive_neutral_element();
-                        for i in 0..4 {
-                            let i_1 = i + 1;
-                            let mut child_vec = vec.clone();
-                            // die temporäre transformationshierachie
-                            child_vec.insert(0, i_1 as u8);
-                            let child_trans =
-                                Hierarchic2DIntegrator::<I>::get_transformation(&child_vec);
-                            let child_transformation = transformation.dot(&child_trans);
-                            child_result.add_assign(&self.base_integrator.integrate_over_domain(
-                                &child_transformation,
-                                func,
-                                simplex,
-                                &mut IntegratorDummy::get(),
-                            ));
+                        &mut cache,
+                    )?);
+                }
+                if refine && estimate.is_none() {
+                    let children = self.integrate_children(transformation, trans, func, simplex)?;
+                    estimate = Some(Estimate::Children(children));
+                }
+                Ok((current_result, estimate, cache))
+            })
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+            let mut leaf_estimates = Vec::with_capacity(leafs.len());
+            for (id, (current_result, estimate, cache)) in leafs.iter().zip(new_results) {
+                tree[*id].get_mut().cache = cache;
+                if let Some(current_result) = current_result {
+                    leaf_results.insert(*id, current_result);
+                }
+                leaf_estimates.push(estimate);
+            }
+
+            // Die momentane Schätzung des Integrals für die relativen Kriterien
+            let mut total = func.additive_neutral_element();
+            for id in leafs.iter() {
+                SimplexResultType::add_assign(&mut total, &leaf_results[id]);
+            }
+
+            // Der Baum wird in der Reihenfolge der Blätter angepasst und aufsummiert
+            for (current_id, estimate) in leafs.into_iter().zip(leaf_estimates) {
+                if let Some(Estimate::Embedded(embedded)) = estimate {
+                    // Dieses Element wurde geprüft, ohne seine Kinder zu integrieren
+                    tree[current_id].get_mut().checked = true;
+                    if self.criterion.needs_refinement(
+                        &embedded,
+                        &leaf_results[&current_id],
+                        &total,
+                    ) {
+                        // Die Kinder werden erst im nächsten Durchlauf integriert
+                        leaf_results.remove(&current_id);
+                        for number in CHILD_NUMBERS {
+                            let child_data = tree[current_id].get().new_child(number);
+                            let child_id = tree.new_node(child_data);
+                            current_id.append(child_id, tree);
                         }
-                        // Wenn die Verfeinerung "genauer" ist, dann wird der Baum angepasst.
-                        if current_result.distance(&child_result).abs() > precision_threshold {
-                            // Dieses Element wurde geprüft
-                            tree[current_id].get_mut().checked = true;
-                            // dem Element fügen wir die Kinder hinzu
-                            for i in 0..4 {
-                                let i = i + 1;
-                                current_id.append(
-                                    tree.new_node(NodeData {
-                                        checked: false,
-                                        number: i,
-                                    }),
-                                    tree,
-                                );
-                            }
-                            // Der Baum hat sich geändert!
-                            state_changed = true;
-                            // Das Resultat ist das genauere resultat
-                            current_result = child_result;
-                        } else {
-                            tree[current_id].get_mut().checked = true;
+                        state_changed = true;
+                        continue;
+                    }
+                } else if let Some(Estimate::Children(children_results)) = estimate {
+                    // Dieses Element wurde geprüft
+                    tree[current_id].get_mut().checked = true;
+                    let mut child_result = func.additive_neutral_element();
+                    for child in children_results.results.iter() {
+                        child_result.add_assign(child);
+                    }
+                    // Wenn die Verfeinerung "genauer" ist, dann wird der Baum angepasst.
+                    if self.criterion.needs_refinement(
+                        &leaf_results[&current_id],
+                        &child_result,
+                        &total,
+                    ) {
+                        // dem Element fügen wir die Kinder hinzu, ihre Integrale sind bekannt
+                        leaf_results.remove(&current_id);
+                        let children = children_results.results.into_iter();
+                        let children = children.zip(children_results.caches);
+                        for (number, (child, cache)) in CHILD_NUMBERS.into_iter().zip(children) {
+                            let mut child_data = tree[current_id].get().new_child(number);
+                            child_data.cache = cache;
+                            let child_id = tree.new_node(child_data);
+                            current_id.append(child_id, tree);
+                            leaf_results.insert(child_id, child);
                         }
+                        // Der Baum hat sich geändert!
+                        state_changed = true;
+                        // Das Resultat ist das genauere resultat
+                        result.add_assign(&child_result);
+                        continue;
                     }
-                    result.add_assign(&current_result);
                 }
-
-                //*tree[current_id].get_mut() = 42;
+                result.add_assign(&leaf_results[&current_id]);
             }
-
-            //println!("{}",result);
         } // Iteration ende
 
-        return result;
+        return Ok(result);
     }
 }
 
@@ -246,11 +668,396 @@ impl<I: Simplex2DIntegrator<IntegratorDummy>> Simplex2DIntegrator<Hierarchic2DIn
 mod tests {
     use crate::common::IntegratorDummy;
     use crate::integration_2d::integrators::{
-        Hierarchic2DIntegrator, Hierarchic2DIntegratorData, Quadrilateral2DIntegrator,
+        EdgeSubdivisionIntegrator, Hierarchic2DIntegrator, Hierarchic2DIntegratorData,
+        Quadrilateral2DIntegrator,
     };
     use crate::integrator_tests;
+    use crate::refinement::{
+        GlobalRefinement, MaxNormCriterion, MixedCriterion, RelativeCriterion,
+    };
+    use crate::simplex::{CompositeCache, IntegratorCache};
 
     integrator_tests! {
-        quadrilaterial1: Hierarchic2DIntegrator<Quadrilateral2DIntegrator>: Hierarchic2DIntegrator::new(Quadrilateral2DIntegrator::new(1),false,1e-2), Hierarchic2DIntegratorData: Hierarchic2DIntegratorData::new_cache(),
+        quadrilaterial1: Hierarchic2DIntegrator<Quadrilateral2DIntegrator>: Hierarchic2DIntegrator::new(Quadrilateral2DIntegrator::new(1).unwrap(),false,1e-2), Hierarchic2DIntegratorData: Hierarchic2DIntegratorData::new_cache(),
+        relative: Hierarchic2DIntegrator<Quadrilateral2DIntegrator, RelativeCriterion>: Hierarchic2DIntegrator::new_with_criterion(Quadrilateral2DIntegrator::new(1).unwrap(),false,RelativeCriterion::new(1e-2)), Hierarchic2DIntegratorData: Hierarchic2DIntegratorData::new_cache(),
+        max_norm: Hierarchic2DIntegrator<Quadrilateral2DIntegrator, MaxNormCriterion>: Hierarchic2DIntegrator::new_with_criterion(Quadrilateral2DIntegrator::new(1).unwrap(),false,MaxNormCriterion::new(1e-2)), Hierarchic2DIntegratorData: Hierarchic2DIntegratorData::new_cache(),
+        mixed: Hierarchic2DIntegrator<Quadrilateral2DIntegrator, MixedCriterion>: Hierarchic2DIntegrator::new_with_criterion(Quadrilateral2DIntegrator::new(1).unwrap(),false,MixedCriterion::new(1e-4, 1e-2)), Hierarchic2DIntegratorData: Hierarchic2DIntegratorData::new_cache(),
+        global: Hierarchic2DIntegrator<Quadrilateral2DIntegrator>: Hierarchic2DIntegrator::new_global(Quadrilateral2DIntegrator::new(1).unwrap(),GlobalRefinement::new(1e-3, 100_000)), Hierarchic2DIntegratorData: Hierarchic2DIntegratorData::new_cache(),
+        nested: Hierarchic2DIntegrator<EdgeSubdivisionIntegrator<Hierarchic2DIntegrator<Quadrilateral2DIntegrator>>>: Hierarchic2DIntegrator::new(EdgeSubdivisionIntegrator::new(Hierarchic2DIntegrator::new(Quadrilateral2DIntegrator::new(1).unwrap(),false,1e-2),2).unwrap(),false,1e-2), Hierarchic2DIntegratorData<CompositeCache<Hierarchic2DIntegratorData>>: IntegratorCache::new_cache(),
+    }
+
+    #[test]
+    fn global_tolerance_and_budget() {
+        use ndarray::array;
+
+        use crate::integration_2d::domain::{Simplex2D, SimplexIntegrator};
+        use crate::integration_2d::functions::{Function2DHistory, PyramidFunction};
+        use crate::integration_2d::integrators::DunavantIntegrator;
+
+        let sim = Simplex2D::new_from_points(&array![0., 0.], &array![1., 0.], &array![0., 1.]);
+        let func = Box::new(Function2DHistory::new(
+            PyramidFunction::new(0.2, 0.3, 0.5, 1.).unwrap(),
+        ));
+        let exact = Hierarchic2DIntegrator::new(DunavantIntegrator::new(2).unwrap(), false, 1e-9)
+            .integrate_simplex(&func, &sim, &mut Hierarchic2DIntegratorData::new_cache())
+            .unwrap()
+            .get();
+
+        let inte = Hierarchic2DIntegrator::new_global(
+            DunavantIntegrator::new(2).unwrap(),
+            GlobalRefinement::new(1e-6, 100_000),
+        );
+        let mut cache = Hierarchic2DIntegratorData::new_cache();
+        let result = inte
+            .integrate_simplex(&func, &sim, &mut cache)
+            .unwrap()
+            .get();
+        assert!((result - exact).abs() < 1e-6);
+        // Im Cache geht es ohne Verfeinerung weiter
+        let size = cache.tree_size();
+        assert_eq!(
+            inte.integrate_simplex(&func, &sim, &mut cache)
+                .unwrap()
+                .get(),
+            result
+        );
+        assert_eq!(cache.tree_size(), size);
+
+        // Mit kleinem Budget: 5 für die Wurzel und 16 je Teilung
+        let inte = Hierarchic2DIntegrator::new_global(
+            DunavantIntegrator::new(2).unwrap(),
+            GlobalRefinement::new(1e-12, 5 + 3 * 16 + 15),
+        );
+        func.delete_history();
+        DunavantIntegrator::new(2)
+            .unwrap()
+            .integrate_simplex(&func, &sim, &mut IntegratorDummy::get())
+            .unwrap();
+        let points = func.function_evaluations();
+        func.delete_history();
+        let mut cache = Hierarchic2DIntegratorData::new_cache();
+        inte.integrate_simplex(&func, &sim, &mut cache).unwrap();
+        assert_eq!(cache.tree_size(), 1 + 3 * 4);
+        assert_eq!(func.function_evaluations(), (5 + 3 * 16) * points);
+    }
+
+    #[test]
+    fn every_subdomain_is_integrated_once() {
+        use ndarray::array;
+
+        use crate::integration_2d::domain::{Simplex2D, SimplexIntegrator};
+        use crate::integration_2d::functions::{Function2DHistory, PyramidFunction};
+        use crate::integration_2d::integrators::DunavantIntegrator;
+
+        let sim = Simplex2D::new_from_points(&array![0., 0.], &array![1., 0.], &array![0., 1.]);
+        let func = Box::new(Function2DHistory::new(
+            PyramidFunction::new(0.2, 0.3, 0.5, 1.).unwrap(),
+        ));
+        DunavantIntegrator::new(2)
+            .unwrap()
+            .integrate_simplex(&func, &sim, &mut IntegratorDummy::get())
+            .unwrap();
+        let points = func.function_evaluations();
+        func.delete_history();
+
+        let inte = Hierarchic2DIntegrator::new(DunavantIntegrator::new(2).unwrap(), false, 1e-7);
+        let mut cache = Hierarchic2DIntegratorData::new_cache();
+        inte.integrate_simplex(&func, &sim, &mut cache).unwrap();
+        // Jeder Knoten einmal, dazu die verworfenen Kinder der Blätter
+        let leafs = (cache.tree_size() - 1) / 4 * 3 + 1;
+        assert_eq!(
+            func.function_evaluations(),
+            (cache.tree_size() + 4 * leafs) * points
+        );
+
+        // Mit fertigem Baum wird jedes Blatt genau einmal integriert
+        func.delete_history();
+        inte.integrate_simplex(&func, &sim, &mut cache).unwrap();
+        assert_eq!(func.function_evaluations(), leafs * points);
+    }
+
+    #[test]
+    fn nested_over_a_cached_base() {
+        use ndarray::array;
+
+        use crate::integration_2d::domain::{Simplex2D, SimplexIntegrator};
+        use crate::integration_2d::functions::Function2DHistory;
+        use crate::integration_2d::integrators::DunavantIntegrator;
+
+        type Nested = Hierarchic2DIntegratorData<CompositeCache<Hierarchic2DIntegratorData>>;
+
+        let sim = Simplex2D::new_from_points(&array![0., 0.], &array![1., 0.], &array![0., 1.]);
+        let func = Box::new(Function2DHistory::new(
+            |xi1: f64, xi2: f64, _xi3: f64, _simplex: &Simplex2D| (6. * xi1 + 4. * xi2).sin(),
+        ));
+        // Die inneren Bäume wachsen bis zum Budget, unabhängig vom Teildreieck
+        let inner = Hierarchic2DIntegrator::new_global(
+            DunavantIntegrator::new(2).unwrap(),
+            GlobalRefinement::new(1e-12, 200),
+        );
+        let inte = Hierarchic2DIntegrator::new(
+            EdgeSubdivisionIntegrator::new(inner, 3).unwrap(),
+            false,
+            1e-9,
+        );
+        let mut cache = Nested::new_cache();
+        let result = inte
+            .integrate_simplex(&func, &sim, &mut cache)
+            .unwrap()
+            .get();
+        let reference = EdgeSubdivisionIntegrator::new(DunavantIntegrator::new(5).unwrap(), 64)
+            .unwrap()
+            .integrate_simplex(&func, &sim, &mut IntegratorDummy::get())
+            .unwrap()
+            .get();
+        assert!((result - reference).abs() < 1e-8);
+        assert!(cache.tree_size() > 1);
+
+        // Jedes Blatt hat die Bäume seiner neun Teildreiecke behalten
+        let inner_sizes = |cache: &Nested| -> Vec<usize> {
+            let tree = &cache.arena;
+            super::collect_leafs(tree, cache.root_node_id)
+                .into_iter()
+                .flat_map(|id| tree[id].get().cache.sub_caches()[0].sub_caches())
+                .map(|inner| inner.tree_size())
+                .collect()
+        };
+        let sizes = inner_sizes(&cache);
+        assert_eq!(sizes.len(), 9 * ((cache.tree_size() - 1) / 4 * 3 + 1));
+        assert!(sizes.iter().all(|&size| size > 1));
+
+        // Mit fertigen Bäumen wird jedes innere Blatt nur noch geschätzt, das Budget ist erreicht
+        func.delete_history();
+        let cached = inte
+            .integrate_simplex(&func, &sim, &mut cache)
+            .unwrap()
+            .get();
+        assert_eq!(cached, result);
+        let leafs: usize = sizes.iter().map(|size| (size - 1) / 4 * 3 + 1).sum();
+        assert_eq!(func.function_evaluations(), leafs * (1 + 4) * 3);
+        assert_eq!(inner_sizes(&cache), sizes);
+    }
+
+    #[test]
+    fn embedded_estimate_without_children() {
+        use ndarray::array;
+
+        use crate::embedded::GrundmannMoellerIntegrator;
+        use crate::integration_2d::domain::{Simplex2D, SimplexIntegrator};
+        use crate::integration_2d::functions::Function2DHistory;
+        use crate::integration_2d::integrators::DunavantIntegrator;
+        use crate::refinement::AbsoluteCriterion;
+
+        let sim = Simplex2D::new_from_points(&array![0., 0.], &array![1., 0.], &array![0., 1.]);
+        let func = Box::new(Function2DHistory::new(
+            |xi1: f64, xi2: f64, _xi3: f64, _simplex: &Simplex2D| (6. * xi1 + 4. * xi2).sin(),
+        ));
+        let reference = EdgeSubdivisionIntegrator::new(DunavantIntegrator::new(5).unwrap(), 64)
+            .unwrap()
+            .integrate_simplex(&func, &sim, &mut IntegratorDummy::get())
+            .unwrap()
+            .get();
+        let base = || GrundmannMoellerIntegrator::<2>::new(5).unwrap();
+        let points = base().get_num_points();
+
+        func.delete_history();
+        let mut cache = Hierarchic2DIntegratorData::new_cache();
+        let inte =
+            Hierarchic2DIntegrator::new_embedded(base(), false, AbsoluteCriterion::new(1e-8));
+        let result = inte
+            .integrate_simplex(&func, &sim, &mut cache)
+            .unwrap()
+            .get();
+        assert!((result - reference).abs() < 1e-7);
+        assert!(cache.tree_size() > 1);
+        // Jedes Element wird genau einmal integriert, die Kinder nur wenn es verfeinert wird
+        assert_eq!(func.function_evaluations(), cache.tree_size() * points);
+
+        // Mit fertigem Baum werden nur noch die Blätter integriert
+        func.delete_history();
+        let cached = inte
+            .integrate_simplex(&func, &sim, &mut cache)
+            .unwrap()
+            .get();
+        assert!((cached - result).abs() < 1e-15);
+        let leafs = (cache.tree_size() - 1) / 4 * 3 + 1;
+        assert_eq!(func.function_evaluations(), leafs * points);
+
+        // Ohne eingebettete Regel werden auch die Kinder der nicht verfeinerten Blätter integriert
+        func.delete_history();
+        let mut cache = Hierarchic2DIntegratorData::new_cache();
+        Hierarchic2DIntegrator::new(base(), false, 1e-8)
+            .integrate_simplex(&func, &sim, &mut cache)
+            .unwrap();
+        let leafs = (cache.tree_size() - 1) / 4 * 3 + 1;
+        assert_eq!(
+            func.function_evaluations(),
+            (cache.tree_size() + 4 * leafs) * points
+        );
+    }
+
+    #[test]
+    fn refinement_stops_at_the_max_level() {
+        use ndarray::array;
+
+        use crate::integration_2d::domain::{Simplex2D, SimplexIntegrator};
+        use crate::integration_2d::integrators::DunavantIntegrator;
+        use crate::refinement::MAX_REFINEMENT_LEVEL;
+
+        // Das Integral existiert nicht, an der ersten Ecke wird immer weiter verfeinert
+        let sim = Simplex2D::new_from_points(&array![0., 0.], &array![1., 0.], &array![0., 1.]);
+        let func =
+            Box::new(|_xi1: f64, xi2: f64, xi3: f64, _simplex: &Simplex2D| (xi2 + xi3).powi(-2));
+        let inte = Hierarchic2DIntegrator::new(DunavantIntegrator::new(2).unwrap(), false, 1e-7);
+        let mut cache = Hierarchic2DIntegratorData::new_cache();
+        inte.integrate_simplex(&func, &sim, &mut cache).unwrap();
+
+        let mut level = 0;
+        let mut max_level = 0;
+        for number in cache.to_vec_tree() {
+            match number {
+                20 => {
+                    level += 1;
+                    max_level = max_level.max(level);
+                }
+                21 => level -= 1,
+                _ => {}
+            }
+        }
+        assert_eq!(max_level, MAX_REFINEMENT_LEVEL);
+    }
+
+    #[test]
+    fn relative_criterion_with_a_vanishing_total() {
+        use ndarray::array;
+
+        use crate::integration_2d::domain::{Simplex2D, SimplexIntegrator};
+        use crate::integration_2d::integrators::Quadrilateral2DIntegrator;
+
+        // Die Funktion ist ungerade in x, das Integral über das symmetrische Dreieck ist null
+        let sim = Simplex2D::new_from_points(&array![0., 1.], &array![-1., 0.], &array![1., 0.]);
+        let func = Box::new(|xi1: f64, xi2: f64, xi3: f64, simplex: &Simplex2D| {
+            let [x, y] = simplex.get_physical_point(&array![xi1, xi2, xi3]);
+            x * (3. * y).exp()
+        });
+        let inte = Hierarchic2DIntegrator::new_with_criterion(
+            Quadrilateral2DIntegrator::new(2).unwrap(),
+            false,
+            RelativeCriterion::new(1e-3),
+        );
+        let mut cache = Hierarchic2DIntegratorData::new_cache();
+        let result = inte.integrate_simplex(&func, &sim, &mut cache).unwrap();
+        assert!(result.get().abs() < 1e-3);
+        assert!(cache.tree_size() < 100);
+    }
+
+    #[test]
+    fn fixed_size_and_dynamic_results() {
+        use ndarray::{array, ArrayD, IxDyn};
+
+        use crate::integration_2d::domain::{
+            ResultTypeWrapper, Simplex2D, Simplex2DFunction, SimplexIntegrator,
+        };
+        use crate::integration_2d::integrators::DunavantIntegrator;
+
+        // xi1, |xi1 - xi2| und 1, einmal als festes Array und einmal dynamisch
+        struct Fixed;
+        impl Simplex2DFunction for Fixed {
+            type Return = ResultTypeWrapper<[f64; 3]>;
+            fn function(
+                &self,
+                xi1: f64,
+                xi2: f64,
+                _xi3: f64,
+                _simplex: &Simplex2D,
+            ) -> Self::Return {
+                ResultTypeWrapper::new([xi1, (xi1 - xi2).abs(), 1.])
+            }
+        }
+        struct Dynamic;
+        impl Simplex2DFunction for Dynamic {
+            type Return = ResultTypeWrapper<ArrayD<f64>>;
+            fn function(
+                &self,
+                xi1: f64,
+                xi2: f64,
+                _xi3: f64,
+                _simplex: &Simplex2D,
+            ) -> Self::Return {
+                let values = vec![xi1, (xi1 - xi2).abs(), 1.];
+                ResultTypeWrapper::new(ArrayD::from_shape_vec(IxDyn(&[3]), values).unwrap())
+            }
+        }
+
+        let sim = Simplex2D::new_from_points(&array![0., 0.], &array![1., 0.], &array![0., 1.]);
+        let inte = Hierarchic2DIntegrator::new(DunavantIntegrator::new(1).unwrap(), false, 1e-4);
+        let fixed = inte
+            .integrate_simplex(
+                &Box::new(Fixed),
+                &sim,
+                &mut Hierarchic2DIntegratorData::new_cache(),
+            )
+            .unwrap()
+            .get();
+        let dynamic = inte
+            .integrate_simplex(
+                &Box::new(Dynamic),
+                &sim,
+                &mut Hierarchic2DIntegratorData::new_cache(),
+            )
+            .unwrap()
+            .get();
+        assert_eq!(dynamic.as_slice().unwrap(), fixed);
+        assert!((fixed[0] - 1. / 6.).abs() < 1e-14);
+        assert!((fixed[2] - 0.5).abs() < 1e-14);
+    }
+
+    #[test]
+    fn tree_round_trip() {
+        let vec_tree = vec![0, 20, 1, 2, 20, 1, 2, 3, 4, 21, 3, 4, 21];
+        let cache = Hierarchic2DIntegratorData::from_vec_tree(&vec_tree).unwrap();
+        assert_eq!(cache.tree_size(), 9);
+        assert_eq!(cache.to_vec_tree(), vec_tree);
+
+        let mut json = Vec::new();
+        cache.write_json(&mut json).unwrap();
+        let loaded = Hierarchic2DIntegratorData::read_json(json.as_slice()).unwrap();
+        assert_eq!(loaded.to_vec_tree(), vec_tree);
+        assert!(Hierarchic2DIntegratorData::from_vec_tree(&[0, 20, 1, 2, 3, 4, 13, 21]).is_err());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_is_bit_identical() {
+        use ndarray::array;
+
+        use crate::integration_2d::domain::{Simplex2D, SimplexIntegrator};
+        use crate::integration_2d::functions::{Function2DHistory, PyramidFunction};
+        use crate::integration_2d::integrators::DunavantIntegrator;
+
+        let sim = Simplex2D::new_from_points(&array![0., 0.], &array![1., 0.], &array![0., 1.]);
+        let inte = Hierarchic2DIntegrator::new(DunavantIntegrator::new(2).unwrap(), false, 1e-7);
+        let run = |threads: usize| {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            pool.install(|| {
+                let func = Box::new(Function2DHistory::new(
+                    PyramidFunction::new(0.2, 0.3, 0.5, 1.).unwrap(),
+                ));
+                let mut cache = Hierarchic2DIntegratorData::new_cache();
+                let result = inte
+                    .integrate_simplex(&func, &sim, &mut cache)
+                    .unwrap()
+                    .get();
+                (result, cache.tree_size(), func.function_evaluations())
+            })
+        };
+        let (serial, serial_size, serial_evaluations) = run(1);
+        let (parallel, parallel_size, parallel_evaluations) = run(4);
+        assert_eq!(serial.to_bits(), parallel.to_bits());
+        assert_eq!(serial_size, parallel_size);
+        assert_eq!(serial_evaluations, parallel_evaluations);
     }
 }
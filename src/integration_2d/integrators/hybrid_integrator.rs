@@ -0,0 +1,142 @@
+use ndarray::Array2;
+
+use crate::common::IntegratorDummy;
+use crate::error::Result;
+use crate::integration_2d::domain::{Simplex2D, SimplexFunction, SimplexIntegrator};
+use crate::integration_2d::integrators::{
+    EdgeSubdivisionIntegrator, Hierarchic2DIntegrator, Hierarchic2DIntegratorData,
+};
+use crate::refinement::{AbsoluteCriterion, RefinementCriterion};
+use crate::simplex::{CompositeCache, IntegratorCache};
+
+/// The hybrid strategy: every edge is divided into `order` parts like in the
+/// [`EdgeSubdivisionIntegrator`], then every subtriangle is refined adaptively by the
+/// [`Hierarchic2DIntegrator`] with its own tree.
+/// The criterion is applied to each subtriangle, the sign is the one of the base integrator.
+pub struct Hybrid2DIntegrator<I, R: RefinementCriterion = AbsoluteCriterion> {
+    edge_integrator: EdgeSubdivisionIntegrator<Hierarchic2DIntegrator<I, R>>,
+}
+
+impl<I, R: RefinementCriterion> Hybrid2DIntegrator<I, R> {
+    /// Every edge is divided into `order` parts, so the order must be at least 1.
+    pub fn new(hierarchic_integrator: Hierarchic2DIntegrator<I, R>, order: usize) -> Result<Self> {
+        Ok(Self {
+            edge_integrator: EdgeSubdivisionIntegrator::new(hierarchic_integrator, order)?,
+        })
+    }
+}
+
+/// The composite cache of the [`Hybrid2DIntegrator`], one tree per subtriangle.
+/// Every tree keeps caches `C` of the base integrator, see [`Hierarchic2DIntegratorData<C>`].
+pub type Hybrid2DIntegratorData<C = IntegratorDummy> =
+    CompositeCache<Hierarchic2DIntegratorData<C>>;
+
+impl<C: IntegratorCache> CompositeCache<Hierarchic2DIntegratorData<C>> {
+    pub fn make_leafs_unchecked(&mut self) {
+        for sub_cache in self.sub_caches.iter_mut() {
+            sub_cache.make_leafs_unchecked();
+        }
+    }
+
+    /// The number of nodes of all trees.
+    pub fn tree_size(&self) -> usize {
+        self.sub_caches()
+            .iter()
+            .map(|sub_cache| sub_cache.tree_size())
+            .sum()
+    }
+}
+
+impl<C: IntegratorCache, I: SimplexIntegrator<2, C>, R: RefinementCriterion>
+    SimplexIntegrator<2, Hybrid2DIntegratorData<C>> for Hybrid2DIntegrator<I, R>
+{
+    fn dupe(&self) -> Self {
+        Self {
+            edge_integrator: SimplexIntegrator::<2, Hybrid2DIntegratorData<C>>::dupe(
+                &self.edge_integrator,
+            ),
+        }
+    }
+
+    fn integrate_over_domain<T: SimplexFunction<2>>(
+        &self,
+        transformation: &Array2<f64>,
+        func: &Box<T>,
+        simplex: &Simplex2D,
+        cached_data: &mut Hybrid2DIntegratorData<C>,
+    ) -> Result<T::Return> {
+        self.edge_integrator
+            .integrate_over_domain(transformation, func, simplex, cached_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::integration_2d::integrators::{
+        DunavantIntegrator, Hierarchic2DIntegrator, Hierarchic2DIntegratorData, Hybrid2DIntegrator,
+        Hybrid2DIntegratorData, Quadrilateral2DIntegrator,
+    };
+    use crate::integrator_tests;
+
+    integrator_tests! {
+        order2_quad: Hybrid2DIntegrator<Quadrilateral2DIntegrator>: Hybrid2DIntegrator::new(Hierarchic2DIntegrator::new(Quadrilateral2DIntegrator::new(1).unwrap(),false,1e-2),2).unwrap(), Hybrid2DIntegratorData: Hybrid2DIntegratorData::new_cache(),
+        order3_dunavant: Hybrid2DIntegrator<DunavantIntegrator>: Hybrid2DIntegrator::new(Hierarchic2DIntegrator::new(DunavantIntegrator::new(2).unwrap(),false,1e-3),3).unwrap(), Hybrid2DIntegratorData: Hybrid2DIntegratorData::new_cache(),
+        nested: Hybrid2DIntegrator<Hierarchic2DIntegrator<DunavantIntegrator>>: Hybrid2DIntegrator::new(Hierarchic2DIntegrator::new(Hierarchic2DIntegrator::new(DunavantIntegrator::new(2).unwrap(),false,1e-2),false,1e-2),2).unwrap(), Hybrid2DIntegratorData<Hierarchic2DIntegratorData>: Hybrid2DIntegratorData::new_cache(),
+    }
+
+    #[test]
+    fn hybrid_matches_closed_form() {
+        use ndarray::array;
+        use num_complex::Complex64;
+
+        use crate::integration_2d::domain::{Simplex2D, SimplexIntegrator};
+        use crate::integration_2d::functions::PlaneWave2DFunction;
+
+        let (a, b) = (3., -2.);
+        let sim = Simplex2D::new_from_points(&array![0., 0.], &array![1., 0.], &array![0., 1.]);
+        let i = Complex64::i();
+        let exact = ((i * b).exp() * ((i * (a - b)).exp() - 1.) / (i * (a - b))
+            - ((i * a).exp() - 1.) / (i * a))
+            / (i * b);
+        let func = Box::new(PlaneWave2DFunction::new([a, b]));
+
+        let inte = Hybrid2DIntegrator::new(
+            Hierarchic2DIntegrator::new(DunavantIntegrator::new(10).unwrap(), false, 1e-12),
+            3,
+        )
+        .unwrap();
+        let mut cache = Hybrid2DIntegratorData::new_cache();
+        let result = inte
+            .integrate_simplex(&func, &sim, &mut cache)
+            .unwrap()
+            .get();
+        assert!((result - exact).norm() < 1e-10);
+        // Jedes der neun Teildreiecke hat seinen eigenen Baum
+        assert_eq!(cache.sub_caches().len(), 9);
+        let size = cache.tree_size();
+        assert!(size >= 9);
+
+        // Mit dem Cache ändern sich weder Baum noch Ergebnis
+        assert_eq!(
+            inte.integrate_simplex(&func, &sim, &mut cache)
+                .unwrap()
+                .get(),
+            result
+        );
+        assert_eq!(cache.tree_size(), size);
+
+        // Eine andere Ordnung legt den Cache neu an
+        let inte = Hybrid2DIntegrator::new(
+            Hierarchic2DIntegrator::new(DunavantIntegrator::new(10).unwrap(), false, 1e-12),
+            2,
+        )
+        .unwrap();
+        inte.integrate_simplex(&func, &sim, &mut cache).unwrap();
+        assert_eq!(cache.sub_caches().len(), 4);
+        assert!(Hybrid2DIntegrator::new(
+            Hierarchic2DIntegrator::new(DunavantIntegrator::new(10).unwrap(), false, 1e-12),
+            0
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,157 @@
+use ndarray::Array2;
+
+use crate::common::IntegratorDummy;
+use crate::error::{IntegrationError, Result};
+use crate::integration_2d::domain::{Simplex2D, SimplexFunction, SimplexIntegrator};
+use crate::integration_2d::integrators::dunavant::{integrate_orbits, Orbit};
+
+const ONE_THIRD: f64 = 1. / 3.;
+
+/// The symmetric rules of J. N. Lyness and D. Jespersen, "Moderate degree symmetric quadrature
+/// rules for the triangle", 1975, for the degrees 1 to 6.
+/// Only rules with positive weights and points inside or on the boundary of the triangle are used.
+/// The weights are normalized to the area of the triangle, the tabulated weights of theory.tex
+/// are the weights of a whole orbit.
+#[rustfmt::skip]
+fn lyness_orbits(order: usize) -> Result<&'static [Orbit]> {
+    Ok(match order {
+        1 => &[
+            (1, [ONE_THIRD, ONE_THIRD, ONE_THIRD], 1.0),
+        ],
+        // Kantenmittelpunkte
+        2 => &[
+            (3, [0.5, 0.5, 0.0], ONE_THIRD),
+        ],
+        // Schwerpunkt, Kantenmittelpunkte und Ecken
+        3 => &[
+            (1, [ONE_THIRD, ONE_THIRD, ONE_THIRD], 9. / 20.),
+            (3, [0.5, 0.5, 0.0], 2. / 15.),
+            (3, [1.0, 0.0, 0.0], 1. / 20.),
+        ],
+        4 => &[
+            (3, [0.8168475729804585, 0.09157621350977073, 0.09157621350977076], 0.3298552309659655 / 3.),
+            (3, [0.1081030181680702, 0.4459484909159649, 0.4459484909159649], 0.6701447690340345 / 3.),
+        ],
+        // Die Regel von Radon
+        5 => &[
+            (1, [ONE_THIRD, ONE_THIRD, ONE_THIRD], 9. / 40.),
+            (3, [0.7974269853530873, 0.10128650732345633, 0.10128650732345633], 0.12593918054482717),
+            (3, [0.05971587178976989, 0.47014206410511505, 0.47014206410511505], 0.13239415278850616),
+        ],
+        6 => &[
+            (3, [0.5014265096581342, 0.2492867451709329, 0.2492867451709329], 0.3503588271790222 / 3.),
+            (3, [0.8738219710169965, 0.06308901449150177, 0.06308901449150169], 0.1525347191106164 / 3.),
+            (6, [0.6365024991213939, 0.05314504984483216, 0.31035245103377396], 0.4971064537103375 / 6.),
+        ],
+        _ => {
+            return Err(IntegrationError::UnsupportedOrder { rule: "Lyness-Jespersen", order })
+        }
+    })
+}
+
+/// Integrates polynomials of degree `order` exactly using the rules in [`lyness_orbits()`]
+///
+/// Signed like the [`DunavantIntegrator`](super::DunavantIntegrator).
+pub struct LynessIntegrator {
+    integration_order: usize,
+}
+
+impl LynessIntegrator {
+    /// The order must be between 1 and 6, otherwise [`IntegrationError::UnsupportedOrder`].
+    pub fn new(order: usize) -> Result<Self> {
+        lyness_orbits(order)?;
+        Ok(Self {
+            integration_order: order,
+        })
+    }
+}
+
+impl SimplexIntegrator<2, IntegratorDummy> for LynessIntegrator {
+    fn dupe(&self) -> Self {
+        Self {
+            integration_order: self.integration_order,
+        }
+    }
+
+    fn integrate_over_domain<T: SimplexFunction<2>>(
+        &self,
+        transformation: &Array2<f64>,
+        func: &Box<T>,
+        simplex: &Simplex2D,
+        _cache_data: &mut IntegratorDummy,
+    ) -> Result<T::Return> {
+        integrate_orbits(
+            lyness_orbits(self.integration_order)?,
+            transformation,
+            func,
+            simplex,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::array;
+
+    use crate::common::IntegratorDummy;
+    use crate::integration_2d::domain::{
+        ResultTypeWrapper, Simplex2D, Simplex2DFunction, SimplexIntegrator,
+    };
+    use crate::integration_2d::integrators::LynessIntegrator;
+    use crate::integrator_tests;
+
+    integrator_tests! {
+        order1: LynessIntegrator: LynessIntegrator::new(1).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order2: LynessIntegrator: LynessIntegrator::new(2).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order3: LynessIntegrator: LynessIntegrator::new(3).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order6: LynessIntegrator: LynessIntegrator::new(6).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+    }
+
+    /// xi1^i * xi2^j * xi3^k
+    struct BarycentricMonomial(i32, i32, i32);
+
+    impl Simplex2DFunction for BarycentricMonomial {
+        type Return = ResultTypeWrapper<f64>;
+        fn function(&self, xi1: f64, xi2: f64, xi3: f64, _simplex: &Simplex2D) -> Self::Return {
+            ResultTypeWrapper::new(xi1.powi(self.0) * xi2.powi(self.1) * xi3.powi(self.2))
+        }
+    }
+
+    fn factorial(n: i32) -> f64 {
+        (1..=n).map(|i| i as f64).product()
+    }
+
+    #[test]
+    fn monomials_exact_up_to_order() {
+        let sim = Simplex2D::new_from_points(&array![1., 1.], &array![3., 1.5], &array![1.5, 2.]);
+        // Doppelte Fläche des Simplex
+        let area2 = 2. * 1. - 0.5 * 0.5;
+        for order in 1..=6 {
+            let inte = LynessIntegrator::new(order).unwrap();
+            for degree in 0..=order as i32 {
+                for i in 0..=degree {
+                    for j in 0..=(degree - i) {
+                        let k = degree - i - j;
+                        let func = Box::new(BarycentricMonomial(i, j, k));
+                        let result = inte
+                            .integrate_simplex(&func, &sim, &mut IntegratorDummy::get())
+                            .unwrap()
+                            .get();
+                        let exact = area2 * factorial(i) * factorial(j) * factorial(k)
+                            / factorial(degree + 2);
+                        assert!(
+                            ((result - exact) / exact).abs() < 1e-12,
+                            "Order {}: xi1^{} xi2^{} xi3^{}, Expected: {}, Actual: {}",
+                            order,
+                            i,
+                            j,
+                            k,
+                            exact,
+                            result
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
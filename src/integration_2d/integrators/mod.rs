@@ -1,9 +1,13 @@
 pub mod dunavant;
 pub mod edge_subdivision_integrator;
 pub mod hierarchic_integrator;
+pub mod hybrid_integrator;
+pub mod lyness;
 pub mod quadrilaterial_integrator;
 
 pub use self::dunavant::*;
 pub use self::edge_subdivision_integrator::*;
 pub use self::hierarchic_integrator::*;
+pub use self::hybrid_integrator::*;
+pub use self::lyness::*;
 pub use self::quadrilaterial_integrator::*;
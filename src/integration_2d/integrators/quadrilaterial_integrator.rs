@@ -1,42 +1,27 @@
 use ndarray::{array, Array1, Array2};
 
+use crate::common::{det2x2, gauss_legendre_golub_welsch};
+use crate::error::{check_shape, Result};
 use crate::integration_2d::domain::{
-    Simplex2D, Simplex2DFunction, Simplex2DIntegrator, Simplex2DResultType,
+    Simplex2D, SimplexFunction, SimplexIntegrator, SimplexResultType,
 };
 
-fn det2x2(mat2x2: &Array2<f64>) -> f64 {
-    mat2x2[[0, 0]] * mat2x2[[1, 1]] - mat2x2[[0, 1]] * mat2x2[[1, 0]]
-}
-
-fn det3x3(mat3x3: &Array2<f64>) -> f64 {
-    let mut sum = 0.0;
-    for j in 0..3 {
-        let mut prod = 1.0;
-        for i in 0..3 {
-            prod = prod * mat3x3[[i, (i + j) % 3]]
-        }
-        sum = sum + prod;
-        let mut prod = -1.0;
-        for i in 0..3 {
-            prod = prod * mat3x3[[(2 - i), (i + j) % 3]]
-        }
-        sum = sum + prod;
-    }
-    return sum;
-}
-
 type Point2D = Array1<f64>;
 
 fn g1(coord: f64) -> f64 {
-    if coord > 1.0 || coord < -1.0 {
-        panic!("g1() argument out of bounds, !( -1.0 <= {} <= 1.0 )", coord)
-    };
+    debug_assert!(
+        (-1.0..=1.0).contains(&coord),
+        "g1() argument out of bounds, !( -1.0 <= {} <= 1.0 )",
+        coord
+    );
     coord / 2.0 + 0.5
 }
 fn g2(coord: f64) -> f64 {
-    if coord > 1.0 || coord < -1.0 {
-        panic!("g2() argument out of bounds, !( -1.0 <= {} <= 1.0 )", coord)
-    };
+    debug_assert!(
+        (-1.0..=1.0).contains(&coord),
+        "g2() argument out of bounds, !( -1.0 <= {} <= 1.0 )",
+        coord
+    );
     -coord / 2.0 + 0.5
 }
 
@@ -118,81 +103,63 @@ fn interpolation_function_derivatives_vector(xy1: Point2D) -> Array2<f64> {
     ];
 }
 
+/// Splits the triangle into three quadrilaterals at the centroid and integrates each of them with
+/// a tensor Gauss-Legendre rule.
+/// The quadrilaterals are mapped orientation preserving, so the integral has the sign of
+/// [`Simplex2D::get_volume()`](crate::simplex::Simplex::get_volume).
 pub struct Quadrilateral2DIntegrator {
     gauss_degree: usize,
+    // Die Gauss-Punkte werden einmal bei der Konstruktion bestimmt
+    gauss_points: Array1<f64>,
+    gauss_weights: Array1<f64>,
 }
 
 impl Quadrilateral2DIntegrator {
-    pub fn new(gauss_degree: usize) -> Self {
-        Self { gauss_degree }
+    /// At least one Gauss point is needed, otherwise
+    /// [`IntegrationError::UnsupportedOrder`](crate::error::IntegrationError::UnsupportedOrder).
+    pub fn new(gauss_degree: usize) -> Result<Self> {
+        let (gauss_points, gauss_weights) = gauss_legendre_golub_welsch(gauss_degree)?;
+        Ok(Self {
+            gauss_degree,
+            gauss_points,
+            gauss_weights,
+        })
     }
 
-    fn get_gauss_points(&self) -> Array1<f64> {
-        match self.gauss_degree {
-            1 => {
-                array![0.]
-            }
-            2 => {
-                array![-(1. / 3.0_f64).sqrt(), (1. / 3.0_f64).sqrt()]
-            }
-            3 => {
-                array![-(3. / 5.0_f64).sqrt(), 0., (3. / 5.0_f64).sqrt()]
-            }
-            _ => {
-                unimplemented!("For higher order gaussian Integration the Golub Welsh Algorithm should be implemented.")
-            }
-        }
-    }
-    fn get_gauss_weights(&self) -> Array1<f64> {
-        match self.gauss_degree {
-            1 => {
-                array![2.]
-            }
-            2 => {
-                array![1., 1.]
-            }
-            3 => {
-                array![5. / 9., 8. / 9., 5. / 9.]
-            }
-            _ => {
-                unimplemented!("For higher order gaussian Integration the Golub Welsh Algorithm should be implemented.")
-            }
-        }
-    }
     fn get_quadrilateral_D1() -> Array2<f64> {
-        // B1 B5 B4 BC
+        // B1 B4 B5 BC
         return array![
             [1., 0.5, 0.5, 1. / 3.],
-            [0., 0.5, 0., 1. / 3.],
-            [0., 0., 0.5, 1. / 3.]
+            [0., 0., 0.5, 1. / 3.],
+            [0., 0.5, 0., 1. / 3.]
         ];
     }
     fn get_quadrilateral_D2() -> Array2<f64> {
-        // B2 B6 B5 BC
+        // B2 B5 B6 BC
         return array![
-            [0., 0., 0.5, 1. / 3.],
+            [0., 0.5, 0., 1. / 3.],
             [1., 0.5, 0.5, 1. / 3.],
-            [0., 0.5, 0., 1. / 3.]
+            [0., 0., 0.5, 1. / 3.]
         ];
     }
     fn get_quadrilateral_D3() -> Array2<f64> {
-        // B3 B4 B6 BC
+        // B3 B6 B4 BC
         return array![
-            [0., 0.5, 0., 1. / 3.],
             [0., 0., 0.5, 1. / 3.],
+            [0., 0.5, 0., 1. / 3.],
             [1., 0.5, 0.5, 1. / 3.]
         ];
     }
 
-    fn integrate_quadrilateral<T: Simplex2DFunction>(
+    fn integrate_quadrilateral<T: SimplexFunction<2>>(
         &self,
         barycentric_domain: &Array2<f64>,
         func: &Box<T>,
         simplex: &Simplex2D,
     ) -> T::Return {
         let mut sum = func.additive_neutral_element();
-        let gauss_points = self.get_gauss_points();
-        let gauss_weights = self.get_gauss_weights();
+        let gauss_points = &self.gauss_points;
+        let gauss_weights = &self.gauss_weights;
         for i in 0..self.gauss_degree {
             for j in 0..self.gauss_degree {
                 let X = gauss_points[i];
@@ -218,20 +185,23 @@ impl Quadrilateral2DIntegrator {
     }
 }
 
-impl<IntegratorDummy> Simplex2DIntegrator<IntegratorDummy> for Quadrilateral2DIntegrator {
+impl<IntegratorDummy> SimplexIntegrator<2, IntegratorDummy> for Quadrilateral2DIntegrator {
     fn dupe(&self) -> Self {
         Self {
             gauss_degree: self.gauss_degree,
+            gauss_points: self.gauss_points.clone(),
+            gauss_weights: self.gauss_weights.clone(),
         }
     }
 
-    fn integrate_over_domain<T: Simplex2DFunction>(
+    fn integrate_over_domain<T: SimplexFunction<2>>(
         &self,
         transformation: &Array2<f64>,
         func: &Box<T>,
         simplex: &Simplex2D,
         cached_data: &mut IntegratorDummy,
-    ) -> T::Return {
+    ) -> Result<T::Return> {
+        check_shape(transformation, [3, 3])?;
         let mut sum = func.additive_neutral_element();
         let d1 = Quadrilateral2DIntegrator::get_quadrilateral_D1();
         let d1 = transformation.dot(&d1);
@@ -242,10 +212,7 @@ impl<IntegratorDummy> Simplex2DIntegrator<IntegratorDummy> for Quadrilateral2DIn
         let d3 = Quadrilateral2DIntegrator::get_quadrilateral_D3();
         let d3 = transformation.dot(&d3);
         sum.add_assign(&self.integrate_quadrilateral(&d3, func, simplex));
-
-        // Weil ich die Orientierung verhaut habe, steht hier ein Minus...
-        sum *= -1.;
-        return sum;
+        return Ok(sum);
     }
 }
 
@@ -256,8 +223,10 @@ mod tests {
     use crate::integrator_tests;
 
     integrator_tests! {
-        gauss_order1: Quadrilateral2DIntegrator: Quadrilateral2DIntegrator::new(1), IntegratorDummy: IntegratorDummy::get(),
-        gauss_order2: Quadrilateral2DIntegrator: Quadrilateral2DIntegrator::new(2), IntegratorDummy: IntegratorDummy::get(),
-        gauss_order3: Quadrilateral2DIntegrator: Quadrilateral2DIntegrator::new(3), IntegratorDummy: IntegratorDummy::get(),
+        gauss_order1: Quadrilateral2DIntegrator: Quadrilateral2DIntegrator::new(1).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        gauss_order2: Quadrilateral2DIntegrator: Quadrilateral2DIntegrator::new(2).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        gauss_order3: Quadrilateral2DIntegrator: Quadrilateral2DIntegrator::new(3).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        gauss_order5: Quadrilateral2DIntegrator: Quadrilateral2DIntegrator::new(5).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        gauss_order8: Quadrilateral2DIntegrator: Quadrilateral2DIntegrator::new(8).unwrap(), IntegratorDummy: IntegratorDummy::get(),
     }
 }
@@ -1,6 +1,7 @@
 use crate::integration_3d::domain::*;
 use ndarray::{array, Array1};
-use std::cell::RefCell;
+use num_complex::Complex64;
+use std::sync::Mutex;
 
 pub struct Constant3DFunction;
 
@@ -34,32 +35,99 @@ impl Simplex3DFunction for Multiplicative3DFunction {
     }
 }
 
+/// The plane wave `e^{i k·x}` with the wave vector `k` at the real point `x`.
+pub struct PlaneWave3DFunction {
+    wave_vector: Array1<f64>,
+}
+
+impl PlaneWave3DFunction {
+    pub fn new(wave_vector: [f64; 3]) -> Self {
+        Self {
+            wave_vector: Array1::from_vec(wave_vector.to_vec()),
+        }
+    }
+
+    fn value(&self, xi: &Array1<f64>, simplex: &Simplex3D) -> Complex64 {
+        Complex64::cis(self.wave_vector.dot(&simplex.get_points_borrow().dot(xi)))
+    }
+}
+
+impl Simplex3DFunction for PlaneWave3DFunction {
+    type Return = ResultTypeWrapper<Complex64>;
+    fn function(
+        &self,
+        xi1: f64,
+        xi2: f64,
+        xi3: f64,
+        xi4: f64,
+        simplex: &Simplex3D,
+    ) -> Self::Return {
+        ResultTypeWrapper::new(self.value(&array![xi1, xi2, xi3, xi4], simplex))
+    }
+}
+
+/// The load vector `e^{i k·x} N_i` of the linear shape functions `N_i = xi_i`.
+pub struct PlaneWaveLoad3DFunction {
+    wave: PlaneWave3DFunction,
+}
+
+impl PlaneWaveLoad3DFunction {
+    pub fn new(wave_vector: [f64; 3]) -> Self {
+        Self {
+            wave: PlaneWave3DFunction::new(wave_vector),
+        }
+    }
+}
+
+impl Simplex3DFunction for PlaneWaveLoad3DFunction {
+    type Return = ResultTypeWrapper<Array1<Complex64>>;
+
+    fn additive_neutral_element(&self) -> Self::Return {
+        ResultTypeWrapper::new(Array1::zeros([4]))
+    }
+
+    fn function(
+        &self,
+        xi1: f64,
+        xi2: f64,
+        xi3: f64,
+        xi4: f64,
+        simplex: &Simplex3D,
+    ) -> Self::Return {
+        let xi = array![xi1, xi2, xi3, xi4];
+        let value = self.wave.value(&xi, simplex);
+        ResultTypeWrapper::new(xi.mapv(|f| value * f))
+    }
+}
+
 /// A struct which will record all function evaluations of the given [`Simplex3DFunction`]
+/// The history is guarded by a mutex, with the feature `parallel` the order of the recorded
+/// points depends on the scheduling of the threads.
 pub struct Function3DHistory<F: Simplex3DFunction> {
-    history: RefCell<Vec<Array1<f64>>>,
+    history: Mutex<Vec<Array1<f64>>>,
     function: F,
 }
 
 impl<F: Simplex3DFunction> Function3DHistory<F> {
     pub fn new(func: F) -> Self {
         return Self {
-            history: RefCell::new(Vec::new()),
+            history: Mutex::new(Vec::new()),
             function: func,
         };
     }
     /// A history of all function evaluations will be returned.
     /// Drops the struct upon after this function.
     pub fn get_history(self) -> Vec<Array1<f64>> {
-        return self.history.take();
+        return self.history.into_inner().unwrap();
     }
 
     pub fn function_evaluations(&self) -> usize {
-        return self.history.borrow().len();
+        return self.history.lock().unwrap().len();
     }
 
     /// Deletes the history of the function evaluations.
     pub fn delete_history(&self) {
-        self.history.borrow_mut().clear();
+        self.history.lock().unwrap().clear();
     }
 }
 
@@ -76,9 +144,101 @@ impl<F: Simplex3DFunction> Simplex3DFunction for Function3DHistory<F> {
     ) -> Self::Return {
         let result = self.function.function(xi1, xi2, xi3, xi4, simplex);
         {
-            let mut history = self.history.borrow_mut();
+            let mut history = self.history.lock().unwrap();
             history.push(array![xi1, xi2, xi3, xi4]);
         }
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ndarray::array;
+
+    use super::{PlaneWave3DFunction, PlaneWaveLoad3DFunction};
+    use crate::common::IntegratorDummy;
+    use crate::integration_3d::domain::{PhysicalFunction3D, Simplex3D, SimplexIntegrator};
+    use crate::integration_3d::integrators::{
+        EdgeSubdivisionIntegrator, Hierarchic3DIntegrator, Hierarchic3DIntegratorData,
+        Quadrilateral3DIntegrator, TetrahedralIntegrator,
+    };
+
+    #[test]
+    fn plane_wave_with_all_integrators() {
+        let sim = Simplex3D::new_from_points(
+            &array![0., 0., 0.],
+            &array![1., 0., 0.],
+            &array![0., 1., 0.],
+            &array![0., 0., 1.],
+        );
+        let func = Box::new(PlaneWave3DFunction::new([2., -1., 1.5]));
+        let reference = TetrahedralIntegrator::new(10)
+            .unwrap()
+            .integrate_simplex(&func, &sim, &mut IntegratorDummy::get())
+            .unwrap()
+            .get();
+        // Bei k = 0 wäre es das Volumen 1/6
+        assert!(reference.norm() < 1. / 6.);
+
+        let results = [
+            Quadrilateral3DIntegrator::new(8)
+                .unwrap()
+                .integrate_simplex(&func, &sim, &mut IntegratorDummy::get()),
+            EdgeSubdivisionIntegrator::new(TetrahedralIntegrator::new(8).unwrap(), 2)
+                .unwrap()
+                .integrate_simplex(&func, &sim, &mut IntegratorDummy::get()),
+            Hierarchic3DIntegrator::new(TetrahedralIntegrator::new(8).unwrap(), false, 1e-9)
+                .integrate_simplex(&func, &sim, &mut Hierarchic3DIntegratorData::new_cache()),
+        ];
+        for result in results {
+            let result = result.unwrap().get();
+            assert!(
+                (result - reference).norm() < 1e-8,
+                "Expected: {}, Actual: {}",
+                reference,
+                result
+            );
+        }
+
+        let load = Box::new(PlaneWaveLoad3DFunction::new([2., -1., 1.5]));
+        let load = TetrahedralIntegrator::new(10)
+            .unwrap()
+            .integrate_simplex(&load, &sim, &mut IntegratorDummy::get())
+            .unwrap()
+            .get();
+        assert_eq!(load.len(), 4);
+        assert!((load.sum() - reference).norm() < 1e-12);
+    }
+
+    #[test]
+    fn physical_and_closure_integrands() {
+        let sim = Simplex3D::new_from_points(
+            &array![0., 0., 0.],
+            &array![1., 0., 0.],
+            &array![0., 1., 0.],
+            &array![0., 0., 1.],
+        );
+        let inte = TetrahedralIntegrator::new(3).unwrap();
+        let physical = inte
+            .integrate_simplex(
+                &Box::new(PhysicalFunction3D::new(|x: &[f64; 3]| x[0] * x[1] * x[2])),
+                &sim,
+                &mut IntegratorDummy::get(),
+            )
+            .unwrap()
+            .get();
+        assert!((physical - 1. / 720.).abs() < 1e-15);
+
+        let closure = inte
+            .integrate_simplex(
+                &Box::new(|_: f64, _: f64, _: f64, _: f64, simplex: &Simplex3D| {
+                    simplex.get_volume().recip()
+                }),
+                &sim,
+                &mut IntegratorDummy::get(),
+            )
+            .unwrap()
+            .get();
+        assert!((closure - 1.).abs() < 1e-14);
+    }
+}
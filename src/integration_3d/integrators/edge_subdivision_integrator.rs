@@ -1,56 +1,170 @@
 use ndarray::{array, Array2};
 
-use crate::common::{IntegratorDummy, det3x3};
+use crate::common::{ordered_map, IntegratorDummy};
+use crate::error::{check_shape, IntegrationError, Result};
 use crate::integration_3d::domain::{
-    det4x4, Simplex3D, Simplex3DFunction, Simplex3DIntegrator, Simplex3DResultType,
+    Simplex3D, SimplexFunction, SimplexIntegrator, SimplexResultType,
 };
+use crate::simplex::{CompositeCache, IntegratorCache};
 
-pub struct EdgeSubdivisionIntegrator<I: Simplex3DIntegrator<IntegratorDummy>> {
+/// Divides every edge into `order` parts and integrates the subtetrahedra, including the ones
+/// splitting the octahedra, with the base integrator.
+/// All subtetrahedra keep the orientation, so the sign is the one of the base integrator.
+pub struct EdgeSubdivisionIntegrator<I> {
     base_integrator: I,
     order: usize,
 }
 
-impl<I: Simplex3DIntegrator<IntegratorDummy>> EdgeSubdivisionIntegrator<I> {
-    pub fn new(base_integrator: I, order: usize) -> Self {
-        return EdgeSubdivisionIntegrator {
+impl<I> EdgeSubdivisionIntegrator<I> {
+    /// Every edge is divided into `order` parts, so the order must be at least 1.
+    pub fn new(base_integrator: I, order: usize) -> Result<Self> {
+        if order == 0 {
+            return Err(IntegrationError::UnsupportedOrder {
+                rule: "edge subdivision",
+                order,
+            });
+        }
+        return Ok(EdgeSubdivisionIntegrator {
             base_integrator,
             order,
-        };
+        });
     }
 }
 
-impl<I: Simplex3DIntegrator<IntegratorDummy>> Simplex3DIntegrator<IntegratorDummy>
+impl<I: SimplexIntegrator<3, IntegratorDummy>> SimplexIntegrator<3, IntegratorDummy>
     for EdgeSubdivisionIntegrator<I>
 {
-    fn integrate_over_domain<T: Simplex3DFunction>(
+    fn dupe(&self) -> Self {
+        return Self {
+            base_integrator: self.base_integrator.dupe(),
+            order: self.order,
+        };
+    }
+
+    fn integrate_over_domain<T: SimplexFunction<3>>(
         &self,
         real_transformation: &Array2<f64>,
         func: &Box<T>,
         simplex: &Simplex3D,
         _cached_data: &mut IntegratorDummy,
-    ) -> T::Return {
-        let order = self.order;
-        let order_fl = order as f64;
+    ) -> Result<T::Return> {
+        // Der Basisintegrator hat keinen Zustand, die Caches der Kinder sind leer
+        SimplexIntegrator::<3, CompositeCache<IntegratorDummy>>::integrate_over_domain(
+            self,
+            real_transformation,
+            func,
+            simplex,
+            &mut CompositeCache::new_cache(),
+        )
+    }
+}
 
+/// With a [`CompositeCache`] the base integrator keeps its cache per subsimplex, so it may be
+/// e.g. a hierarchic integrator.
+impl<C: IntegratorCache, I: SimplexIntegrator<3, C>> SimplexIntegrator<3, CompositeCache<C>>
+    for EdgeSubdivisionIntegrator<I>
+{
+    fn dupe(&self) -> Self {
+        Self {
+            base_integrator: self.base_integrator.dupe(),
+            order: self.order,
+        }
+    }
+
+    fn integrate_over_domain<T: SimplexFunction<3>>(
+        &self,
+        real_transformation: &Array2<f64>,
+        func: &Box<T>,
+        simplex: &Simplex3D,
+        cached_data: &mut CompositeCache<C>,
+    ) -> Result<T::Return> {
+        check_shape(real_transformation, [4, 4])?;
+        let sub_transformations = sub_transformations(self.order, real_transformation);
+        let sub_caches = cached_data.sub_caches_mut(sub_transformations.len());
+        // Die Teilsimplizes werden (evtl. parallel) integriert und in ihrer Reihenfolge aufsummiert
+        let sub_results = ordered_map(
+            sub_transformations
+                .into_iter()
+                .zip(sub_caches.iter_mut())
+                .collect(),
+            |(transformation, sub_cache)| {
+                self.base_integrator.integrate_over_domain(
+                    &transformation,
+                    func,
+                    simplex,
+                    sub_cache,
+                )
+            },
+        )
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?;
         let mut result = func.additive_neutral_element();
-        //todo!("Generalize to 3 Dimensions, currently it's only two.");
-        let order = order - 1;
-        for i in 0..=order {
-            for j in 0..=(order - i) {
-                for k in 0..=(order - i - j) {
-                    // Die vierte Koordinate
-                    let m = order - i - j - k;
-                    //println!("{},{},{},{}", j, i, k, m);
-                    // Umbenennung
-                    let (i0, i1, i2, i3) = (j as f64, i as f64, k as f64, m as f64);
-                    // Shift zum größeren Simplex
-                    let i0 = i0 + 1.;
-                    //
-                    //println!("{:2.0},{:2.0},{:2.0},{:2.0}", i0, i1, i2, i3);
+        for sub_result in sub_results {
+            result.add_assign(&sub_result);
+        }
+        Ok(result)
+    }
+}
 
-                    // Dieser Fall geht immer
+/// The transformations of the `order^3` subtetrahedra relative to the `real_transformation`.
+pub(crate) fn sub_transformations(
+    order: usize,
+    real_transformation: &Array2<f64>,
+) -> Vec<Array2<f64>> {
+    let order_fl = order as f64;
+
+    // Zuerst werden alle Teilsimplizes bestimmt
+    let mut sub_transformations = Vec::new();
+    //todo!("Generalize to 3 Dimensions, currently it's only two.");
+    let order = order - 1;
+    for i in 0..=order {
+        for j in 0..=(order - i) {
+            for k in 0..=(order - i - j) {
+                // Die vierte Koordinate
+                let m = order - i - j - k;
+                // Umbenennung
+                let (i0, i1, i2, i3) = (j as f64, i as f64, k as f64, m as f64);
+                // Shift zum größeren Simplex
+                let i0 = i0 + 1.;
+
+                // Dieser Fall geht immer
+                let ch_transformation = array![
+                    [i0 / order_fl, i1 / order_fl, i2 / order_fl, i3 / order_fl],
+                    [
+                        (i0 - 1.) / order_fl,
+                        (i1 + 1.) / order_fl,
+                        i2 / order_fl,
+                        i3 / order_fl
+                    ],
+                    [
+                        (i0 - 1.) / order_fl,
+                        i1 / order_fl,
+                        (i2 + 1.) / order_fl,
+                        i3 / order_fl
+                    ],
+                    [
+                        (i0 - 1.) / order_fl,
+                        i1 / order_fl,
+                        i2 / order_fl,
+                        (i3 + 1.) / order_fl
+                    ]
+                ];
+                let ch_transformation = ch_transformation.reversed_axes();
+                let transformation = real_transformation.dot(&ch_transformation);
+
+                //let ch_transformation = ch_transformation.reversed_axes();
+                //let transformation = transformation.dot(&ch_transformation);
+                sub_transformations.push(transformation);
+
+                // Oktaederfall
+                if i0 >= 2. {
                     let ch_transformation = array![
-                        [i0 / order_fl, i1 / order_fl, i2 / order_fl, i3 / order_fl],
+                        [
+                            (i0 - 1.) / order_fl,
+                            i1 / order_fl,
+                            i2 / order_fl,
+                            (i3 + 1.) / order_fl
+                        ],
                         [
                             (i0 - 1.) / order_fl,
                             (i1 + 1.) / order_fl,
@@ -63,11 +177,44 @@ impl<I: Simplex3DIntegrator<IntegratorDummy>> Simplex3DIntegrator<IntegratorDumm
                             (i2 + 1.) / order_fl,
                             i3 / order_fl
                         ],
+                        [
+                            (i0 - 2.) / order_fl,
+                            (i1 + 1.) / order_fl,
+                            (i2 + 1.) / order_fl,
+                            i3 / order_fl
+                        ]
+                    ];
+                    let ch_transformation = ch_transformation.reversed_axes();
+                    let transformation = real_transformation.dot(&ch_transformation);
+
+                    //let ch_transformation = ch_transformation.reversed_axes();
+                    //let transformation = transformation.dot(&ch_transformation);
+                    sub_transformations.push(transformation);
+
+                    let ch_transformation = array![
+                        [
+                            (i0 - 1.) / order_fl,
+                            i1 / order_fl,
+                            (i2 + 1.) / order_fl,
+                            i3 / order_fl
+                        ],
+                        [
+                            (i0 - 2.) / order_fl,
+                            i1 / order_fl,
+                            (i2 + 1.) / order_fl,
+                            (i3 + 1.) / order_fl
+                        ],
                         [
                             (i0 - 1.) / order_fl,
                             i1 / order_fl,
                             i2 / order_fl,
                             (i3 + 1.) / order_fl
+                        ],
+                        [
+                            (i0 - 2.) / order_fl,
+                            (i1 + 1.) / order_fl,
+                            (i2 + 1.) / order_fl,
+                            i3 / order_fl
                         ]
                     ];
                     let ch_transformation = ch_transformation.reversed_axes();
@@ -75,255 +222,131 @@ impl<I: Simplex3DIntegrator<IntegratorDummy>> Simplex3DIntegrator<IntegratorDumm
 
                     //let ch_transformation = ch_transformation.reversed_axes();
                     //let transformation = transformation.dot(&ch_transformation);
-                    result.add_assign(&{
-                        let r = self.base_integrator.integrate_over_domain(
-                            &transformation,
-                            func,
-                            simplex,
-                            &mut IntegratorDummy,
-                        );
-                        //println!("Tet 1: {}", det3x3(&transformation));
-                        r
-                    });
-
-                    // Oktaederfall
-                    if i0 >= 2. {
-                        let ch_transformation = array![
-                            [
-                                (i0 - 1.) / order_fl,
-                                i1 / order_fl,
-                                i2 / order_fl,
-                                (i3 + 1.) / order_fl
-                            ],
-                            [
-                                (i0 - 1.) / order_fl,
-                                (i1 + 1.) / order_fl,
-                                i2 / order_fl,
-                                i3 / order_fl
-                            ],
-                            [
-                                (i0 - 1.) / order_fl,
-                                i1 / order_fl,
-                                (i2 + 1.) / order_fl,
-                                i3 / order_fl
-                            ],
-                            [
-                                (i0 - 2.) / order_fl,
-                                (i1 + 1.) / order_fl,
-                                (i2 + 1.) / order_fl,
-                                i3 / order_fl
-                            ]
-                        ];
-                        let ch_transformation = ch_transformation.reversed_axes();
-                        //println!("DetOkt1 = {}", det4x4(&ch_transformation));
-                        let transformation = real_transformation.dot(&ch_transformation);
-
-                        //let ch_transformation = ch_transformation.reversed_axes();
-                        //let transformation = transformation.dot(&ch_transformation);
-                        result.add_assign(&{
-                            let r = self.base_integrator.integrate_over_domain(
-                                &transformation,
-                                func,
-                                simplex,
-                                &mut IntegratorDummy,
-                            );
-                            println!("Okt 1: {}", det3x3(&transformation));
-                            r
-                        });
+                    sub_transformations.push(transformation);
 
-                        let ch_transformation = array![
-                            [
-                                (i0 - 1.) / order_fl,
-                                i1 / order_fl,
-                                (i2 + 1.) / order_fl,
-                                i3 / order_fl
-                            ],
-                            [
-                                (i0 - 2.) / order_fl,
-                                i1 / order_fl,
-                                (i2 + 1.) / order_fl,
-                                (i3 + 1.) / order_fl
-                            ],
-                            [
-                                (i0 - 1.) / order_fl,
-                                i1 / order_fl,
-                                i2 / order_fl,
-                                (i3 + 1.) / order_fl
-                            ],
-                            [
-                                (i0 - 2.) / order_fl,
-                                (i1 + 1.) / order_fl,
-                                (i2 + 1.) / order_fl,
-                                i3 / order_fl
-                            ]
-                        ];
-                        let ch_transformation = ch_transformation.reversed_axes();
-                        //println!("DetOkt2 = {}", det4x4(&ch_transformation));
-                        let transformation = real_transformation.dot(&ch_transformation);
-
-                        //let ch_transformation = ch_transformation.reversed_axes();
-                        //let transformation = transformation.dot(&ch_transformation);
-                        result.add_assign(&{
-                            let r = self.base_integrator.integrate_over_domain(
-                                &transformation,
-                                func,
-                                simplex,
-                                &mut IntegratorDummy,
-                            );
-                            //println!("Okt 2: {}", det3x3(&transformation));
-                            r
-                        });
-
-                        let ch_transformation = array![
-                            [
-                                (i0 - 2.) / order_fl,
-                                (i1 + 1.) / order_fl,
-                                (i2 + 1.) / order_fl,
-                                i3 / order_fl
-                            ],
-                            [
-                                (i0 - 2.) / order_fl,
-                                i1 / order_fl,
-                                (i2 + 1.) / order_fl,
-                                (i3 + 1.) / order_fl
-                            ],
-                            [
-                                (i0 - 1.) / order_fl,
-                                i1 / order_fl,
-                                i2 / order_fl,
-                                (i3 + 1.) / order_fl
-                            ],
-                            [
-                                (i0 - 2.) / order_fl,
-                                (i1 + 1.) / order_fl,
-                                i2 / order_fl,
-                                (i3 + 1.) / order_fl
-                            ]
-                        ];
-                        let ch_transformation = ch_transformation.reversed_axes();
-                        //println!("DetOkt3 = {}", det4x4(&ch_transformation));
-                        let transformation = real_transformation.dot(&ch_transformation);
+                    let ch_transformation = array![
+                        [
+                            (i0 - 2.) / order_fl,
+                            (i1 + 1.) / order_fl,
+                            (i2 + 1.) / order_fl,
+                            i3 / order_fl
+                        ],
+                        [
+                            (i0 - 2.) / order_fl,
+                            i1 / order_fl,
+                            (i2 + 1.) / order_fl,
+                            (i3 + 1.) / order_fl
+                        ],
+                        [
+                            (i0 - 1.) / order_fl,
+                            i1 / order_fl,
+                            i2 / order_fl,
+                            (i3 + 1.) / order_fl
+                        ],
+                        [
+                            (i0 - 2.) / order_fl,
+                            (i1 + 1.) / order_fl,
+                            i2 / order_fl,
+                            (i3 + 1.) / order_fl
+                        ]
+                    ];
+                    let ch_transformation = ch_transformation.reversed_axes();
+                    let transformation = real_transformation.dot(&ch_transformation);
 
-                        //let ch_transformation = ch_transformation.reversed_axes();
-                        //let transformation = transformation.dot(&ch_transformation);
-                        result.add_assign(&{
-                            let r = self.base_integrator.integrate_over_domain(
-                                &transformation,
-                                func,
-                                simplex,
-                                &mut IntegratorDummy,
-                            );
-                            //println!("Okt 3: {}", det3x3(&transformation));
-                            r
-                        });
+                    //let ch_transformation = ch_transformation.reversed_axes();
+                    //let transformation = transformation.dot(&ch_transformation);
+                    sub_transformations.push(transformation);
 
-                        let ch_transformation = array![
-                            [
-                                (i0 - 2.) / order_fl,
-                                (i1 + 1.) / order_fl,
-                                i2 / order_fl,
-                                (i3 + 1.) / order_fl
-                            ],
-                            [
-                                (i0 - 2.) / order_fl,
-                                (i1 + 1.) / order_fl,
-                                (i2 + 1.) / order_fl,
-                                i3 / order_fl
-                            ],
-                            [
-                                (i0 - 1.) / order_fl,
-                                (i1 + 1.) / order_fl,
-                                i2 / order_fl,
-                                i3 / order_fl
-                            ],
-                            [
-                                (i0 - 1.) / order_fl,
-                                i1 / order_fl,
-                                i2 / order_fl,
-                                (i3 + 1.) / order_fl
-                            ]
-                        ];
-                        let ch_transformation = ch_transformation.reversed_axes();
-                        //println!("DetOkt4 = {}", det4x4(&ch_transformation));
-                        let transformation = real_transformation.dot(&ch_transformation);
+                    let ch_transformation = array![
+                        [
+                            (i0 - 2.) / order_fl,
+                            (i1 + 1.) / order_fl,
+                            i2 / order_fl,
+                            (i3 + 1.) / order_fl
+                        ],
+                        [
+                            (i0 - 2.) / order_fl,
+                            (i1 + 1.) / order_fl,
+                            (i2 + 1.) / order_fl,
+                            i3 / order_fl
+                        ],
+                        [
+                            (i0 - 1.) / order_fl,
+                            (i1 + 1.) / order_fl,
+                            i2 / order_fl,
+                            i3 / order_fl
+                        ],
+                        [
+                            (i0 - 1.) / order_fl,
+                            i1 / order_fl,
+                            i2 / order_fl,
+                            (i3 + 1.) / order_fl
+                        ]
+                    ];
+                    let ch_transformation = ch_transformation.reversed_axes();
+                    let transformation = real_transformation.dot(&ch_transformation);
 
-                        //let ch_transformation = ch_transformation.reversed_axes();
-                        //let transformation = transformation.dot(&ch_transformation);
-                        result.add_assign(&{
-                            let r = self.base_integrator.integrate_over_domain(
-                                &transformation,
-                                func,
-                                simplex,
-                                &mut IntegratorDummy,
-                            );
-                            //println!("Okt 4: {}", det3x3(&transformation));
-                            r
-                        });
-                    }
-                    // Umgedrehter Tetraederfall
-                    if i0 >= 3. {
-                        let ch_transformation = array![
-                            [
-                                (i0 - 2.) / order_fl,
-                                i1 / order_fl,
-                                (i2 + 1.) / order_fl,
-                                (i3 + 1.) / order_fl
-                            ],
-                            [
-                                (i0 - 2.) / order_fl,
-                                (i1 + 1.) / order_fl,
-                                i2 / order_fl,
-                                (i3 + 1.) / order_fl
-                            ],
-                            [
-                                (i0 - 2.) / order_fl,
-                                (i1 + 1.) / order_fl,
-                                (i2 + 1.) / order_fl,
-                                i3 / order_fl
-                            ],
-                            [
-                                (i0 - 3.) / order_fl,
-                                (i1 + 1.) / order_fl,
-                                (i2 + 1.) / order_fl,
-                                (i3 + 1.) / order_fl
-                            ]
-                        ];
-                        let ch_transformation = ch_transformation.reversed_axes();
-                        //println!("DetTet2 = {}", det4x4(&ch_transformation));
-                        let transformation = real_transformation.dot(&ch_transformation);
+                    //let ch_transformation = ch_transformation.reversed_axes();
+                    //let transformation = transformation.dot(&ch_transformation);
+                    sub_transformations.push(transformation);
+                }
+                // Umgedrehter Tetraederfall
+                if i0 >= 3. {
+                    let ch_transformation = array![
+                        [
+                            (i0 - 2.) / order_fl,
+                            i1 / order_fl,
+                            (i2 + 1.) / order_fl,
+                            (i3 + 1.) / order_fl
+                        ],
+                        [
+                            (i0 - 2.) / order_fl,
+                            (i1 + 1.) / order_fl,
+                            i2 / order_fl,
+                            (i3 + 1.) / order_fl
+                        ],
+                        [
+                            (i0 - 2.) / order_fl,
+                            (i1 + 1.) / order_fl,
+                            (i2 + 1.) / order_fl,
+                            i3 / order_fl
+                        ],
+                        [
+                            (i0 - 3.) / order_fl,
+                            (i1 + 1.) / order_fl,
+                            (i2 + 1.) / order_fl,
+                            (i3 + 1.) / order_fl
+                        ]
+                    ];
+                    let ch_transformation = ch_transformation.reversed_axes();
+                    let transformation = real_transformation.dot(&ch_transformation);
 
-                        //let ch_transformation = ch_transformation.reversed_axes();
-                        //let transformation = transformation.dot(&ch_transformation);
-                        result.add_assign(&{
-                            let r = self.base_integrator.integrate_over_domain(
-                                &transformation,
-                                func,
-                                simplex,
-                                &mut IntegratorDummy,
-                            );
-                            println!("Tet 2: {}", det3x3(&transformation));
-                            r
-                        });
-                    }
+                    //let ch_transformation = ch_transformation.reversed_axes();
+                    //let transformation = transformation.dot(&ch_transformation);
+                    sub_transformations.push(transformation);
                 }
             }
         }
-        return result;
     }
+    sub_transformations
 }
 
-
 #[cfg(test)]
 mod tests {
     use crate::common::IntegratorDummy;
     use crate::integration_3d::integrators::{
-        EdgeSubdivisionIntegrator, Quadrilateral3DIntegrator,
+        EdgeSubdivisionIntegrator, Hierarchic3DIntegrator, Hierarchic3DIntegratorData,
+        Quadrilateral3DIntegrator, TetrahedralIntegrator,
     };
     use crate::integrator_tests_3d;
+    use crate::simplex::CompositeCache;
 
     integrator_tests_3d! {
-        order2_quad: EdgeSubdivisionIntegrator<Quadrilateral3DIntegrator>: EdgeSubdivisionIntegrator::new(Quadrilateral3DIntegrator::new(2),2), IntegratorDummy: IntegratorDummy::get(),
-        order3_quad: EdgeSubdivisionIntegrator<Quadrilateral3DIntegrator>: EdgeSubdivisionIntegrator::new(Quadrilateral3DIntegrator::new(2),3), IntegratorDummy: IntegratorDummy::get(),
-        order4_quad: EdgeSubdivisionIntegrator<Quadrilateral3DIntegrator>: EdgeSubdivisionIntegrator::new(Quadrilateral3DIntegrator::new(2),4), IntegratorDummy: IntegratorDummy::get(),
+        order2_quad: EdgeSubdivisionIntegrator<Quadrilateral3DIntegrator>: EdgeSubdivisionIntegrator::new(Quadrilateral3DIntegrator::new(2).unwrap(),2).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order3_quad: EdgeSubdivisionIntegrator<Quadrilateral3DIntegrator>: EdgeSubdivisionIntegrator::new(Quadrilateral3DIntegrator::new(2).unwrap(),3).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order4_quad: EdgeSubdivisionIntegrator<Quadrilateral3DIntegrator>: EdgeSubdivisionIntegrator::new(Quadrilateral3DIntegrator::new(2).unwrap(),4).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order2_tet: EdgeSubdivisionIntegrator<TetrahedralIntegrator>: EdgeSubdivisionIntegrator::new(TetrahedralIntegrator::new(5).unwrap(),2).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order3_tet: EdgeSubdivisionIntegrator<TetrahedralIntegrator>: EdgeSubdivisionIntegrator::new(TetrahedralIntegrator::new(5).unwrap(),3).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        hierarchic_children: EdgeSubdivisionIntegrator<Hierarchic3DIntegrator<TetrahedralIntegrator>>: EdgeSubdivisionIntegrator::new(Hierarchic3DIntegrator::new(TetrahedralIntegrator::new(2).unwrap(),false,1e-2),2).unwrap(), CompositeCache<Hierarchic3DIntegratorData>: CompositeCache::new_cache(),
     }
-}
\ No newline at end of file
+}
@@ -1,18 +1,34 @@
-use std::{mem, ops::AddAssign};
+use std::collections::{BinaryHeap, HashMap};
+use std::io::{Read, Write};
 
 use indextree::{Arena, NodeEdge, NodeId};
 use ndarray::prelude::*;
 
-use crate::common::IntegratorDummy;
+use crate::common::{ordered_map, IntegratorDummy};
+use crate::embedded::{EmbeddedIntegrator, EmbeddedResult};
+use crate::error::{check_shape, Result};
 use crate::integration_3d::{
-    domain::{Simplex3D, Simplex3DFunction, Simplex3DIntegrator},
-    Simplex3DResultType,
+    domain::{Simplex3D, SimplexFunction, SimplexIntegrator},
+    SimplexResultType,
 };
+use crate::refinement::{
+    AbsoluteCriterion, EvaluationBudget, GlobalRefinement, LeafError, RefinementCriterion,
+    MAX_REFINEMENT_LEVEL,
+};
+use crate::simplex::{CompositeCache, IntegratorCache};
+use crate::tree_format::{TreeData, TreeNode};
 
-pub struct Hierarchic3DIntegrator<I: Simplex3DIntegrator<IntegratorDummy>> {
+/// The leafs are refined by the [`RefinementCriterion`] `R`, or globally, see [`Self::new_global()`].
+/// Every subdomain is positively oriented relative to the tetrahedron, so the sign is the one of
+/// the base integrator.
+/// With a [`Hierarchic3DIntegratorData<C>`] every node of the tree keeps the caches `C` of the base
+/// integrator, one per tetrahedron of the node.
+pub struct Hierarchic3DIntegrator<I, R: RefinementCriterion = AbsoluteCriterion> {
     base_integrator: I,
     consolidated: bool,
-    precision: f64,
+    criterion: R,
+    global: Option<GlobalRefinement>,
+    embedded: bool,
 }
 
 fn subdivision_transformations() -> [Array2<f64>; 19] {
@@ -222,85 +238,312 @@ fn octahedron_subdivisions() -> [Array2<f64>; 4] {
     ]
 }
 
-impl<I: Simplex3DIntegrator<IntegratorDummy>> Hierarchic3DIntegrator<I> {
+impl<I> Hierarchic3DIntegrator<I> {
+    /// Refines a leaf if the distance to its refinement is larger than `precision`.
     pub fn new(base_integrator: I, consolidated: bool, precision: f64) -> Self {
+        Self::new_with_criterion(
+            base_integrator,
+            consolidated,
+            AbsoluteCriterion::new(precision),
+        )
+    }
+
+    /// Always splits the leaf with the largest error estimate, see [`GlobalRefinement`].
+    /// A cached tree is reused as the starting point.
+    pub fn new_global(base_integrator: I, global: GlobalRefinement) -> Self {
+        Self {
+            base_integrator,
+            consolidated: false,
+            criterion: AbsoluteCriterion::new(global.tolerance),
+            global: Some(global),
+            embedded: false,
+        }
+    }
+}
+
+impl<I, R: RefinementCriterion> Hierarchic3DIntegrator<I, R> {
+    pub fn new_with_criterion(base_integrator: I, consolidated: bool, criterion: R) -> Self {
+        Self {
+            base_integrator,
+            consolidated,
+            criterion,
+            global: None,
+            embedded: false,
+        }
+    }
+
+    /// Decides the refinement of a leaf with the embedded rule of the base integrator, so the
+    /// children are only integrated if the leaf is refined, see [`EmbeddedIntegrator`].
+    pub fn new_embedded(base_integrator: I, consolidated: bool, criterion: R) -> Self
+    where
+        I: EmbeddedIntegrator<3>,
+    {
         Self {
             base_integrator,
             consolidated,
-            precision,
+            criterion,
+            global: None,
+            embedded: true,
         }
     }
 
-    fn get_transformation(parent_vector: &Vec<u8>) -> Array2<f64> {
-        // Der höchste Index ist 18
-        //println!("{:?}",parent_vector);
-        let subdivision_transformations = &subdivision_transformations();
-        // Transformation ist ein Standardfall abhängig von dem ersten Kind
+    /// The integral over the subdomain `number` with the transformation `trans` relative to the
+    /// simplex, with the `cache` of the subdomain.
+    fn integrate_subdomain<C: IntegratorCache, T: SimplexFunction<3>>(
+        &self,
+        transformation: &Array2<f64>,
+        trans: &Array2<f64>,
+        number: u8,
+        func: &Box<T>,
+        simplex: &Simplex3D,
+        cache: &mut CompositeCache<C>,
+    ) -> Result<T::Return>
+    where
+        I: SimplexIntegrator<3, C>,
+    {
+        let child_transform = transformation.dot(trans);
 
-        let mut result = if is_tetrahedron_domain_number(parent_vector[0]) {
-            array![
-                [1.0, 0.0, 0.0, 0.0],
-                [0.0, 1.0, 0.0, 0.0],
-                [0.0, 0.0, 1.0, 0.0],
-                [0.0, 0.0, 0.0, 1.0]
-            ]
+        // Fallunterscheidung: Ist es ein Oktaeder oder ein Tetraeder?
+        if is_tetrahedron_domain_number(number) {
+            self.integrate_tetrahedron(&child_transform, func, simplex, cache)
         } else {
-            array![
-                [1.0, 0.0, 0.0, 0.0, 0.0, 0.0],
-                [0.0, 1.0, 0.0, 0.0, 0.0, 0.0],
-                [0.0, 0.0, 1.0, 0.0, 0.0, 0.0],
-                [0.0, 0.0, 0.0, 1.0, 0.0, 0.0],
-                [0.0, 0.0, 0.0, 0.0, 1.0, 0.0],
-                [0.0, 0.0, 0.0, 0.0, 0.0, 1.0]
-            ]
+            self.integrate_octahedron(&child_transform, func, simplex, cache)
+        }
+    }
+
+    /// [`Self::integrate_subdomain()`] with the integral of the embedded rule, if there is one.
+    fn integrate_subdomain_embedded<C: IntegratorCache, T: SimplexFunction<3>>(
+        &self,
+        transformation: &Array2<f64>,
+        trans: &Array2<f64>,
+        number: u8,
+        func: &Box<T>,
+        simplex: &Simplex3D,
+        cache: &mut CompositeCache<C>,
+    ) -> Result<Option<EmbeddedResult<T::Return>>>
+    where
+        I: SimplexIntegrator<3, C>,
+    {
+        let child_transform = transformation.dot(trans);
+        if is_tetrahedron_domain_number(number) {
+            return self.base_integrator.integrate_over_domain_embedded(
+                &child_transform,
+                func,
+                simplex,
+                &mut cache.sub_caches_mut(1)[0],
+            );
+        }
+        // Das Oktaeder als Summe seiner 4 Tetraeder, für beide Regeln
+        let octahedron_subdivisions = &octahedron_subdivisions();
+        let sub_caches = cache.sub_caches_mut(octahedron_subdivisions.len());
+        let mut both = EmbeddedResult {
+            result: func.additive_neutral_element(),
+            embedded: func.additive_neutral_element(),
         };
-        for i in 0..parent_vector.len() - 1 {
-            let current = parent_vector[i];
-            // Die Zahlen gehen bis 4, 0 ist besonders.
-            let current_transformation = &subdivision_transformations[(current - 1) as usize];
-            //println!("{}\n{}",current,current_transformation);
-            result = current_transformation.dot(&result);
+        for (subdivision, sub_cache) in octahedron_subdivisions.iter().zip(sub_caches) {
+            let trans = child_transform.dot(subdivision);
+            let temp = self
+                .base_integrator
+                .integrate_over_domain_embedded(&trans, func, simplex, sub_cache)?;
+            let Some(temp) = temp else {
+                return Ok(None);
+            };
+            SimplexResultType::add_assign(&mut both.result, &temp.result);
+            SimplexResultType::add_assign(&mut both.embedded, &temp.embedded);
         }
-        return result;
+        Ok(Some(both))
     }
 
-    fn integrate_tetrahedron<T: Simplex3DFunction>(
+    /// The integrals over the children of the subdomain, in the order of [`child_numbers()`],
+    /// each with new caches.
+    fn integrate_children<C: IntegratorCache, T: SimplexFunction<3>>(
         &self,
         transformation: &Array2<f64>,
+        trans: &Array2<f64>,
+        number: u8,
         func: &Box<T>,
         simplex: &Simplex3D,
-    ) -> T::Return {
-        if !(transformation.shape()[0] == 4 && transformation.shape()[1] == 4) {
-            panic!(
-                "T: Die Transformationsmatrix ist nicht der Dimension 4 x 4, sondern {} x {}",
-                transformation.shape()[0],
-                transformation.shape()[1]
+    ) -> Result<Children<T::Return, C>>
+    where
+        I: SimplexIntegrator<3, C>,
+    {
+        let numbers = child_numbers(number);
+        let mut children = Children {
+            results: Vec::with_capacity(numbers.len()),
+            caches: Vec::with_capacity(numbers.len()),
+        };
+        for child_number in numbers {
+            // die temporäre transformationshierachie
+            let child_trans = child_transformation(trans, child_number);
+            let mut cache = CompositeCache::new_cache();
+            children.results.push(self.integrate_subdomain(
+                transformation,
+                &child_trans,
+                child_number,
+                func,
+                simplex,
+                &mut cache,
+            )?);
+            children.caches.push(cache);
+        }
+        Ok(children)
+    }
+
+    /// Globally adaptive integration: the leaf with the largest error estimate is split first.
+    /// Every leaf keeps the integrals over its children, the result is the sum of these.
+    fn integrate_global<C: IntegratorCache, T: SimplexFunction<3>>(
+        &self,
+        global: &GlobalRefinement,
+        transformation: &Array2<f64>,
+        func: &Box<T>,
+        simplex: &Simplex3D,
+        cached_data: &mut Hierarchic3DIntegratorData<C>,
+    ) -> Result<T::Return>
+    where
+        I: SimplexIntegrator<3, C>,
+    {
+        let tree = &mut cached_data.arena;
+        let root_node_id = cached_data.root_node_id;
+        let estimate = |coarse: &T::Return, children: &Children<T::Return, C>| {
+            let mut fine = func.additive_neutral_element();
+            for child in &children.results {
+                SimplexResultType::add_assign(&mut fine, child);
+            }
+            coarse.distance(&fine)
+        };
+
+        // Zuerst werden alle vorhandenen Blätter geschätzt
+        let leafs = collect_leafs(tree, root_node_id);
+        let work = leafs
+            .iter()
+            .zip(take_caches(tree, &leafs))
+            .map(|(&id, cache)| {
+                let node = tree[id].get();
+                (&node.transformation, node.number, cache)
+            })
+            .collect();
+        let leaf_results = ordered_map(work, |(trans, number, mut cache)| {
+            let coarse =
+                self.integrate_subdomain(transformation, trans, number, func, simplex, &mut cache)?;
+            let children = self.integrate_children(transformation, trans, number, func, simplex)?;
+            Ok((coarse, children, cache))
+        })
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?;
+        let mut budget = EvaluationBudget::new(global);
+        let mut children_results = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        let mut error_sum = 0.;
+        for (id, (coarse, children, cache)) in leafs.into_iter().zip(leaf_results) {
+            let number = tree[id].get().number;
+            tree[id].get_mut().cache = cache;
+            budget.spend(base_calls(number) + children_base_calls(number));
+            let error = estimate(&coarse, &children);
+            error_sum += error;
+            queue.push(LeafError { error, id });
+            children_results.insert(id, children);
+        }
+
+        while error_sum > global.tolerance {
+            let Some(worst) = queue.pop() else {
+                break;
+            };
+            if tree[worst.id].get().level >= MAX_REFINEMENT_LEVEL {
+                // Das Blatt wird nicht mehr geteilt, sein Fehler bleibt in der Summe
+                continue;
+            }
+            let numbers = child_numbers(tree[worst.id].get().number);
+            // Eine Teilung kostet die Integration aller Enkel
+            let split_evaluations = numbers.iter().map(|&n| children_base_calls(n)).sum();
+            if !budget.try_spend(split_evaluations) {
+                break;
+            }
+            let children = children_results.remove(&worst.id).unwrap();
+            let child_ids: Vec<NodeId> = numbers
+                .into_iter()
+                .zip(children.caches)
+                .map(|(number, cache)| {
+                    let mut child = tree[worst.id].get().new_child(number);
+                    child.cache = cache;
+                    let child_id = tree.new_node(child);
+                    worst.id.append(child_id, tree);
+                    child_id
+                })
+                .collect();
+            let grandchildren_results = ordered_map(
+                child_ids
+                    .iter()
+                    .map(|&id| {
+                        let node = tree[id].get();
+                        (&node.transformation, node.number)
+                    })
+                    .collect(),
+                |(trans, number)| {
+                    self.integrate_children(transformation, trans, number, func, simplex)
+                },
             )
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+            error_sum -= worst.error;
+
+            // Die Kinder werden zu Blättern, ihr Integral ist schon bekannt
+            for ((id, coarse), grandchildren) in child_ids
+                .into_iter()
+                .zip(children.results)
+                .zip(grandchildren_results)
+            {
+                let error = estimate(&coarse, &grandchildren);
+                error_sum += error;
+                queue.push(LeafError { error, id });
+                children_results.insert(id, grandchildren);
+            }
         }
+
+        // Aufsummiert wird in DFS Reihenfolge
+        let mut result = func.additive_neutral_element();
+        for id in collect_leafs(tree, root_node_id) {
+            for child_result in &children_results[&id].results {
+                SimplexResultType::add_assign(&mut result, child_result);
+            }
+        }
+        Ok(result)
+    }
+
+    fn integrate_tetrahedron<C: IntegratorCache, T: SimplexFunction<3>>(
+        &self,
+        transformation: &Array2<f64>,
+        func: &Box<T>,
+        simplex: &Simplex3D,
+        cache: &mut CompositeCache<C>,
+    ) -> Result<T::Return>
+    where
+        I: SimplexIntegrator<3, C>,
+    {
+        check_shape(transformation, [4, 4])?;
         let result = self.base_integrator.integrate_over_domain(
             transformation,
             func,
             simplex,
-            &mut IntegratorDummy::get(),
+            &mut cache.sub_caches_mut(1)[0],
         );
         //println!("{}", result);
         result
     }
 
-    fn integrate_octahedron<T: Simplex3DFunction>(
+    /// The octahedron is integrated as 4 tetrahedra, each with its own cache.
+    fn integrate_octahedron<C: IntegratorCache, T: SimplexFunction<3>>(
         &self,
         transformation: &Array2<f64>,
         func: &Box<T>,
         simplex: &Simplex3D,
-    ) -> T::Return {
-        if !(transformation.shape()[0] == 4 && transformation.shape()[1] == 6) {
-            panic!(
-                "O: Die Transformationsmatrix ist nicht der Dimension 4 x 6, sondern {} x {}",
-                transformation.shape()[0],
-                transformation.shape()[1]
-            )
-        }
+        cache: &mut CompositeCache<C>,
+    ) -> Result<T::Return>
+    where
+        I: SimplexIntegrator<3, C>,
+    {
+        check_shape(transformation, [4, 6])?;
         let octahedron_subdivisions = &octahedron_subdivisions();
+        let sub_caches = cache.sub_caches_mut(octahedron_subdivisions.len());
         let mut result = func.additive_neutral_element();
         for i in 0..4 {
             let trans = &transformation.dot(&octahedron_subdivisions[i]);
@@ -308,70 +551,195 @@ impl<I: Simplex3DIntegrator<IntegratorDummy>> Hierarchic3DIntegrator<I> {
                 &trans,
                 func,
                 simplex,
-                &mut IntegratorDummy::get(),
-            );
-            Simplex3DResultType::add_assign(&mut result, &temp_result);
+                &mut sub_caches[i],
+            )?;
+            SimplexResultType::add_assign(&mut result, &temp_result);
             //result.add_assign();
             //println!("{},{}", i,temp_result);
         }
-        result
+        Ok(result)
     }
 }
 
 #[derive(Debug)]
-struct NodeData {
+struct NodeData<C> {
     checked: bool,
     number: u8,
+    /// Die Wurzel hat die Stufe 0
+    level: usize,
+    /// Die Transformation von der Wurzel bis zu diesem Knoten, sie wird nur einmal berechnet.
+    transformation: Array2<f64>,
+    /// Die Caches des Basisintegrators für diesen Knoten, sie werden nicht gespeichert.
+    cache: CompositeCache<C>,
+}
+
+/// The integrals over the children of a leaf, which are not yet in the tree, and the caches of
+/// the base integrator for them.
+struct Children<R, C> {
+    results: Vec<R>,
+    caches: Vec<CompositeCache<C>>,
+}
+
+/// What the refinement of a leaf is decided with.
+enum Estimate<R, C> {
+    Children(Children<R, C>),
+    /// The integral of the embedded rule over the leaf.
+    Embedded(R),
 }
 
 /// See Theory PDF under Arbitrary decisions.
-/// All Magic numbers are checked here and only here.
+/// The magic numbers of a stored tree are already checked when it is read, see
+/// [`Hierarchic3DIntegratorData::from_vec_tree()`].
 fn is_tetrahedron_domain_number(num: u8) -> bool {
-    if num > 19 {
-        panic!("Illegal Magic number for subdomain! {}", num)
-    }
+    debug_assert!(num <= 19, "Illegal Magic number for subdomain! {}", num);
     num < 13
 }
 
-/// see [`is_tetrahedron_domain_number()`]
-fn is_octahedron_domain_number(num: u8) -> bool {
-    !is_tetrahedron_domain_number(num)
+/// A tetrahedron has 4 child tetrahedra (1 to 4) and 1 child octahedron (13),
+/// an octahedron has 8 child tetrahedra (5 to 12) and 6 child octahedra (14 to 19).
+fn child_numbers(num: u8) -> Vec<u8> {
+    if is_tetrahedron_domain_number(num) {
+        vec![1, 2, 3, 4, 13]
+    } else {
+        (5..=12).chain(14..=19).collect()
+    }
+}
+
+/// The calls of the base integrator for the subdomain `number`, an octahedron is integrated as
+/// 4 tetrahedra.
+fn base_calls(number: u8) -> usize {
+    if is_tetrahedron_domain_number(number) {
+        1
+    } else {
+        4
+    }
 }
 
-impl NodeData {
-    fn new(checked: bool, number: u8) -> Self {
-        return Self { checked, number };
+/// The calls of the base integrator for all children of the subdomain `number`.
+fn children_base_calls(number: u8) -> usize {
+    child_numbers(number).into_iter().map(base_calls).sum()
+}
+
+/// The transformation of the child `number` of the subdomain with the transformation `trans`.
+fn child_transformation(trans: &Array2<f64>, number: u8) -> Array2<f64> {
+    // Die Zahlen gehen bis 19, 0 ist besonders.
+    let subdivision_transformations = subdivision_transformations();
+    trans.dot(&subdivision_transformations[(number - 1) as usize])
+}
+
+/// All leafs in DFS order.
+fn collect_leafs<C>(tree: &Arena<NodeData<C>>, root_node_id: NodeId) -> Vec<NodeId> {
+    root_node_id
+        .descendants(tree)
+        .filter(|&id| tree[id].first_child().is_none())
+        .collect()
+}
+
+/// Takes the caches out of the nodes, so the nodes can be integrated in parallel.
+fn take_caches<C: IntegratorCache>(
+    tree: &mut Arena<NodeData<C>>,
+    ids: &[NodeId],
+) -> Vec<CompositeCache<C>> {
+    ids.iter()
+        .map(|&id| std::mem::replace(&mut tree[id].get_mut().cache, CompositeCache::new_cache()))
+        .collect()
+}
+
+impl<C: IntegratorCache> NodeData<C> {
+    /// The root is always a tetrahedron.
+    fn new_root() -> Self {
+        Self {
+            checked: false,
+            number: 0,
+            level: 0,
+            transformation: Array2::eye(4),
+            cache: CompositeCache::new_cache(),
+        }
+    }
+
+    fn new_child(&self, number: u8) -> Self {
+        Self {
+            checked: false,
+            number,
+            level: self.level + 1,
+            transformation: child_transformation(&self.transformation, number),
+            cache: CompositeCache::new_cache(),
+        }
+    }
+}
+
+impl<C: IntegratorCache> TreeData for NodeData<C> {
+    const DIMENSION: u8 = 3;
+
+    fn new_root() -> Self {
+        Self::new_root()
+    }
+
+    fn new_child(&self, number: u8) -> Self {
+        self.new_child(number)
+    }
+
+    fn number(&self) -> u8 {
+        self.number
+    }
+
+    fn checked(&self) -> bool {
+        self.checked
     }
 
-    /// see [`is_tetrahedron_domain_number()`]
-    fn is_simplex_subdomain(&self) -> bool {
-        is_tetrahedron_domain_number(self.number)
+    fn set_checked(&mut self, checked: bool) {
+        self.checked = checked;
     }
 
-    /// see [`is_octahedron_domain_number()`]
-    fn is_octahedral_subdomain(&self) -> bool {
-        is_octahedron_domain_number(self.number)
+    fn child_numbers(number: u8) -> Vec<u8> {
+        child_numbers(number)
     }
 }
 
+/// The refinement tree, every node keeps the caches `C` of the base integrator.
 #[derive(Debug)]
-pub struct Hierarchic3DIntegratorData {
+pub struct Hierarchic3DIntegratorData<C = IntegratorDummy> {
     cached: bool,
     root_node_id: NodeId,
-    arena: Arena<NodeData>,
+    arena: Arena<NodeData<C>>,
 }
 
 impl Hierarchic3DIntegratorData {
+    /// The cache for a base integrator without state, see [`IntegratorCache::new_cache()`] for
+    /// other base integrators.
     pub fn new_cache() -> Self {
-        let mut arena = Arena::new();
-        let root = arena.new_node(NodeData::new(false, 0));
-        Self {
-            cached: false,
-            arena: arena,
-            root_node_id: root,
-        }
+        IntegratorCache::new_cache()
+    }
+
+    /// The node numbers in DFS order, the children of a node are enclosed by 20 and 21.
+    /// E.g. `[0, 20, 1, 2, 3, 4, 13, 21]` is the first refinement.
+    pub fn from_vec_tree(vec_tree: &[u8]) -> Result<Self> {
+        Self::from_tree_node(&TreeNode::from_vec_tree(vec_tree)?)
+    }
+
+    pub fn read_binary<R: Read>(reader: R) -> Result<Self> {
+        Self::from_tree_node(&TreeNode::read_binary(
+            NodeData::<IntegratorDummy>::DIMENSION,
+            reader,
+        )?)
     }
 
+    pub fn read_json<R: Read>(reader: R) -> Result<Self> {
+        Self::from_tree_node(&TreeNode::read_json(
+            NodeData::<IntegratorDummy>::DIMENSION,
+            reader,
+        )?)
+    }
+
+    /// MagicNumberMadness, see [`Self::from_vec_tree()`]
+    /// An invalid tree is an [`IntegrationError::MalformedTree`](crate::error::IntegrationError::MalformedTree).
+    pub fn new_cache_from_vec_tree(vec_tree: &Vec<u8>) -> Result<Self> {
+        Self::from_vec_tree(vec_tree)
+    }
+}
+
+/// The caches of the base integrator are not stored with the tree.
+impl<C: IntegratorCache> Hierarchic3DIntegratorData<C> {
     pub fn make_leafs_unchecked(&mut self) {
         let tree = &mut self.arena;
         let root_node_id = self.root_node_id;
@@ -395,98 +763,84 @@ impl Hierarchic3DIntegratorData {
         return self.arena.count();
     }
 
-    /// MagicNumberMadness
-    /// 20 is opening, 21 is closing
-    pub fn new_cache_from_vec_tree(vec_tree: &Vec<u8>) -> Self {
-        let arena = &mut Arena::new();
-
-        // Zeiger
-        let mut index = 0;
-        // Stackw
-        let mut stack = Vec::new();
-        let mut root_node_id = None;
-        loop {
-            let element = vec_tree[index];
-            match element {
-                0 => {
-                    // Ist es Null, dann ist das der erste Knoten
-                    let node_id = arena.new_node(NodeData::new(false, element));
-                    root_node_id = Some(node_id);
-                    stack.push(node_id);
-                    // FIXME: hier fehlt sehr viel Error checking
-                }
-                1..=19 => {
-                    // Wir stellen fest, das muss ein Knoten des Baums sein (Index 1 .. 19)
-                    let node_id = arena.new_node(NodeData::new(false, element));
-
-                    // Dieser muss einen Elternknoten haben, welcher auf dem Stack ist.
-                    let parent: &NodeId = stack.last().unwrap();
-                    parent.append(node_id, arena);
-
-                    // ist der nächste
-                    if let Some(next_id) = vec_tree.get(index + 1) {
-                        if *next_id == 20_u8 {
-                            stack.push(node_id);
-                        }
-                    }
-                }
-                20 => {}
-                21 => {
-                    stack.pop();
-                }
-                _ => {
-                    panic!()
-                }
-            }
+    /// The tree as magic numbers, see [`Self::from_vec_tree()`]. The checked flags are lost.
+    pub fn to_vec_tree(&self) -> Vec<u8> {
+        TreeNode::from_arena(&self.arena, self.root_node_id).to_vec_tree()
+    }
 
-            if stack.is_empty() {
-                break;
-            }
-            index += 1;
-        }
-        // Die Arena ist fertig, aber sie wird formal noch geteilt
-        // Um Eigentümer davon zu werden, muss ich mir das Eigentum nehmen. (take ownership)
-        let arena = mem::take(arena);
-        match root_node_id {
-            None => {
-                panic!("No Root Node found!")
-            }
-            Some(root) => {
-                println!("{:?}", root.debug_pretty_print(&arena));
-                let arena = arena;
-                return Self {
-                    cached: true,
-                    arena: arena,
-                    root_node_id: root,
-                };
-            }
+    /// Writes the tree with the checked flags in the versioned binary format.
+    pub fn write_binary<W: Write>(&self, writer: W) -> Result<()> {
+        TreeNode::from_arena(&self.arena, self.root_node_id)
+            .write_binary(NodeData::<C>::DIMENSION, writer)
+    }
+
+    /// Writes the tree with the checked flags in the versioned, readable JSON format.
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<()> {
+        TreeNode::from_arena(&self.arena, self.root_node_id)
+            .write_json(NodeData::<C>::DIMENSION, writer)
+    }
+
+    fn from_tree_node(root: &TreeNode) -> Result<Self> {
+        let (arena, root_node_id) = root.to_arena()?;
+        Ok(Self {
+            cached: true,
+            arena,
+            root_node_id,
+        })
+    }
+}
+
+impl<C: IntegratorCache> IntegratorCache for Hierarchic3DIntegratorData<C> {
+    fn new_cache() -> Self {
+        let mut arena = Arena::new();
+        let root = arena.new_node(NodeData::new_root());
+        Self {
+            cached: false,
+            arena: arena,
+            root_node_id: root,
         }
     }
 }
 
-impl<I: Simplex3DIntegrator<IntegratorDummy>> Simplex3DIntegrator<Hierarchic3DIntegratorData>
-    for Hierarchic3DIntegrator<I>
+impl<C: IntegratorCache, I: SimplexIntegrator<3, C>, R: RefinementCriterion>
+    SimplexIntegrator<3, Hierarchic3DIntegratorData<C>> for Hierarchic3DIntegrator<I, R>
 {
-    fn integrate_over_domain<T: Simplex3DFunction>(
+    fn dupe(&self) -> Self {
+        Self {
+            base_integrator: self.base_integrator.dupe(),
+            criterion: self.criterion.clone(),
+            consolidated: self.consolidated,
+            global: self.global,
+            embedded: self.embedded,
+        }
+    }
+
+    fn integrate_over_domain<T: SimplexFunction<3>>(
         &self,
         transformation: &Array2<f64>,
         func: &Box<T>,
         simplex: &Simplex3D,
-        cached_data: &mut Hierarchic3DIntegratorData,
-    ) -> T::Return {
+        cached_data: &mut Hierarchic3DIntegratorData<C>,
+    ) -> Result<T::Return> {
+        check_shape(transformation, [4, 4])?;
         // Sollte cached_data noch nicht initialisiert worden sein, dann wirds zeit
         // Danach ist der Cache grundsätzlich gültig.
         if !cached_data.cached {
             cached_data.arena = Arena::new();
-            cached_data.root_node_id = cached_data.arena.new_node(NodeData::new(false, 0));
+            cached_data.root_node_id = cached_data.arena.new_node(NodeData::new_root());
             cached_data.cached = true;
         }
+        if let Some(global) = &self.global {
+            return self.integrate_global(global, transformation, func, simplex, cached_data);
+        }
         // It all begins with a tree!
         let tree = &mut cached_data.arena;
         let root_node_id = cached_data.root_node_id;
 
+        // Die Integrale der Blätter werden je Aufruf nur einmal berechnet, denn sie hängen von der
+        // Funktion ab, der Baum aber nicht.
+        let mut leaf_results = HashMap::new();
         let mut state_changed = true;
-        let precision_threshold = self.precision;
         let mut result = func.additive_neutral_element();
 
         while state_changed {
@@ -494,208 +848,381 @@ impl<I: Simplex3DIntegrator<IntegratorDummy>> Simplex3DIntegrator<Hierarchic3DIn
             state_changed = false;
             // Das Integral wird von vorn Integriert.
             result = func.additive_neutral_element();
-            // Alle Knoten DFS durchgehen
-            let mut next_edge = Some(NodeEdge::Start(root_node_id));
-            while let Some(current_edge) = next_edge {
-                next_edge = current_edge.next_traverse(&tree);
-                let current_id = match current_edge {
-                    NodeEdge::Start(_) => continue,
-                    NodeEdge::End(id) => id,
-                };
-
-                // Wenn der mometane Knoten ein Blattknoten ist, dann....
-                if tree[current_id].first_child().is_none() {
-                    // Ermittle alle Vorfahren
-                    let mut vec = Vec::new();
-                    vec.push(tree[current_id].get().number);
-
-                    let mut par = current_id;
-                    while let Some(parent) = tree[par].parent() {
-                        vec.push(tree[parent].get().number);
-                        par = parent;
-                    }
-                    // Alle Elternknoten wurden ermittelt.
-                    // `vec` soll nicht mehr bearbeitet werden
-                    let vec = vec;
-
-                    // Jetzt wird das Integral des Blatts bestimmt.
-                    let trans = Hierarchic3DIntegrator::<I>::get_transformation(&vec);
-                    let child_transform = transformation.dot(&trans);
-
-                    // Fallunterscheidung: Ist es ein Oktaeder oder ein Tetraeder?
-                    //println!("-- {:?}",vec);
-                    let mut current_result = if tree[current_id].get().is_simplex_subdomain() {
-                        self.integrate_tetrahedron(&child_transform, func, simplex)
-                    } else {
-                        self.integrate_octahedron(&child_transform, func, simplex)
-                    };
-                    //if tree[current_id].get().is_octahedral_subdomain() && current_result > 0.0 { println!("{},{:?}",current_result,vec); }
-
-                    // Wenn das Blatt noch nicht überprüft worden ist und noch nicht consolidiert ist.
-                    if !tree[current_id].get().checked && !self.consolidated {
-                        //println!("-- Verfeinerung");
-                        // Dann wird eine Verfeinerungsstufe mehr eingebaut.
-                        //todo!("AB hier wirds kritisch, verfeinerung muss zwischen oktaeder und tetraeder unterscheiden!");
-
-                        let mut child_result = func.additive_neutral_element();
-                        // Wenn die kleinste subdomain ein Simplex ist:
-                        if tree[current_id].get().is_simplex_subdomain() {
-                            {
-                                // Dann gibt es 4 (Index 1 bis 4) Kindtetraeder
-                                for i in 0..4 {
-                                    let i_1 = i + 1;
-                                    let mut child_vec = vec.clone();
-                                    // die temporäre transformationshierachie
-                                    child_vec.insert(0, i_1 as u8);
-                                    let child_trans =
-                                        Hierarchic3DIntegrator::<I>::get_transformation(&child_vec);
-                                    let child_transformation = transformation.dot(&child_trans);
-                                    Simplex3DResultType::add_assign(
-                                        &mut child_result,
-                                        &self.integrate_tetrahedron(
-                                            &child_transformation,
-                                            func,
-                                            simplex,
-                                        ),
-                                    );
-                                }
-                                // und 1 Kindoktaeder (Index 13)
-                                {
-                                    let i_1 = 13;
-                                    let mut child_vec = vec.clone();
-
-                                    child_vec.insert(0, i_1 as u8);
-                                    let child_trans =
-                                        Hierarchic3DIntegrator::<I>::get_transformation(&child_vec);
-                                    let child_transformation = transformation.dot(&child_trans);
-                                    Simplex3DResultType::add_assign(
-                                        &mut child_result,
-                                        &self.integrate_octahedron(
-                                            &child_transformation,
-                                            func,
-                                            simplex,
-                                        ),
-                                    );
-                                }
-                            }
-                        } else {
-                            // Wenn ein Oktaeder Verfeinert werden muss:
-                            {
-                                // Dann gibt es 8 (Index 5 bis 12) Kindtetraeder
-                                for i in 0..8 {
-                                    let i_1 = i + 5;
-                                    let mut child_vec = vec.clone();
-                                    // die temporäre transformationshierachie
-                                    child_vec.insert(0, i_1 as u8);
-                                    let child_trans =
-                                        Hierarchic3DIntegrator::<I>::get_transformation(&child_vec);
-                                    let child_transformation = transformation.dot(&child_trans);
-                                    Simplex3DResultType::add_assign(
-                                        &mut child_result,
-                                        &self.integrate_tetrahedron(
-                                            &child_transformation,
-                                            func,
-                                            simplex,
-                                        ),
-                                    );
-                                }
-                                // und 6 Kindoktaeder (Index 14 bis 19)
-                                for i in 0..6 {
-                                    let i_1 = i + 14;
-                                    let mut child_vec = vec.clone();
-                                    // die temporäre transformationshierachie
-                                    child_vec.insert(0, i_1 as u8);
-                                    let child_trans =
-                                        Hierarchic3DIntegrator::<I>::get_transformation(&child_vec);
-                                    let child_transformation = transformation.dot(&child_trans);
-                                    Simplex3DResultType::add_assign(
-                                        &mut child_result,
-                                        &self.integrate_octahedron(
-                                            &child_transformation,
-                                            func,
-                                            simplex,
-                                        ),
-                                    );
-                                }
-                            }
-                        }
+            // Alle Blätter DFS ermitteln
+            let leafs = collect_leafs(tree, root_node_id);
 
-                        // Wenn die Verfeinerung "genauer" ist, dann wird der Baum angepasst.
-                        // Und das Element wurde überprüft.
-                        tree[current_id].get_mut().checked = true;
-                        if current_result.distance(&child_result) > precision_threshold {
-                            if tree[current_id].get().is_simplex_subdomain() {
-                                for i in 0..4 {
-                                    let i = i + 1;
-                                    current_id.append(
-                                        tree.new_node(NodeData {
-                                            checked: false,
-                                            number: i,
-                                        }),
-                                        tree,
-                                    );
-                                }
-                                current_id.append(
-                                    tree.new_node(NodeData {
-                                        checked: false,
-                                        number: 13,
-                                    }),
-                                    tree,
-                                )
-                            } else {
-                                for i in 0..8 {
-                                    let i = i + 5;
-                                    current_id.append(
-                                        tree.new_node(NodeData {
-                                            checked: false,
-                                            number: i,
-                                        }),
-                                        tree,
-                                    );
-                                }
-                                for i in 0..6 {
-                                    let i = i + 14;
-                                    current_id.append(
-                                        tree.new_node(NodeData {
-                                            checked: false,
-                                            number: i,
-                                        }),
-                                        tree,
-                                    );
-                                }
-                            }
-                            // dem Element fügen wir die Kinder hinzu
-
-                            // Der Baum hat sich geändert!
-                            state_changed = true;
-                            // Das Resultat ist das genauere resultat
-                            current_result = child_result;
-                        }
+            // Integriert werden nur neue Blätter und die Kinder der ungeprüften Blätter
+            // Wenn das Blatt noch nicht überprüft worden ist und noch nicht consolidiert ist.
+            let work = leafs
+                .iter()
+                .zip(take_caches(tree, &leafs))
+                .map(|(id, cache)| {
+                    let node = tree[*id].get();
+                    let refine =
+                        !node.checked && !self.consolidated && node.level < MAX_REFINEMENT_LEVEL;
+                    let unknown = !leaf_results.contains_key(id);
+                    (&node.transformation, node.number, cache, unknown, refine)
+                })
+                .collect();
+            // Die Blätter sind unabhängig voneinander und werden (evtl. parallel) integriert
+            let new_results = ordered_map(work, |(trans, number, mut cache, unknown, refine)| {
+                let mut current_result = None;
+                let mut estimate = None;
+                if unknown && refine && self.embedded {
+                    let both = self.integrate_subdomain_embedded(
+                        transformation,
+                        trans,
+                        number,
+                        func,
+                        simplex,
+                        &mut cache,
+                    )?;
+                    if let Some(both) = both {
+                        current_result = Some(both.result);
+                        estimate = Some(Estimate::Embedded(both.embedded));
                     }
-                    //println!("{},{}",result,current_result);
-                    Simplex3DResultType::add_assign(&mut result, &current_result);
                 }
+                if unknown && current_result.is_none() {
+                    current_result = Some(self.integrate_subdomain(
+                        transformation,
+                        trans,
+                        number,
+                        func,
+                        simplex,
+                        &mut cache,
+                    )?);
+                }
+                if refine && estimate.is_none() {
+                    let children =
+                        self.integrate_children(transformation, trans, number, func, simplex)?;
+                    estimate = Some(Estimate::Children(children));
+                }
+                Ok((current_result, estimate, cache))
+            })
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+            let mut leaf_estimates = Vec::with_capacity(leafs.len());
+            for (id, (current_result, estimate, cache)) in leafs.iter().zip(new_results) {
+                tree[*id].get_mut().cache = cache;
+                if let Some(current_result) = current_result {
+                    leaf_results.insert(*id, current_result);
+                }
+                leaf_estimates.push(estimate);
+            }
 
-                //*tree[current_id].get_mut() = 42;
+            // Die momentane Schätzung des Integrals für die relativen Kriterien
+            let mut total = func.additive_neutral_element();
+            for id in leafs.iter() {
+                SimplexResultType::add_assign(&mut total, &leaf_results[id]);
             }
 
-            //println!("{}",result);
+            // Der Baum wird in der Reihenfolge der Blätter angepasst und aufsummiert
+            for (current_id, estimate) in leafs.into_iter().zip(leaf_estimates) {
+                if let Some(Estimate::Embedded(embedded)) = estimate {
+                    // Das Element wurde überprüft, ohne seine Kinder zu integrieren
+                    tree[current_id].get_mut().checked = true;
+                    if self.criterion.needs_refinement(
+                        &embedded,
+                        &leaf_results[&current_id],
+                        &total,
+                    ) {
+                        // Die Kinder werden erst im nächsten Durchlauf integriert
+                        leaf_results.remove(&current_id);
+                        for number in child_numbers(tree[current_id].get().number) {
+                            let child_data = tree[current_id].get().new_child(number);
+                            let child_id = tree.new_node(child_data);
+                            current_id.append(child_id, tree);
+                        }
+                        state_changed = true;
+                        continue;
+                    }
+                } else if let Some(Estimate::Children(children_results)) = estimate {
+                    // Und das Element wurde überprüft.
+                    tree[current_id].get_mut().checked = true;
+                    let mut child_result = func.additive_neutral_element();
+                    for child in children_results.results.iter() {
+                        SimplexResultType::add_assign(&mut child_result, child);
+                    }
+                    // Wenn die Verfeinerung "genauer" ist, dann wird der Baum angepasst.
+                    if self.criterion.needs_refinement(
+                        &leaf_results[&current_id],
+                        &child_result,
+                        &total,
+                    ) {
+                        // dem Element fügen wir die Kinder hinzu, ihre Integrale sind bekannt
+                        leaf_results.remove(&current_id);
+                        let numbers = child_numbers(tree[current_id].get().number);
+                        let children = children_results.results.into_iter();
+                        let children = children.zip(children_results.caches);
+                        for (number, (child, cache)) in numbers.into_iter().zip(children) {
+                            let mut child_data = tree[current_id].get().new_child(number);
+                            child_data.cache = cache;
+                            let child_id = tree.new_node(child_data);
+                            current_id.append(child_id, tree);
+                            leaf_results.insert(child_id, child);
+                        }
+                        // Der Baum hat sich geändert!
+                        state_changed = true;
+                        // Das Resultat ist das genauere resultat
+                        SimplexResultType::add_assign(&mut result, &child_result);
+                        continue;
+                    }
+                }
+                SimplexResultType::add_assign(&mut result, &leaf_results[&current_id]);
+            }
         } // Iteration ende
 
-        return result;
+        return Ok(result);
     }
 }
 
-
 #[cfg(test)]
 mod tests {
     use crate::common::IntegratorDummy;
     use crate::integration_3d::integrators::{
         Hierarchic3DIntegrator, Hierarchic3DIntegratorData, Quadrilateral3DIntegrator,
+        TetrahedralIntegrator,
     };
     use crate::integrator_tests_3d;
+    use crate::refinement::{GlobalRefinement, MixedCriterion};
+    use crate::simplex::IntegratorCache;
 
     integrator_tests_3d! {
-        quadrilaterial1: Hierarchic3DIntegrator<Quadrilateral3DIntegrator>: Hierarchic3DIntegrator::new(Quadrilateral3DIntegrator::new(2),false,1e-2), Hierarchic3DIntegratorData: Hierarchic3DIntegratorData::new_cache(),
+        quadrilaterial1: Hierarchic3DIntegrator<Quadrilateral3DIntegrator>: Hierarchic3DIntegrator::new(Quadrilateral3DIntegrator::new(2).unwrap(),false,1e-2), Hierarchic3DIntegratorData: Hierarchic3DIntegratorData::new_cache(),
+        tetrahedral2: Hierarchic3DIntegrator<TetrahedralIntegrator>: Hierarchic3DIntegrator::new(TetrahedralIntegrator::new(2).unwrap(),false,1e-2), Hierarchic3DIntegratorData: Hierarchic3DIntegratorData::new_cache(),
+        tetrahedral5: Hierarchic3DIntegrator<TetrahedralIntegrator>: Hierarchic3DIntegrator::new(TetrahedralIntegrator::new(5).unwrap(),false,1e-2), Hierarchic3DIntegratorData: Hierarchic3DIntegratorData::new_cache(),
+        mixed: Hierarchic3DIntegrator<TetrahedralIntegrator, MixedCriterion>: Hierarchic3DIntegrator::new_with_criterion(TetrahedralIntegrator::new(2).unwrap(),false,MixedCriterion::new(1e-4, 1e-2)), Hierarchic3DIntegratorData: Hierarchic3DIntegratorData::new_cache(),
+        global: Hierarchic3DIntegrator<TetrahedralIntegrator>: Hierarchic3DIntegrator::new_global(TetrahedralIntegrator::new(2).unwrap(),GlobalRefinement::new(1e-3, 10_000)), Hierarchic3DIntegratorData: Hierarchic3DIntegratorData::new_cache(),
+        nested: Hierarchic3DIntegrator<Hierarchic3DIntegrator<TetrahedralIntegrator>>: Hierarchic3DIntegrator::new(Hierarchic3DIntegrator::new(TetrahedralIntegrator::new(2).unwrap(),false,1e-2),false,1e-2), Hierarchic3DIntegratorData<Hierarchic3DIntegratorData>: IntegratorCache::new_cache(),
+    }
+
+    #[test]
+    fn global_splits_worst_leaf_within_budget() {
+        use ndarray::array;
+
+        use crate::integration_3d::domain::{Simplex3D, SimplexIntegrator};
+        use crate::integration_3d::functions::Multiplicative3DFunction;
+
+        let sim = Simplex3D::new_from_points(
+            &array![0., 0., 0.],
+            &array![1., 0., 0.],
+            &array![0., 1., 0.],
+            &array![0., 0., 1.],
+        );
+        let func = Box::new(Multiplicative3DFunction {});
+        // Die Wurzel kostet 1 + 8 Aufrufe, die erste Teilung 4 * 8 + 32, jede weitere mindestens 64
+        let inte = Hierarchic3DIntegrator::new_global(
+            TetrahedralIntegrator::new(1).unwrap(),
+            GlobalRefinement::new(1e-12, 9 + 64 + 63),
+        );
+        let mut cache = Hierarchic3DIntegratorData::new_cache();
+        let result = inte
+            .integrate_simplex(&func, &sim, &mut cache)
+            .unwrap()
+            .get();
+        assert_eq!(cache.tree_size(), 1 + 5);
+        // Das Ergebnis ist die Summe über die Enkel der Wurzel
+        let mut vec_tree = vec![0, 20];
+        for child in [1, 2, 3, 4] {
+            vec_tree.extend([child, 20, 1, 2, 3, 4, 13, 21]);
+        }
+        vec_tree.extend([13, 20]);
+        vec_tree.extend((5..=12).chain(14..=19));
+        vec_tree.extend([21, 21]);
+        let local = Hierarchic3DIntegrator::new(TetrahedralIntegrator::new(1).unwrap(), true, 0.);
+        let mut local_cache =
+            Hierarchic3DIntegratorData::new_cache_from_vec_tree(&vec_tree).unwrap();
+        let reference = local
+            .integrate_simplex(&func, &sim, &mut local_cache)
+            .unwrap()
+            .get();
+        assert!((result - reference).abs() < 1e-12);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn tree_survives_binary_and_json() {
+        use ndarray::array;
+
+        use crate::integration_3d::domain::{Simplex3D, SimplexIntegrator};
+        use crate::integration_3d::functions::Multiplicative3DFunction;
+
+        let sim = Simplex3D::new_from_points(
+            &array![0., 0., 0.],
+            &array![1., 0., 0.],
+            &array![0., 1., 0.],
+            &array![0., 0., 1.],
+        );
+        let func = Box::new(Multiplicative3DFunction {});
+        let inte = Hierarchic3DIntegrator::new(TetrahedralIntegrator::new(2).unwrap(), false, 1e-5);
+        let mut cache = Hierarchic3DIntegratorData::new_cache();
+        let result = inte
+            .integrate_simplex(&func, &sim, &mut cache)
+            .unwrap()
+            .get();
+
+        let mut binary = Vec::new();
+        cache.write_binary(&mut binary).unwrap();
+        let mut json = Vec::new();
+        cache.write_json(&mut json).unwrap();
+        for mut loaded in [
+            Hierarchic3DIntegratorData::read_binary(binary.as_slice()).unwrap(),
+            Hierarchic3DIntegratorData::read_json(json.as_slice()).unwrap(),
+        ] {
+            assert_eq!(loaded.to_vec_tree(), cache.to_vec_tree());
+            // Die Blätter sind schon geprüft, es wird nicht weiter verfeinert
+            let loaded_result = inte
+                .integrate_simplex(&func, &sim, &mut loaded)
+                .unwrap()
+                .get();
+            assert_eq!(loaded_result.to_bits(), result.to_bits());
+            assert_eq!(loaded.tree_size(), cache.tree_size());
+        }
+
+        let from_vec = Hierarchic3DIntegratorData::from_vec_tree(&cache.to_vec_tree()).unwrap();
+        assert_eq!(from_vec.tree_size(), cache.tree_size());
+        // Ein 3D Baum ist kein 2D Baum, und ein Tetraeder hat keine 4 Kinder
+        assert!(
+            crate::integration_2d::integrators::Hierarchic2DIntegratorData::read_binary(
+                binary.as_slice()
+            )
+            .is_err()
+        );
+        assert!(Hierarchic3DIntegratorData::from_vec_tree(&[0, 20, 1, 2, 3, 4, 21]).is_err());
+        // Ein unbekanntes Token bricht nicht mehr ab
+        assert!(matches!(
+            Hierarchic3DIntegratorData::new_cache_from_vec_tree(&vec![0, 20, 1, 2, 3, 4, 13, 22]),
+            Err(crate::error::IntegrationError::MalformedTree(_))
+        ));
+    }
+
+    #[test]
+    fn global_budget_bounds_the_evaluations() {
+        use ndarray::array;
+
+        use crate::integration_3d::domain::{Simplex3D, SimplexIntegrator};
+        use crate::integration_3d::functions::Function3DHistory;
+
+        let sim = Simplex3D::new_from_points(
+            &array![0., 0., 0.],
+            &array![1., 0., 0.],
+            &array![0., 1., 0.],
+            &array![0., 0., 1.],
+        );
+        let func = Box::new(Function3DHistory::new(
+            |xi1: f64, xi2: f64, xi3: f64, _xi4: f64, _simplex: &Simplex3D| {
+                (10. * xi1 + 4. * xi2 - 7. * xi3).sin()
+            },
+        ));
+        let points = TetrahedralIntegrator::new(2).unwrap().get_num_points();
+        let max_evaluations = 2_000;
+        let inte = Hierarchic3DIntegrator::new_global(
+            TetrahedralIntegrator::new(2).unwrap(),
+            GlobalRefinement::new(1e-12, max_evaluations),
+        );
+        let mut cache = Hierarchic3DIntegratorData::new_cache();
+        inte.integrate_simplex(&func, &sim, &mut cache).unwrap();
+        let octahedra = cache
+            .root_node_id
+            .descendants(&cache.arena)
+            .filter(|&id| !super::is_tetrahedron_domain_number(cache.arena[id].get().number))
+            .count();
+        assert!(octahedra > 0);
+        // Ein Oktaeder zählt als vier Aufrufe, die teuerste Teilung kostet 64 + 6 * 32
+        let evaluations = func.function_evaluations() / points;
+        assert!(evaluations <= max_evaluations);
+        assert!(evaluations > max_evaluations - 256);
+    }
+
+    #[test]
+    fn embedded_estimate_without_children() {
+        use indextree::NodeId;
+        use ndarray::array;
+
+        use crate::embedded::GrundmannMoellerIntegrator;
+        use crate::integration_3d::domain::{Simplex3D, SimplexIntegrator};
+        use crate::integration_3d::functions::Function3DHistory;
+        use crate::refinement::AbsoluteCriterion;
+
+        let sim = Simplex3D::new_from_points(
+            &array![0., 0., 0.],
+            &array![1., 0., 0.],
+            &array![0., 1., 0.],
+            &array![0., 0., 1.],
+        );
+        let func = Box::new(Function3DHistory::new(
+            |xi1: f64, xi2: f64, xi3: f64, _xi4: f64, _simplex: &Simplex3D| {
+                (xi1 + 2. * xi2 + 3. * xi3).exp()
+            },
+        ));
+        let base = || GrundmannMoellerIntegrator::<3>::new(5).unwrap();
+        let points = base().get_num_points();
+        let mut cache = Hierarchic3DIntegratorData::new_cache();
+        let inte =
+            Hierarchic3DIntegrator::new_embedded(base(), false, AbsoluteCriterion::new(1e-6));
+        let result = inte
+            .integrate_simplex(&func, &sim, &mut cache)
+            .unwrap()
+            .get();
+        assert!(cache.tree_size() > 1);
+        // Jedes Element wird genau einmal integriert, ein Oktaeder als vier Tetraeder
+        let tetrahedra = |cache: &Hierarchic3DIntegratorData, ids: Vec<NodeId>| -> usize {
+            ids.into_iter()
+                .map(|id| super::base_calls(cache.arena[id].get().number))
+                .sum()
+        };
+        let nodes = cache.root_node_id.descendants(&cache.arena).collect();
+        assert_eq!(
+            func.function_evaluations(),
+            tetrahedra(&cache, nodes) * points
+        );
+
+        // Mit fertigem Baum werden nur noch die Blätter integriert
+        func.delete_history();
+        let cached = inte
+            .integrate_simplex(&func, &sim, &mut cache)
+            .unwrap()
+            .get();
+        assert!((cached - result).abs() < 1e-15);
+        let leafs = super::collect_leafs(&cache.arena, cache.root_node_id);
+        assert_eq!(
+            func.function_evaluations(),
+            tetrahedra(&cache, leafs) * points
+        );
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_is_bit_identical() {
+        use ndarray::array;
+
+        use crate::integration_3d::domain::{Simplex3D, SimplexIntegrator};
+        use crate::integration_3d::functions::Multiplicative3DFunction;
+
+        let sim = Simplex3D::new_from_points(
+            &array![0., 0., 0.],
+            &array![1., 0., 0.],
+            &array![0., 1., 0.],
+            &array![0., 0., 1.],
+        );
+        let inte = Hierarchic3DIntegrator::new(TetrahedralIntegrator::new(2).unwrap(), false, 1e-5);
+        let run = |threads: usize| {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            pool.install(|| {
+                let func = Box::new(Multiplicative3DFunction {});
+                let mut cache = Hierarchic3DIntegratorData::new_cache();
+                let result = inte
+                    .integrate_simplex(&func, &sim, &mut cache)
+                    .unwrap()
+                    .get();
+                (result, cache.tree_size())
+            })
+        };
+        let (serial, serial_size) = run(1);
+        let (parallel, parallel_size) = run(4);
+        assert_eq!(serial.to_bits(), parallel.to_bits());
+        assert_eq!(serial_size, parallel_size);
+    }
+}
@@ -0,0 +1,129 @@
+use ndarray::Array2;
+
+use crate::common::IntegratorDummy;
+use crate::error::Result;
+use crate::integration_3d::domain::{Simplex3D, SimplexFunction, SimplexIntegrator};
+use crate::integration_3d::integrators::{
+    EdgeSubdivisionIntegrator, Hierarchic3DIntegrator, Hierarchic3DIntegratorData,
+};
+use crate::refinement::{AbsoluteCriterion, RefinementCriterion};
+use crate::simplex::{CompositeCache, IntegratorCache};
+
+/// The hybrid strategy: every edge is divided into `order` parts like in the
+/// [`EdgeSubdivisionIntegrator`], then every subtetrahedron is refined adaptively by the
+/// [`Hierarchic3DIntegrator`] with its own tree.
+/// The criterion is applied to each subtetrahedron, the sign is the one of the base integrator.
+pub struct Hybrid3DIntegrator<I, R: RefinementCriterion = AbsoluteCriterion> {
+    edge_integrator: EdgeSubdivisionIntegrator<Hierarchic3DIntegrator<I, R>>,
+}
+
+impl<I, R: RefinementCriterion> Hybrid3DIntegrator<I, R> {
+    /// Every edge is divided into `order` parts, so the order must be at least 1.
+    pub fn new(hierarchic_integrator: Hierarchic3DIntegrator<I, R>, order: usize) -> Result<Self> {
+        Ok(Self {
+            edge_integrator: EdgeSubdivisionIntegrator::new(hierarchic_integrator, order)?,
+        })
+    }
+}
+
+/// The composite cache of the [`Hybrid3DIntegrator`], one tree per subtetrahedron.
+/// Every tree keeps caches `C` of the base integrator, see [`Hierarchic3DIntegratorData<C>`].
+pub type Hybrid3DIntegratorData<C = IntegratorDummy> =
+    CompositeCache<Hierarchic3DIntegratorData<C>>;
+
+impl<C: IntegratorCache> CompositeCache<Hierarchic3DIntegratorData<C>> {
+    pub fn make_leafs_unchecked(&mut self) {
+        for sub_cache in self.sub_caches.iter_mut() {
+            sub_cache.make_leafs_unchecked();
+        }
+    }
+
+    /// The number of nodes of all trees.
+    pub fn tree_size(&self) -> usize {
+        self.sub_caches()
+            .iter()
+            .map(|sub_cache| sub_cache.tree_size())
+            .sum()
+    }
+}
+
+impl<C: IntegratorCache, I: SimplexIntegrator<3, C>, R: RefinementCriterion>
+    SimplexIntegrator<3, Hybrid3DIntegratorData<C>> for Hybrid3DIntegrator<I, R>
+{
+    fn dupe(&self) -> Self {
+        Self {
+            edge_integrator: SimplexIntegrator::<3, Hybrid3DIntegratorData<C>>::dupe(
+                &self.edge_integrator,
+            ),
+        }
+    }
+
+    fn integrate_over_domain<T: SimplexFunction<3>>(
+        &self,
+        transformation: &Array2<f64>,
+        func: &Box<T>,
+        simplex: &Simplex3D,
+        cached_data: &mut Hybrid3DIntegratorData<C>,
+    ) -> Result<T::Return> {
+        self.edge_integrator
+            .integrate_over_domain(transformation, func, simplex, cached_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::integration_3d::integrators::{
+        Hierarchic3DIntegrator, Hierarchic3DIntegratorData, Hybrid3DIntegrator,
+        Hybrid3DIntegratorData, TetrahedralIntegrator,
+    };
+    use crate::integrator_tests_3d;
+
+    integrator_tests_3d! {
+        order2_tet: Hybrid3DIntegrator<TetrahedralIntegrator>: Hybrid3DIntegrator::new(Hierarchic3DIntegrator::new(TetrahedralIntegrator::new(2).unwrap(),false,1e-2),2).unwrap(), Hybrid3DIntegratorData: Hybrid3DIntegratorData::new_cache(),
+        nested: Hybrid3DIntegrator<Hierarchic3DIntegrator<TetrahedralIntegrator>>: Hybrid3DIntegrator::new(Hierarchic3DIntegrator::new(Hierarchic3DIntegrator::new(TetrahedralIntegrator::new(2).unwrap(),false,1e-2),false,1e-2),2).unwrap(), Hybrid3DIntegratorData<Hierarchic3DIntegratorData>: Hybrid3DIntegratorData::new_cache(),
+    }
+
+    #[test]
+    fn hybrid_matches_reference() {
+        use ndarray::array;
+
+        use crate::common::IntegratorDummy;
+        use crate::integration_3d::domain::{Simplex3D, SimplexIntegrator};
+        use crate::integration_3d::functions::PlaneWave3DFunction;
+
+        let sim = Simplex3D::new_from_points(
+            &array![0., 0., 0.],
+            &array![1., 0., 0.],
+            &array![0., 1., 0.],
+            &array![0., 0., 1.],
+        );
+        let func = Box::new(PlaneWave3DFunction::new([2., -1., 1.5]));
+        let reference = TetrahedralIntegrator::new(10)
+            .unwrap()
+            .integrate_simplex(&func, &sim, &mut IntegratorDummy::get())
+            .unwrap()
+            .get();
+
+        let inte = Hybrid3DIntegrator::new(
+            Hierarchic3DIntegrator::new(TetrahedralIntegrator::new(8).unwrap(), false, 1e-9),
+            2,
+        )
+        .unwrap();
+        let mut cache = Hybrid3DIntegratorData::new_cache();
+        let result = inte
+            .integrate_simplex(&func, &sim, &mut cache)
+            .unwrap()
+            .get();
+        assert!((result - reference).norm() < 1e-8);
+        // Vier Ecktetraeder und vier aus dem Oktaeder
+        assert_eq!(cache.sub_caches().len(), 8);
+        let size = cache.tree_size();
+        assert_eq!(
+            inte.integrate_simplex(&func, &sim, &mut cache)
+                .unwrap()
+                .get(),
+            result
+        );
+        assert_eq!(cache.tree_size(), size);
+    }
+}
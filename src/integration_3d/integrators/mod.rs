@@ -1,10 +1,14 @@
 //pub mod hierarchic_integrator;
 pub mod edge_subdivision_integrator;
 pub mod hierarchic_integrator;
+pub mod hybrid_integrator;
 pub mod quadrilaterial_integrator;
+pub mod tetrahedral_integrator;
 pub mod visual_integrator;
 
 //pub use self::hierarchic_integrator::*;
 pub use self::hierarchic_integrator::*;
+pub use self::hybrid_integrator::*;
 pub use self::quadrilaterial_integrator::*;
+pub use self::tetrahedral_integrator::*;
 pub use self::edge_subdivision_integrator::*;
\ No newline at end of file
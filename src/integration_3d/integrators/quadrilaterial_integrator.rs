@@ -1,43 +1,28 @@
 use ndarray::{array, Array1, Array2};
 
+use crate::common::{det3x3, gauss_legendre_golub_welsch};
+use crate::error::{check_shape, Result};
 use crate::integration_3d::{
-    domain::{Simplex3D, Simplex3DFunction, Simplex3DIntegrator},
-    Simplex3DResultType,
+    domain::{Simplex3D, SimplexFunction, SimplexIntegrator},
+    SimplexResultType,
 };
 
-fn det2x2(mat2x2: &Array2<f64>) -> f64 {
-    mat2x2[[0, 0]] * mat2x2[[1, 1]] - mat2x2[[0, 1]] * mat2x2[[1, 0]]
-}
-
-fn det3x3(mat3x3: &Array2<f64>) -> f64 {
-    let mut sum = 0.0;
-    for j in 0..3 {
-        let mut prod = 1.0;
-        for i in 0..3 {
-            prod = prod * mat3x3[[i, (i + j) % 3]]
-        }
-        sum = sum + prod;
-        let mut prod = -1.0;
-        for i in 0..3 {
-            prod = prod * mat3x3[[(2 - i), (i + j) % 3]]
-        }
-        sum = sum + prod;
-    }
-    return sum;
-}
-
 type Point3D = Array1<f64>;
 
 fn g1(coord: f64) -> f64 {
-    if coord > 1.0 || coord < -1.0 {
-        panic!("g1() argument out of bounds, !( -1.0 <= {} <= 1.0 )", coord)
-    };
+    debug_assert!(
+        (-1.0..=1.0).contains(&coord),
+        "g1() argument out of bounds, !( -1.0 <= {} <= 1.0 )",
+        coord
+    );
     coord / 2.0 + 0.5
 }
 fn g2(coord: f64) -> f64 {
-    if coord > 1.0 || coord < -1.0 {
-        panic!("g2() argument out of bounds, !( -1.0 <= {} <= 1.0 )", coord)
-    };
+    debug_assert!(
+        (-1.0..=1.0).contains(&coord),
+        "g2() argument out of bounds, !( -1.0 <= {} <= 1.0 )",
+        coord
+    );
     -coord / 2.0 + 0.5
 }
 
@@ -269,47 +254,28 @@ fn interpolation_function_derivatives_vector(xyz1: Point3D) -> Array2<f64> {
     ];
 }
 
+/// Splits the tetrahedron into four hexahedra and integrates each of them with a tensor
+/// Gauss-Legendre rule. The hexahedra are mapped orientation preserving, the integral is signed
+/// like [`Simplex3D::get_volume()`](crate::simplex::Simplex::get_volume).
 pub struct Quadrilateral3DIntegrator {
     gauss_degree: usize,
+    // Die Gauss-Punkte werden einmal bei der Konstruktion bestimmt
+    gauss_points: Array1<f64>,
+    gauss_weights: Array1<f64>,
 }
 
 impl Quadrilateral3DIntegrator {
-    pub fn new(gauss_degree: usize) -> Self {
-        Self { gauss_degree }
+    /// At least one Gauss point is needed, otherwise
+    /// [`IntegrationError::UnsupportedOrder`](crate::error::IntegrationError::UnsupportedOrder).
+    pub fn new(gauss_degree: usize) -> Result<Self> {
+        let (gauss_points, gauss_weights) = gauss_legendre_golub_welsch(gauss_degree)?;
+        Ok(Self {
+            gauss_degree,
+            gauss_points,
+            gauss_weights,
+        })
     }
 
-    fn get_gauss_points(&self) -> Array1<f64> {
-        match self.gauss_degree {
-            1 => {
-                array![0.]
-            }
-            2 => {
-                array![-(1. / 3.0_f64).sqrt(), (1. / 3.0_f64).sqrt()]
-            }
-            3 => {
-                array![-(3. / 5.0_f64).sqrt(), 0., (3. / 5.0_f64).sqrt()]
-            }
-            _ => {
-                unimplemented!("For higher order gaussian Integration the Golub Welsh Algorithm should be implemented.")
-            }
-        }
-    }
-    fn get_gauss_weights(&self) -> Array1<f64> {
-        match self.gauss_degree {
-            1 => {
-                array![2.]
-            }
-            2 => {
-                array![1., 1.]
-            }
-            3 => {
-                array![5. / 9., 8. / 9., 5. / 9.]
-            }
-            _ => {
-                unimplemented!("For higher order gaussian Integration the Golub Welsh Algorithm should be implemented.")
-            }
-        }
-    }
     fn get_quadrilateral(index: usize) -> Array2<f64> {
         match index {
             1 => {
@@ -350,15 +316,15 @@ impl Quadrilateral3DIntegrator {
         }
     }
 
-    fn integrate_quadrilateral<T: Simplex3DFunction>(
+    fn integrate_quadrilateral<T: SimplexFunction<3>>(
         &self,
         barycentric_domain: &Array2<f64>,
         func: &Box<T>,
         simplex: &Simplex3D,
     ) -> T::Return {
         let mut sum = func.additive_neutral_element();
-        let gauss_points = self.get_gauss_points();
-        let gauss_weights = self.get_gauss_weights();
+        let gauss_points = &self.gauss_points;
+        let gauss_weights = &self.gauss_weights;
         for i in 0..self.gauss_degree {
             for j in 0..self.gauss_degree {
                 for k in 0..self.gauss_degree {
@@ -394,21 +360,23 @@ impl Quadrilateral3DIntegrator {
     }
 }
 
-impl<IntegratorDummy> Simplex3DIntegrator<IntegratorDummy> for Quadrilateral3DIntegrator {
-    fn integrate_over_domain<T: Simplex3DFunction>(
+impl<IntegratorDummy> SimplexIntegrator<3, IntegratorDummy> for Quadrilateral3DIntegrator {
+    fn dupe(&self) -> Self {
+        Self {
+            gauss_degree: self.gauss_degree,
+            gauss_points: self.gauss_points.clone(),
+            gauss_weights: self.gauss_weights.clone(),
+        }
+    }
+
+    fn integrate_over_domain<T: SimplexFunction<3>>(
         &self,
         transformation: &Array2<f64>,
         func: &Box<T>,
         simplex: &Simplex3D,
         _cached_data: &mut IntegratorDummy,
-    ) -> T::Return {
-        if !(transformation.shape()[0] == 4 && transformation.shape()[1] == 4) {
-            panic!(
-                "Die Transformationsmatrix ist nicht der Dimension 4 x 4, sondern {} x {}",
-                transformation.shape()[0],
-                transformation.shape()[1]
-            )
-        }
+    ) -> Result<T::Return> {
+        check_shape(transformation, [4, 4])?;
         let mut sum = func.additive_neutral_element();
         for i in 1..=4 {
             // M(4,8)
@@ -417,7 +385,7 @@ impl<IntegratorDummy> Simplex3DIntegrator<IntegratorDummy> for Quadrilateral3DIn
             let d1 = transformation.dot(&d1);
             sum.add_assign(&self.integrate_quadrilateral(&d1, func, simplex));
         }
-        return sum;
+        return Ok(sum);
     }
 }
 
@@ -428,8 +396,10 @@ mod tests {
     use crate::integrator_tests_3d;
 
     integrator_tests_3d! {
-        // gauss_order1: Quadrilateral3DIntegrator: Quadrilateral3DIntegrator::new(1), IntegratorDummy: IntegratorDummy::get(), // Unterintegriert?
-        gauss_order2: Quadrilateral3DIntegrator: Quadrilateral3DIntegrator::new(2), IntegratorDummy: IntegratorDummy::get(),
-        gauss_order3: Quadrilateral3DIntegrator: Quadrilateral3DIntegrator::new(3), IntegratorDummy: IntegratorDummy::get(),
+        // gauss_order1: Quadrilateral3DIntegrator: Quadrilateral3DIntegrator::new(1).unwrap(), IntegratorDummy: IntegratorDummy::get(), // Unterintegriert?
+        gauss_order2: Quadrilateral3DIntegrator: Quadrilateral3DIntegrator::new(2).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        gauss_order3: Quadrilateral3DIntegrator: Quadrilateral3DIntegrator::new(3).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        gauss_order5: Quadrilateral3DIntegrator: Quadrilateral3DIntegrator::new(5).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        gauss_order8: Quadrilateral3DIntegrator: Quadrilateral3DIntegrator::new(8).unwrap(), IntegratorDummy: IntegratorDummy::get(),
     }
 }
\ No newline at end of file
@@ -0,0 +1,349 @@
+use ndarray::{array, Array1, Array2};
+
+use crate::common::{det3x3, IntegratorDummy};
+use crate::error::{check_shape, IntegrationError, Result};
+use crate::integration_3d::{
+    domain::{Simplex3D, SimplexFunction, SimplexIntegrator},
+    SimplexResultType,
+};
+
+/// One symmetric orbit of a tetrahedral rule: multiplicity (1, 4, 6, 12 or 24), the generating
+/// point in barycentric coordinates and the weight of every point in the orbit.
+type Orbit = (usize, [f64; 4], f64);
+
+/// Fully symmetric rules for the tetrahedron with the orbit structure of Keast (1986) and
+/// Jaśkowiec & Sukumar (2020), for the degrees 1 to 10.
+/// All points lie inside the tetrahedron and all weights are positive.
+/// The weights are normalized to the volume of the tetrahedron.
+#[rustfmt::skip]
+fn tetrahedral_orbits(order: usize) -> Result<&'static [Orbit]> {
+    Ok(match order {
+        1 => &[
+            (1, [0.25, 0.25, 0.25, 0.25], 1.0),
+        ],
+        2 => &[
+            (4, [0.13819660112501053, 0.13819660112501053, 0.13819660112501053, 0.5854101966249684], 0.25),
+        ],
+        3 => &[
+            (4, [0.11700008752183856, 0.11700008752183856, 0.11700008752183856, 0.6489997374344844], 0.13542909500580752),
+            (4, [0.3297893780727358, 0.3297893780727358, 0.3297893780727358, 0.01063186578179266], 0.1145709049941925),
+        ],
+        // Für Grad 4 wird die Regel vom Grad 5 mit 14 Punkten verwendet
+        4 | 5 => &[
+            (4, [0.3108859192633006, 0.3108859192633006, 0.3108859192633006, 0.06734224221009817], 0.11268792571801585),
+            (4, [0.09273525031089122, 0.09273525031089122, 0.09273525031089122, 0.7217942490673264], 0.07349304311636196),
+            (6, [0.04550370412564965, 0.04550370412564965, 0.45449629587435036, 0.45449629587435036], 0.042546020777081466),
+        ],
+        6 => &[
+            (4, [0.3223378901422755, 0.3223378901422755, 0.3223378901422755, 0.03298632957317347], 0.055357181543654724),
+            (4, [0.04067395853461135, 0.04067395853461135, 0.04067395853461135, 0.877978124396166], 0.010077211055320643),
+            (4, [0.21460287125915203, 0.21460287125915203, 0.21460287125915203, 0.3561913862225439], 0.039922750258167494),
+            (12, [0.06366100187501753, 0.06366100187501753, 0.6030056647916492, 0.2696723314583158], 0.048214285714285716),
+        ],
+        7 => &[
+            (1, [0.25, 0.25, 0.25, 0.25], 0.09548528946413085),
+            (4, [0.3157011497782028, 0.3157011497782028, 0.3157011497782028, 0.0528965506653916], 0.04232958120996703),
+            (6, [0.05048982259839637, 0.05048982259839637, 0.44951017740160365, 0.44951017740160365], 0.03189692783285758),
+            (12, [0.021265472541483248, 0.021265472541483248, 0.14663881381848495, 0.8108302410985485], 0.008110770829903342),
+            (12, [0.18883383102600104, 0.18883383102600104, 0.5751716375870001, 0.04716070036099788], 0.03720713072833462),
+        ],
+        8 => &[
+            (4, [0.18707627001778007, 0.18707627001778007, 0.18707627001778007, 0.43877118994665976], 0.04722890689865714),
+            (4, [0.04480899590365718, 0.04480899590365718, 0.04480899590365718, 0.8655730122890285], 0.008754015940988458),
+            (4, [0.3137593219953921, 0.3137593219953921, 0.3137593219953921, 0.0587220340138237], 0.04374045196784967),
+            (4, [0.11521849939143243, 0.11521849939143243, 0.11521849939143243, 0.6543445018257027], 0.03019354218832435),
+            (6, [0.06561157362072134, 0.06561157362072134, 0.43438842637927866, 0.43438842637927866], 0.03696243571447139),
+            (12, [0.0212318685134353, 0.0212318685134353, 0.24319451094340974, 0.7143417520297196], 0.007159349597468387),
+            (12, [0.2038721882493015, 0.2038721882493015, 0.004730965632852893, 0.5875246578685441], 0.014387126880022713),
+        ],
+        9 => &[
+            (1, [0.25, 0.25, 0.25, 0.25], 0.0575032194063012),
+            (4, [0.05953491593914446, 0.05953491593914446, 0.05953491593914446, 0.8213952521825666], 0.003328409580673428),
+            (4, [0.03897706188992429, 0.03897706188992429, 0.03897706188992429, 0.8830688143302271], 0.005163426295564457),
+            (4, [0.16474604050091718, 0.16474604050091718, 0.16474604050091718, 0.5057618784972484], 0.024039794568060064),
+            (4, [0.3221412681310642, 0.3221412681310642, 0.3221412681310642, 0.03357619560680746], 0.02995085496715541),
+            (6, [0.10994308168491795, 0.10994308168491795, 0.39005691831508205, 0.39005691831508205], 0.03739994888853011),
+            (12, [0.4594565186364763, 0.4594565186364763, 0.07948470105967534, 0.001602261667372125], 0.00811264867524351),
+            (12, [0.18339888080093725, 0.18339888080093725, 0.0347476253880564, 0.5984546130100691], 0.020727326676914105),
+            (12, [0.03363607635264877, 0.03363607635264877, 0.7184696190737336, 0.21425822822096888], 0.010173953449234439),
+        ],
+        10 => &[
+            (1, [0.25, 0.25, 0.25, 0.25], 0.04791247162427909),
+            (4, [0.3117825175317806, 0.3117825175317806, 0.3117825175317806, 0.0646524474046582], 0.021529348454839708),
+            (4, [0.1550466360283467, 0.1550466360283467, 0.1550466360283467, 0.5348600919149599], 0.031612738375650366),
+            (4, [0.03318315809474317, 0.03318315809474317, 0.03318315809474317, 0.9004505257157704], 0.003512249246040439),
+            (6, [0.3831276557620955, 0.3831276557620955, 0.11687234423790448, 0.11687234423790448], 0.029814177526522555),
+            (6, [0.4954850810808019, 0.4954850810808019, 0.00451491891919807, 0.00451491891919807], 0.0012445980911349613),
+            (12, [0.0479429094721537, 0.0479429094721537, 0.6160333226148151, 0.2880808584408775], 0.01363391260158407),
+            (12, [0.11850202408319763, 0.11850202408319763, 0.02311103145012409, 0.7398849203834806], 0.009481231069522675),
+            (12, [0.44598054452292785, 0.44598054452292785, 0.0017833224406069376, 0.10625558851353734], 0.0055249258443609405),
+            (12, [0.23523081816181848, 0.23523081816181848, 0.02477305240204856, 0.5047653112743145], 0.015267777559549939),
+            (12, [0.0002594832327198225, 0.0002594832327198225, 0.18045291758749854, 0.8190281159470618], 0.0010186137886201878),
+        ],
+        _ => {
+            return Err(IntegrationError::UnsupportedOrder { rule: "tetrahedral", order })
+        }
+    })
+}
+
+/// Expands an orbit into all distinct permutations of its generating point, returned as columns.
+/// A table entry with the wrong multiplicity is a [`IntegrationError::NumericalFailure`].
+fn expand_orbit(orbit: &Orbit) -> Result<Array2<f64>> {
+    let generator = orbit.1;
+    let mut points: Vec<[f64; 4]> = Vec::with_capacity(orbit.0);
+    for i in 0..4 {
+        for j in 0..4 {
+            for k in 0..4 {
+                if i == j || i == k || j == k {
+                    continue;
+                }
+                let l = 6 - i - j - k;
+                let point = [generator[i], generator[j], generator[k], generator[l]];
+                if !points.contains(&point) {
+                    points.push(point);
+                }
+            }
+        }
+    }
+    if points.len() != orbit.0 {
+        return Err(IntegrationError::NumericalFailure(format!(
+            "the orbit {:?} has {} instead of {} points",
+            generator,
+            points.len(),
+            orbit.0
+        )));
+    }
+    let mut result = Array2::zeros([4, points.len()]);
+    for (n, point) in points.iter().enumerate() {
+        for m in 0..4 {
+            result[[m, n]] = point[m];
+        }
+    }
+    Ok(result)
+}
+
+/// Integrates polynomials of degree `order` exactly using the rules in [`tetrahedral_orbits()`]
+///
+/// The weights are scaled with the signed volume, see
+/// [`Simplex3D::get_volume()`](crate::simplex::Simplex::get_volume).
+pub struct TetrahedralIntegrator {
+    // Die Orbits werden einmal bei der Konstruktion expandiert, M(4,n)
+    integration_points: Array2<f64>,
+    integration_weights: Array1<f64>,
+}
+
+impl TetrahedralIntegrator {
+    /// The order must be between 1 and 10, otherwise [`IntegrationError::UnsupportedOrder`].
+    pub fn new(order: usize) -> Result<Self> {
+        let orbits = tetrahedral_orbits(order)?;
+        let num_points = orbits.iter().map(|orbit| orbit.0).sum();
+        let mut integration_points = Array2::zeros([4, num_points]);
+        let mut integration_weights = Array1::zeros(num_points);
+        let mut offset = 0;
+        for orbit in orbits {
+            let points = expand_orbit(orbit)?;
+            for (n, point) in points.columns().into_iter().enumerate() {
+                integration_points.column_mut(offset + n).assign(&point);
+                integration_weights[offset + n] = orbit.2;
+            }
+            offset += orbit.0;
+        }
+        Ok(Self {
+            integration_points,
+            integration_weights,
+        })
+    }
+
+    /// The number of function evaluations per tetrahedron.
+    pub fn get_num_points(&self) -> usize {
+        self.integration_weights.len()
+    }
+}
+
+impl SimplexIntegrator<3, IntegratorDummy> for TetrahedralIntegrator {
+    fn dupe(&self) -> Self {
+        Self {
+            integration_points: self.integration_points.clone(),
+            integration_weights: self.integration_weights.clone(),
+        }
+    }
+
+    fn integrate_over_domain<T: SimplexFunction<3>>(
+        &self,
+        transformation: &Array2<f64>,
+        func: &Box<T>,
+        simplex: &Simplex3D,
+        _cache_data: &mut IntegratorDummy,
+    ) -> Result<T::Return> {
+        check_shape(transformation, [4, 4])?;
+        // Gleiches Vorzeichen wie Simplex3D::get_volume()
+        let jacobi = array![
+            [-1., -1., -1.],
+            [1., 0., 0.],
+            [0., 1., 0.],
+            [0., 0., 1.]
+        ];
+
+        // M(3,3) = M(3,4) x M(4,4) x M(4,3)
+        let real_jacobi = simplex.get_points().dot(transformation).dot(&jacobi);
+        let volume = det3x3(&real_jacobi) / 6.;
+
+        // M(4,n) = M(4,4) x M(4,n)
+        let integration_points = transformation.dot(&self.integration_points);
+
+        let mut result = func.additive_neutral_element();
+        for (integration_point, weight) in integration_points
+            .columns()
+            .into_iter()
+            .zip(self.integration_weights.iter())
+        {
+            let mut point = func.function_vec(&integration_point.into_owned(), simplex);
+            point *= volume * weight;
+            result.add_assign(&point);
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{array, Array2};
+
+    use crate::common::IntegratorDummy;
+    use crate::error::IntegrationError;
+    use crate::integration_3d::domain::{
+        ResultTypeWrapper, Simplex3D, Simplex3DFunction, SimplexIntegrator,
+    };
+    use crate::integration_3d::integrators::TetrahedralIntegrator;
+    use crate::integrator_tests_3d;
+
+    integrator_tests_3d! {
+        order1: TetrahedralIntegrator: TetrahedralIntegrator::new(1).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order2: TetrahedralIntegrator: TetrahedralIntegrator::new(2).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order5: TetrahedralIntegrator: TetrahedralIntegrator::new(5).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order8: TetrahedralIntegrator: TetrahedralIntegrator::new(8).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        order10: TetrahedralIntegrator: TetrahedralIntegrator::new(10).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+    }
+
+    /// xi1^i * xi2^j * xi3^k * xi4^l
+    struct BarycentricMonomial(i32, i32, i32, i32);
+
+    impl Simplex3DFunction for BarycentricMonomial {
+        type Return = ResultTypeWrapper<f64>;
+        fn function(
+            &self,
+            xi1: f64,
+            xi2: f64,
+            xi3: f64,
+            xi4: f64,
+            _simplex: &Simplex3D,
+        ) -> Self::Return {
+            ResultTypeWrapper::new(
+                xi1.powi(self.0) * xi2.powi(self.1) * xi3.powi(self.2) * xi4.powi(self.3),
+            )
+        }
+    }
+
+    fn factorial(n: i32) -> f64 {
+        (1..=n).map(|i| i as f64).product()
+    }
+
+    #[test]
+    fn number_of_points() {
+        let expected = [1, 4, 8, 14, 14, 24, 35, 46, 59, 85];
+        for (order, num_points) in (1..=10).zip(expected) {
+            assert_eq!(
+                TetrahedralIntegrator::new(order).unwrap().get_num_points(),
+                num_points
+            );
+        }
+    }
+
+    #[test]
+    fn monomials_exact_up_to_order() {
+        let sim = Simplex3D::new_from_points(
+            &array![1., 1., 0.],
+            &array![3., 1.5, 0.5],
+            &array![1.5, 2., 0.],
+            &array![1.2, 1.4, 1.],
+        );
+        let volume = sim.get_volume();
+        for order in 1..=10 {
+            let inte = TetrahedralIntegrator::new(order).unwrap();
+            for degree in 0..=order as i32 {
+                for i in 0..=degree {
+                    for j in 0..=(degree - i) {
+                        for k in 0..=(degree - i - j) {
+                            let l = degree - i - j - k;
+                            let func = Box::new(BarycentricMonomial(i, j, k, l));
+                            let result = inte
+                                .integrate_simplex(&func, &sim, &mut IntegratorDummy::get())
+                                .unwrap()
+                                .get();
+                            let exact = 6.
+                                * volume
+                                * factorial(i)
+                                * factorial(j)
+                                * factorial(k)
+                                * factorial(l)
+                                / factorial(degree + 3);
+                            assert!(
+                                ((result - exact) / exact).abs() < 1e-12,
+                                "Order {}: xi1^{} xi2^{} xi3^{} xi4^{}, Expected: {}, Actual: {}",
+                                order,
+                                i,
+                                j,
+                                k,
+                                l,
+                                exact,
+                                result
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_input_is_an_error() {
+        assert!(matches!(
+            TetrahedralIntegrator::new(11),
+            Err(IntegrationError::UnsupportedOrder { order: 11, .. })
+        ));
+        assert!(TetrahedralIntegrator::new(0).is_err());
+        // Ein Tabelleneintrag mit falscher Vielfachheit
+        assert!(matches!(
+            super::expand_orbit(&(4, [0.25, 0.25, 0.25, 0.25], 1.)),
+            Err(IntegrationError::NumericalFailure(_))
+        ));
+
+        let inte = TetrahedralIntegrator::new(2).unwrap();
+        let func = Box::new(BarycentricMonomial(1, 0, 0, 0));
+        let sim = Simplex3D::new_from_points(
+            &array![0., 0., 0.],
+            &array![1., 0., 0.],
+            &array![0., 1., 0.],
+            &array![0., 0., 1.],
+        );
+        assert!(matches!(
+            inte.integrate_over_domain(&Array2::eye(3), &func, &sim, &mut IntegratorDummy::get()),
+            Err(IntegrationError::DimensionMismatch {
+                expected: [4, 4],
+                found: [3, 3]
+            })
+        ));
+
+        // Alle Punkte liegen in einer Ebene
+        let flat = Simplex3D::new_from_points(
+            &array![0., 0., 0.],
+            &array![1., 0., 0.],
+            &array![0., 1., 0.],
+            &array![1., 1., 0.],
+        );
+        assert!(matches!(
+            inte.integrate_simplex(&func, &flat, &mut IntegratorDummy::get()),
+            Err(IntegrationError::DegenerateSimplex { .. })
+        ));
+    }
+}
@@ -7,9 +7,10 @@ use std::ops::AddAssign;
 use ndarray::{array, Array1, Array2};
 
 use crate::common::IntegratorDummy;
+use crate::error::{check_shape, Result};
 use crate::integration_3d::{
-    domain::{Simplex3D, Simplex3DFunction, Simplex3DIntegrator},
-    Simplex3DResultType,
+    domain::{Simplex3D, SimplexFunction, SimplexIntegrator},
+    SimplexResultType,
 };
 
 pub struct OrientationChecker;
@@ -26,21 +27,26 @@ impl OrientationChecker {
     }
 }
 
-impl Simplex3DIntegrator<IntegratorDummy> for OrientationChecker {
-    fn integrate_over_domain<T: Simplex3DFunction>(
+impl SimplexIntegrator<3, IntegratorDummy> for OrientationChecker {
+    fn dupe(&self) -> Self {
+        Self {}
+    }
+
+    fn integrate_over_domain<T: SimplexFunction<3>>(
         &self,
         transformation: &Array2<f64>,
         func: &Box<T>,
         simplex: &Simplex3D,
         _cache_data: &mut IntegratorDummy,
-    ) -> T::Return {
+    ) -> Result<T::Return> {
+        check_shape(transformation, [4, 4])?;
         let mut result = func.additive_neutral_element();
         for i in 0..4 {
             let point = Self::point_order(i);
             let point = transformation.dot(&point);
 
-            Simplex3DResultType::add_assign(&mut result, &func.function_vec(&point, simplex));
+            SimplexResultType::add_assign(&mut result, &func.function_vec(&point, simplex));
         }
-        return result;
+        return Ok(result);
     }
 }
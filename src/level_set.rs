@@ -0,0 +1,420 @@
+//! Integration of functions with a kink along the zero level set of a second function.
+//!
+//! The phase field integrands contain `|varsigma(f_base)|`, so they are not differentiable where
+//! the quadratic ansatz function `f_base` is zero. The [`CutIntegrator`] splits the simplex along
+//! this zero level set, the base integrator only sees the smooth pieces on both sides.
+
+use ndarray::{Array1, Array2};
+
+use crate::common::{determinant, ordered_map, IntegratorDummy, MaybeSync};
+use crate::error::{check_shape, IntegrationError, Result};
+use crate::integration_2d::integrators::edge_subdivision_integrator::sub_transformations as sub_triangles;
+use crate::integration_3d::integrators::edge_subdivision_integrator::sub_transformations as sub_tetrahedra;
+use crate::problems::problem_definition::{shape_func_2d, shape_func_3d};
+use crate::simplex::{Simplex, SimplexFunction, SimplexIntegrator, SimplexResultType};
+
+/// A function over the simplex whose zero level set is the kink of the integrand.
+pub trait LevelSet<const D: usize>: MaybeSync {
+    /// The value at the barycentric coordinates `xi` of the `simplex`.
+    fn value(&self, xi: &Array1<f64>, simplex: &Simplex<D>) -> f64;
+}
+
+impl<const D: usize, F: Fn(&Array1<f64>, &Simplex<D>) -> f64 + MaybeSync> LevelSet<D> for F {
+    fn value(&self, xi: &Array1<f64>, simplex: &Simplex<D>) -> f64 {
+        self(xi, simplex)
+    }
+}
+
+/// The ansatz function `f_base` of the phase field problems with the given nodal values, 6 for a
+/// triangle and 10 for a tetrahedron. The values belong to the vertices, then to the midpoints of
+/// the edges (1,3), (1,2), (2,3) and for a tetrahedron (1,4), (2,4), (3,4).
+#[derive(Clone)]
+pub struct AnsatzLevelSet<const D: usize> {
+    nodal_values: Array1<f64>,
+}
+
+impl<const D: usize> AnsatzLevelSet<D> {
+    pub fn new(nodal_values: Array1<f64>) -> Self {
+        Self { nodal_values }
+    }
+}
+
+impl LevelSet<2> for AnsatzLevelSet<2> {
+    fn value(&self, xi: &Array1<f64>, _simplex: &Simplex<2>) -> f64 {
+        shape_func_2d::ansatz_function(&self.nodal_values, xi)
+    }
+}
+
+impl LevelSet<3> for AnsatzLevelSet<3> {
+    fn value(&self, xi: &Array1<f64>, _simplex: &Simplex<3>) -> f64 {
+        shape_func_3d::ansatz_function(&self.nodal_values, xi)
+    }
+}
+
+/// Divides every edge into `order` parts like the edge subdivision integrators and cuts every
+/// subsimplex along the zero level set, which is interpolated linearly between its vertices.
+/// The pieces are split into simplices and integrated with the base integrator.
+///
+/// A linear level set is cut exactly. For a curved one, e.g. the [`AnsatzLevelSet`], the distance
+/// between the level set and its interpolation decreases with the square of `1 / order`.
+/// All pieces keep the orientation, so the sign is the one of the base integrator.
+pub struct CutIntegrator<I, L> {
+    base_integrator: I,
+    level_set: L,
+    order: usize,
+}
+
+impl<I, L> CutIntegrator<I, L> {
+    /// Every edge is divided into `order` parts, so the order must be at least 1.
+    pub fn new(base_integrator: I, level_set: L, order: usize) -> Result<Self> {
+        if order == 0 {
+            return Err(IntegrationError::UnsupportedOrder {
+                rule: "level set cut",
+                order,
+            });
+        }
+        Ok(Self {
+            base_integrator,
+            level_set,
+            order,
+        })
+    }
+
+    /// Integrates the pieces of all subsimplices, summed up in their order.
+    fn integrate_pieces<const D: usize, T: SimplexFunction<D>>(
+        &self,
+        sub_transformations: Vec<Array2<f64>>,
+        func: &Box<T>,
+        simplex: &Simplex<D>,
+    ) -> Result<T::Return>
+    where
+        I: SimplexIntegrator<D, IntegratorDummy>,
+        L: LevelSet<D>,
+    {
+        let sub_results = ordered_map(sub_transformations, |transformation| {
+            let values: Vec<f64> = transformation
+                .columns()
+                .into_iter()
+                .map(|xi| self.level_set.value(&xi.to_owned(), simplex))
+                .collect();
+            let mut result = func.additive_neutral_element();
+            for mut piece in pieces(&values) {
+                // Teile ohne Volumen tragen nichts bei, die übrigen werden positiv orientiert
+                let det = determinant(&piece)?;
+                if det == 0. {
+                    continue;
+                }
+                if det < 0. {
+                    let first = piece.column(0).to_owned();
+                    let second = piece.column(1).to_owned();
+                    piece.column_mut(0).assign(&second);
+                    piece.column_mut(1).assign(&first);
+                }
+                let piece_result = self.base_integrator.integrate_over_domain(
+                    &transformation.dot(&piece),
+                    func,
+                    simplex,
+                    &mut IntegratorDummy,
+                )?;
+                SimplexResultType::add_assign(&mut result, &piece_result);
+            }
+            Ok(result)
+        })
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?;
+        let mut result = func.additive_neutral_element();
+        for sub_result in sub_results {
+            result.add_assign(&sub_result);
+        }
+        Ok(result)
+    }
+}
+
+/// The vertex `i` of a subsimplex with `n` vertices in its local barycentric coordinates.
+fn vertex(n: usize, i: usize) -> Array1<f64> {
+    let mut point = Array1::zeros(n);
+    point[i] = 1.;
+    point
+}
+
+/// The zero of the linear interpolation of the `values` along the edge between the vertices `i`
+/// and `j`, which have different signs.
+fn crossing(values: &[f64], i: usize, j: usize) -> Array1<f64> {
+    let t = values[i] / (values[i] - values[j]);
+    let mut point = Array1::zeros(values.len());
+    point[i] = 1. - t;
+    point[j] = t;
+    point
+}
+
+/// The simplex with the `points` as vertices, i.e. as columns.
+fn piece(points: &[&Array1<f64>]) -> Array2<f64> {
+    let mut piece = Array2::zeros([points.len(), points.len()]);
+    for (mut column, point) in piece.columns_mut().into_iter().zip(points) {
+        column.assign(point);
+    }
+    piece
+}
+
+/// The three tetrahedra of the prism with the triangles `a` and `b`, where `a[i]` and `b[i]` are
+/// connected by an edge.
+fn prism(a: [&Array1<f64>; 3], b: [&Array1<f64>; 3]) -> [Array2<f64>; 3] {
+    [
+        piece(&[a[0], a[1], a[2], b[0]]),
+        piece(&[a[1], a[2], b[0], b[1]]),
+        piece(&[a[2], b[0], b[1], b[2]]),
+    ]
+}
+
+/// The pieces of a subsimplex on both sides of the linear interpolation of the `values` at its
+/// vertices, in its local barycentric coordinates. The orientation is not fixed yet.
+fn pieces(values: &[f64]) -> Vec<Array2<f64>> {
+    let n = values.len();
+    // Null zählt zur nichtnegativen Seite, Schnittpunkte in einer Ecke ergeben leere Teile
+    let (negative, nonnegative): (Vec<usize>, Vec<usize>) = (0..n).partition(|&i| values[i] < 0.);
+    if negative.is_empty() || nonnegative.is_empty() {
+        return vec![Array2::eye(n)];
+    }
+    // Die Ecke, die allein auf ihrer Seite liegt
+    let (lone, others) = if negative.len() == 1 {
+        (negative[0], &nonnegative)
+    } else {
+        (nonnegative[0], &negative)
+    };
+
+    match (n, negative.len()) {
+        (3, _) => {
+            let (a, b, c) = (lone, others[0], others[1]);
+            let (pab, pac) = (crossing(values, a, b), crossing(values, a, c));
+            let (vb, vc) = (vertex(n, b), vertex(n, c));
+            vec![
+                piece(&[&vertex(n, a), &pab, &pac]),
+                piece(&[&pab, &vb, &vc]),
+                piece(&[&pab, &vc, &pac]),
+            ]
+        }
+        (4, 1) | (4, 3) => {
+            let a = lone;
+            let p: Vec<Array1<f64>> = others.iter().map(|&o| crossing(values, a, o)).collect();
+            let v: Vec<Array1<f64>> = others.iter().map(|&o| vertex(n, o)).collect();
+            let mut pieces = vec![piece(&[&vertex(n, a), &p[0], &p[1], &p[2]])];
+            pieces.extend(prism([&p[0], &p[1], &p[2]], [&v[0], &v[1], &v[2]]));
+            pieces
+        }
+        _ => {
+            // Zwei Ecken auf jeder Seite, die Schnittfläche ist ein Viereck
+            let (a, b, c, d) = (negative[0], negative[1], nonnegative[0], nonnegative[1]);
+            let (pac, pad) = (crossing(values, a, c), crossing(values, a, d));
+            let (pbc, pbd) = (crossing(values, b, c), crossing(values, b, d));
+            let (va, vb, vc, vd) = (vertex(n, a), vertex(n, b), vertex(n, c), vertex(n, d));
+            let mut pieces = prism([&va, &pac, &pad], [&vb, &pbc, &pbd]).to_vec();
+            pieces.extend(prism([&vc, &pac, &pbc], [&vd, &pad, &pbd]));
+            pieces
+        }
+    }
+}
+
+impl<I: SimplexIntegrator<2, IntegratorDummy>, L: LevelSet<2> + Clone>
+    SimplexIntegrator<2, IntegratorDummy> for CutIntegrator<I, L>
+{
+    fn dupe(&self) -> Self {
+        Self {
+            base_integrator: self.base_integrator.dupe(),
+            level_set: self.level_set.clone(),
+            order: self.order,
+        }
+    }
+
+    fn integrate_over_domain<T: SimplexFunction<2>>(
+        &self,
+        transformation: &Array2<f64>,
+        func: &Box<T>,
+        simplex: &Simplex<2>,
+        _cached_data: &mut IntegratorDummy,
+    ) -> Result<T::Return> {
+        check_shape(transformation, [3, 3])?;
+        self.integrate_pieces(sub_triangles(self.order, transformation), func, simplex)
+    }
+}
+
+impl<I: SimplexIntegrator<3, IntegratorDummy>, L: LevelSet<3> + Clone>
+    SimplexIntegrator<3, IntegratorDummy> for CutIntegrator<I, L>
+{
+    fn dupe(&self) -> Self {
+        Self {
+            base_integrator: self.base_integrator.dupe(),
+            level_set: self.level_set.clone(),
+            order: self.order,
+        }
+    }
+
+    fn integrate_over_domain<T: SimplexFunction<3>>(
+        &self,
+        transformation: &Array2<f64>,
+        func: &Box<T>,
+        simplex: &Simplex<3>,
+        _cached_data: &mut IntegratorDummy,
+    ) -> Result<T::Return> {
+        check_shape(transformation, [4, 4])?;
+        self.integrate_pieces(sub_tetrahedra(self.order, transformation), func, simplex)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{array, Array1};
+
+    use super::{AnsatzLevelSet, CutIntegrator, LevelSet};
+    use crate::common::IntegratorDummy;
+    use crate::integration_2d::integrators::{
+        DunavantIntegrator, EdgeSubdivisionIntegrator, Quadrilateral2DIntegrator,
+    };
+    use crate::integration_3d::integrators::TetrahedralIntegrator;
+    use crate::{integrator_tests, integrator_tests_3d};
+
+    integrator_tests! {
+        dunavant: CutIntegrator<DunavantIntegrator, AnsatzLevelSet<2>>: CutIntegrator::new(DunavantIntegrator::new(2).unwrap(), AnsatzLevelSet::new(array![0.5, -0.4, 0.3, 0.2, -0.1, 0.1]), 2).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+        quadrilaterial: CutIntegrator<Quadrilateral2DIntegrator, AnsatzLevelSet<2>>: CutIntegrator::new(Quadrilateral2DIntegrator::new(1).unwrap(), AnsatzLevelSet::new(array![0.5, -0.4, 0.3, 0.2, -0.1, 0.1]), 1).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+    }
+
+    integrator_tests_3d! {
+        tetrahedral: CutIntegrator<TetrahedralIntegrator, AnsatzLevelSet<3>>: CutIntegrator::new(TetrahedralIntegrator::new(2).unwrap(), AnsatzLevelSet::new(array![0.5, -0.4, 0.3, -0.2, 0.2, -0.1, 0.1, 0.3, -0.3, 0.1]), 2).unwrap(), IntegratorDummy: IntegratorDummy::get(),
+    }
+
+    #[test]
+    fn linear_kink_is_exact() {
+        use crate::integration_2d::domain::{Simplex2D, SimplexIntegrator};
+        use crate::integration_3d::domain::Simplex3D;
+
+        // ∫ |x - a| über das Einheitsdreieck und ∫ |x + y - a| über den Einheitstetraeder
+        let a: f64 = 0.3;
+        let exact_2d = a.powi(2) / 2. - a.powi(3) / 6. + (1. - a).powi(3) / 6.;
+        let exact_3d = 1. / 12. - a / 6. + a.powi(3) / 3. - a.powi(4) / 6.;
+
+        let sim = Simplex2D::new_from_points(&array![0., 0.], &array![1., 0.], &array![0., 1.]);
+        let level_set =
+            move |xi: &Array1<f64>, simplex: &Simplex2D| simplex.get_physical_point(xi)[0] - a;
+        let func = Box::new(move |xi1: f64, xi2: f64, xi3: f64, simplex: &Simplex2D| {
+            level_set(&array![xi1, xi2, xi3], simplex).abs()
+        });
+        for order in 1..=3 {
+            let result = CutIntegrator::new(DunavantIntegrator::new(1).unwrap(), level_set, order)
+                .unwrap()
+                .integrate_simplex(&func, &sim, &mut IntegratorDummy::get())
+                .unwrap()
+                .get();
+            assert!((result - exact_2d).abs() < 1e-14, "{} {}", result, exact_2d);
+        }
+        // Ohne Schnitt konvergiert die Unterteilung nur langsam
+        let uncut = EdgeSubdivisionIntegrator::new(DunavantIntegrator::new(1).unwrap(), 3)
+            .unwrap()
+            .integrate_simplex(&func, &sim, &mut IntegratorDummy::get())
+            .unwrap()
+            .get();
+        assert!((uncut - exact_2d).abs() > 1e-4);
+
+        // Zwei Ecken auf jeder Seite
+        let sim = Simplex3D::new_from_points(
+            &array![0., 0., 0.],
+            &array![1., 0., 0.],
+            &array![0., 1., 0.],
+            &array![0., 0., 1.],
+        );
+        let level_set = move |xi: &Array1<f64>, simplex: &Simplex3D| {
+            let x = simplex.get_physical_point(xi);
+            x[0] + x[1] - a
+        };
+        let func = Box::new(
+            move |xi1: f64, xi2: f64, xi3: f64, xi4: f64, simplex: &Simplex3D| {
+                level_set(&array![xi1, xi2, xi3, xi4], simplex).abs()
+            },
+        );
+        for order in 1..=3 {
+            let result =
+                CutIntegrator::new(TetrahedralIntegrator::new(1).unwrap(), level_set, order)
+                    .unwrap()
+                    .integrate_simplex(&func, &sim, &mut IntegratorDummy::get())
+                    .unwrap()
+                    .get();
+            assert!((result - exact_3d).abs() < 1e-14, "{} {}", result, exact_3d);
+        }
+    }
+
+    #[test]
+    fn ansatz_level_set_interpolates_the_nodal_values() {
+        use crate::integration_2d::domain::Simplex2D;
+        use crate::integration_3d::domain::Simplex3D;
+
+        let sim = Simplex2D::new_from_points(&array![0., 0.], &array![2., 0.], &array![0., 1.]);
+        let nodal_values = array![0.5, -0.4, 0.3, 0.2, -0.1, 0.1];
+        let level_set = AnsatzLevelSet::new(nodal_values.clone());
+        let nodes = [
+            array![1., 0., 0.],
+            array![0., 1., 0.],
+            array![0., 0., 1.],
+            array![0.5, 0., 0.5],
+            array![0.5, 0.5, 0.],
+            array![0., 0.5, 0.5],
+        ];
+        for (node, value) in nodes.iter().zip(nodal_values.iter()) {
+            assert!((level_set.value(node, &sim) - value).abs() < 1e-15);
+        }
+
+        let sim = Simplex3D::new_from_points(
+            &array![0., 0., 0.],
+            &array![1., 0., 0.],
+            &array![0., 1., 0.],
+            &array![0., 0., 1.],
+        );
+        let nodal_values = array![0.5, -0.4, 0.3, -0.2, 0.2, -0.1, 0.1, 0.3, -0.3, 0.1];
+        let level_set = AnsatzLevelSet::new(nodal_values.clone());
+        let nodes = [
+            array![1., 0., 0., 0.],
+            array![0., 1., 0., 0.],
+            array![0., 0., 1., 0.],
+            array![0., 0., 0., 1.],
+            array![0.5, 0., 0.5, 0.],
+            array![0.5, 0.5, 0., 0.],
+            array![0., 0.5, 0.5, 0.],
+            array![0.5, 0., 0., 0.5],
+            array![0., 0.5, 0., 0.5],
+            array![0., 0., 0.5, 0.5],
+        ];
+        for (node, value) in nodes.iter().zip(nodal_values.iter()) {
+            assert!((level_set.value(node, &sim) - value).abs() < 1e-15);
+        }
+    }
+
+    #[test]
+    fn curved_ansatz_level_set() {
+        use crate::integration_2d::domain::{Simplex2D, SimplexIntegrator};
+
+        let sim = Simplex2D::new_from_points(&array![0., 0.], &array![2., 0.], &array![0., 1.]);
+        let level_set = AnsatzLevelSet::new(array![0.5, -0.4, 0.3, 0.2, -0.1, 0.1]);
+        let kink = level_set.clone();
+        let func = Box::new(move |xi1: f64, xi2: f64, xi3: f64, simplex: &Simplex2D| {
+            kink.value(&array![xi1, xi2, xi3], simplex).abs()
+        });
+        let integrate = |order| {
+            CutIntegrator::new(
+                DunavantIntegrator::new(5).unwrap(),
+                level_set.clone(),
+                order,
+            )
+            .unwrap()
+            .integrate_simplex(&func, &sim, &mut IntegratorDummy::get())
+            .unwrap()
+            .get()
+        };
+        let reference = integrate(64);
+        let cut = integrate(16);
+        // Die Schnittkurve wird nur stückweise linear angenähert, der Fehler bleibt aber klein
+        let uncut = EdgeSubdivisionIntegrator::new(DunavantIntegrator::new(5).unwrap(), 16)
+            .unwrap()
+            .integrate_simplex(&func, &sim, &mut IntegratorDummy::get())
+            .unwrap()
+            .get();
+        assert!((cut - reference).abs() < 1e-6, "{} {}", cut, reference);
+        assert!(10. * (cut - reference).abs() < (uncut - reference).abs());
+    }
+}
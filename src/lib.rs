@@ -0,0 +1,36 @@
+//! Integration of non-smooth functions over simplices (triangles and tetrahedra) by subdivision.
+//!
+//! The simplex, function, result and integrator traits are generic over the dimension and live in
+//! [`simplex`]. The integrators are split by dimension into [`integration_2d`] and [`integration_3d`].
+//! [`refinement`] holds the error criteria and the global (priority queue) mode of the hierarchic
+//! integrators, [`bisection`] the adaptive longest-edge bisection of either dimension.
+//! [`level_set`] cuts the simplices along the kink of an integrand, e.g. the zero level set of the
+//! phase field ansatz function.
+//! [`embedded`] has quadrature rules which estimate their own error with an embedded rule.
+//! Their refinement trees can be stored in a versioned binary or JSON format, see e.g.
+//! [`Hierarchic3DIntegratorData::write_binary()`](integration_3d::integrators::Hierarchic3DIntegratorData::write_binary).
+//! [`mesh`] assembles the integrals over whole triangulations into global matrices and vectors.
+//! [`problems`] contains the phase field integrands, [`common`] shared helpers.
+//! [`autodiff`] integrates the residuals and tangents of energy densities written over dual numbers.
+//! Invalid input is reported as [`error::IntegrationError`] instead of a panic.
+//! The most used items are collected in the [`prelude`].
+//!
+//! With the cargo feature `parallel` the leafs of the hierarchic integrators, the sub-simplices of
+//! the edge subdivision, the elements of a [`mesh::Mesh`] and the rows of the phase field
+//! matrices are integrated with rayon. The results are summed up in a fixed order, so they are
+//! bit-identical to the serial path.
+
+pub mod autodiff;
+pub mod bisection;
+pub mod common;
+pub mod embedded;
+pub mod error;
+pub mod integration_2d;
+pub mod integration_3d;
+pub mod level_set;
+pub mod mesh;
+pub mod prelude;
+pub mod problems;
+pub mod refinement;
+pub mod simplex;
+mod tree_format;
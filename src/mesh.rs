@@ -0,0 +1,431 @@
+use ndarray::{Array1, Array2, Axis};
+use sprs::{CsMat, TriMat};
+
+use crate::common::{ordered_map, MaybeSend, MaybeSync};
+use crate::error::{IntegrationError, Result};
+use crate::simplex::{ResultTypeWrapper, Simplex, SimplexFunction, SimplexIntegrator};
+
+/// A triangulation of dimension `D`, given by the node coordinates and the connectivity of the
+/// elements.
+/// The nodes are stored as the columns of a `D x n` matrix.
+/// The first `D + 1` nodes of an element are its vertices, further nodes (e.g. the edge nodes of
+/// the 6-node triangle or the 10-node tetrahedron) only take part in the assembly.
+pub struct Mesh<const D: usize> {
+    nodes: Array2<f64>,
+    elements: Vec<Vec<usize>>,
+}
+
+/// A mesh of triangles
+pub type Mesh2D = Mesh<2>;
+/// A mesh of tetrahedra
+pub type Mesh3D = Mesh<3>;
+
+impl<const D: usize> Mesh<D> {
+    /// The nodes need `D` coordinates and every element at least `D + 1` existing nodes.
+    pub fn new(nodes: Array2<f64>, elements: Vec<Vec<usize>>) -> Result<Self> {
+        if nodes.nrows() != D {
+            return Err(IntegrationError::DimensionMismatch {
+                expected: [D, nodes.ncols()],
+                found: [nodes.nrows(), nodes.ncols()],
+            });
+        }
+        for (number, element) in elements.iter().enumerate() {
+            if element.len() < D + 1 {
+                return Err(IntegrationError::InvalidElement {
+                    element: number,
+                    message: format!(
+                        "{} nodes are given, at least {} are needed",
+                        element.len(),
+                        D + 1
+                    ),
+                });
+            }
+            if let Some(node) = element.iter().find(|&&node| node >= nodes.ncols()) {
+                return Err(IntegrationError::InvalidElement {
+                    element: number,
+                    message: format!(
+                        "the node {} does not exist, there are only {} nodes",
+                        node,
+                        nodes.ncols()
+                    ),
+                });
+            }
+        }
+        Ok(Self { nodes, elements })
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.ncols()
+    }
+
+    pub fn num_elements(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn get_nodes(&self) -> &Array2<f64> {
+        &self.nodes
+    }
+
+    pub fn get_element(&self, element: usize) -> &[usize] {
+        &self.elements[element]
+    }
+
+    /// The simplex spanned by the first `D + 1` nodes of the element.
+    pub fn get_simplex(&self, element: usize) -> Simplex<D> {
+        Simplex::new_from_array(self.nodes.select(Axis(1), &self.elements[element][..D + 1]))
+            .expect("the shape is checked in Mesh::new()")
+    }
+
+    /// The entries of the global vector `values` at the nodes of the element, in local order.
+    /// `values` needs one entry per node of the mesh.
+    pub fn gather(&self, element: usize, values: &Array1<f64>) -> Result<Array1<f64>> {
+        if values.len() != self.num_nodes() {
+            return Err(IntegrationError::DimensionMismatch {
+                expected: [self.num_nodes(), 1],
+                found: [values.len(), 1],
+            });
+        }
+        Ok(self.elements[element]
+            .iter()
+            .map(|&node| values[node])
+            .collect())
+    }
+
+    /// One cache per element, created by `init`.
+    /// The caches are meant to be kept by the caller and handed to every assembly, e.g. to
+    /// reuse the refinement trees of the hierarchic integrators over several Newton iterations.
+    pub fn new_caches<C, F: Fn() -> C>(&self, init: F) -> Vec<C> {
+        (0..self.num_elements()).map(|_| init()).collect()
+    }
+
+    /// Integrates the function returned by `element_function` over every element.
+    /// `element_function` receives the number of the element and its connectivity.
+    /// The first failed element in the order of the mesh is returned as the error.
+    /// There must be exactly one cache per element.
+    pub fn integrate_elements<I, C, T, F>(
+        &self,
+        integrator: &I,
+        caches: &mut [C],
+        element_function: F,
+    ) -> Result<Vec<T::Return>>
+    where
+        I: SimplexIntegrator<D, C>,
+        C: MaybeSend,
+        T: SimplexFunction<D>,
+        F: Fn(usize, &[usize]) -> T + MaybeSync + MaybeSend,
+    {
+        if caches.len() != self.num_elements() {
+            return Err(IntegrationError::DimensionMismatch {
+                expected: [self.num_elements(), 1],
+                found: [caches.len(), 1],
+            });
+        }
+        // Die Elemente sind unabhängig voneinander, jedes hat seinen eigenen Cache
+        ordered_map(
+            caches.iter_mut().enumerate().collect(),
+            |(number, cache)| {
+                let func = Box::new(element_function(number, &self.elements[number]));
+                integrator.integrate_simplex(&func, &self.get_simplex(number), cache)
+            },
+        )
+        .into_iter()
+        .collect()
+    }
+
+    /// Integrates the element matrices and scatters them into a global sparse matrix.
+    /// The element matrices are indexed by the local nodes of the element.
+    pub fn assemble_matrix<I, C, T, F>(
+        &self,
+        integrator: &I,
+        caches: &mut [C],
+        element_function: F,
+    ) -> Result<CsMat<f64>>
+    where
+        I: SimplexIntegrator<D, C>,
+        C: MaybeSend,
+        T: SimplexFunction<D, Return = ResultTypeWrapper<Array2<f64>>>,
+        F: Fn(usize, &[usize]) -> T + MaybeSync + MaybeSend,
+    {
+        let element_results = self.integrate_elements(integrator, caches, element_function)?;
+
+        let n = self.num_nodes();
+        let mut triplets = TriMat::new((n, n));
+        for (number, result) in element_results.iter().enumerate() {
+            let element = &self.elements[number];
+            let mat = result.get_borrow();
+            if mat.shape() != [element.len(), element.len()] {
+                return Err(IntegrationError::DimensionMismatch {
+                    expected: [element.len(), element.len()],
+                    found: [mat.nrows(), mat.ncols()],
+                });
+            }
+            for (i, &row) in element.iter().enumerate() {
+                for (j, &column) in element.iter().enumerate() {
+                    triplets.add_triplet(row, column, mat[[i, j]]);
+                }
+            }
+        }
+        // Doppelte Einträge werden beim Umwandeln aufsummiert
+        Ok(triplets.to_csr())
+    }
+
+    /// Integrates the element vectors and scatters them into a global vector.
+    pub fn assemble_vector<I, C, T, F>(
+        &self,
+        integrator: &I,
+        caches: &mut [C],
+        element_function: F,
+    ) -> Result<Array1<f64>>
+    where
+        I: SimplexIntegrator<D, C>,
+        C: MaybeSend,
+        T: SimplexFunction<D, Return = ResultTypeWrapper<Array1<f64>>>,
+        F: Fn(usize, &[usize]) -> T + MaybeSync + MaybeSend,
+    {
+        let element_results = self.integrate_elements(integrator, caches, element_function)?;
+
+        let mut global = Array1::zeros([self.num_nodes()]);
+        for (number, result) in element_results.iter().enumerate() {
+            let element = &self.elements[number];
+            let vec = result.get_borrow();
+            if vec.len() != element.len() {
+                return Err(IntegrationError::DimensionMismatch {
+                    expected: [element.len(), 1],
+                    found: [vec.len(), 1],
+                });
+            }
+            for (i, &row) in element.iter().enumerate() {
+                global[row] += vec[i];
+            }
+        }
+        Ok(global)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{array, Array1, Array2};
+
+    use crate::common::IntegratorDummy;
+    use crate::error::IntegrationError;
+    use crate::integration_2d::domain::{ResultTypeWrapper, Simplex2D, Simplex2DFunction};
+    use crate::integration_2d::integrators::{
+        DunavantIntegrator, Hierarchic2DIntegrator, Hierarchic2DIntegratorData,
+    };
+    use crate::integration_3d::domain::{Simplex3D, Simplex3DFunction};
+    use crate::integration_3d::integrators::TetrahedralIntegrator;
+
+    use super::{Mesh2D, Mesh3D};
+
+    /// The unit square, split along the diagonal from (0,0) to (1,1)
+    fn unit_square() -> Mesh2D {
+        Mesh2D::new(
+            array![[0., 1., 1., 0.], [0., 0., 1., 1.]],
+            vec![vec![0, 1, 2], vec![0, 2, 3]],
+        )
+        .unwrap()
+    }
+
+    /// The linear mass matrix xi_i * xi_j
+    struct MassMatrix;
+
+    impl Simplex2DFunction for MassMatrix {
+        type Return = ResultTypeWrapper<Array2<f64>>;
+        fn function(&self, xi1: f64, xi2: f64, xi3: f64, _simplex: &Simplex2D) -> Self::Return {
+            let xi = array![xi1, xi2, xi3];
+            let mut mat = Array2::zeros([3, 3]);
+            for i in 0..3 {
+                for j in 0..3 {
+                    mat[[i, j]] = xi[i] * xi[j];
+                }
+            }
+            ResultTypeWrapper::new(mat)
+        }
+    }
+
+    /// The linear load vector times the interpolated nodal values.
+    struct LoadVector(Array1<f64>);
+
+    impl Simplex2DFunction for LoadVector {
+        type Return = ResultTypeWrapper<Array1<f64>>;
+        fn function(&self, xi1: f64, xi2: f64, xi3: f64, _simplex: &Simplex2D) -> Self::Return {
+            let xi = array![xi1, xi2, xi3];
+            ResultTypeWrapper::new(&xi * xi.dot(&self.0))
+        }
+    }
+
+    /// The ramp max(x - 1/2, 0), which needs refinement along x = 1/2
+    struct Ramp;
+
+    impl Simplex2DFunction for Ramp {
+        type Return = ResultTypeWrapper<f64>;
+        fn function(&self, xi1: f64, xi2: f64, xi3: f64, simplex: &Simplex2D) -> Self::Return {
+            let x = simplex.get_points().row(0).dot(&array![xi1, xi2, xi3]);
+            ResultTypeWrapper::new((x - 0.5).max(0.))
+        }
+    }
+
+    struct Constant3D;
+
+    impl Simplex3DFunction for Constant3D {
+        type Return = ResultTypeWrapper<f64>;
+        fn function(
+            &self,
+            _xi1: f64,
+            _xi2: f64,
+            _xi3: f64,
+            _xi4: f64,
+            _simplex: &Simplex3D,
+        ) -> Self::Return {
+            ResultTypeWrapper::new(1.)
+        }
+    }
+
+    #[test]
+    fn mass_matrix_assembly() {
+        let mesh = unit_square();
+        let inte = DunavantIntegrator::new(2).unwrap();
+        let mut caches = mesh.new_caches(IntegratorDummy::get);
+
+        let mat = mesh
+            .assemble_matrix(&inte, &mut caches, |_, _| MassMatrix)
+            .unwrap();
+
+        // Beide Dreiecke haben die Fläche 1/2, die Diagonale ist A/6, der Rest A/12
+        let expected = array![
+            [2. / 12., 1. / 24., 2. / 24., 1. / 24.],
+            [1. / 24., 1. / 12., 1. / 24., 0.],
+            [2. / 24., 1. / 24., 2. / 12., 1. / 24.],
+            [1. / 24., 0., 1. / 24., 1. / 12.]
+        ];
+        assert_eq!(mat.nnz(), 14);
+        for i in 0..4 {
+            for j in 0..4 {
+                let actual = mat.get(i, j).copied().unwrap_or(0.);
+                assert!(
+                    (actual - expected[[i, j]]).abs() < 1e-14,
+                    "Entry ({},{}), Expected: {}, Actual: {}",
+                    i,
+                    j,
+                    expected[[i, j]],
+                    actual
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn load_vector_assembly() {
+        let mesh = unit_square();
+        let inte = DunavantIntegrator::new(2).unwrap();
+        let mut caches = mesh.new_caches(IntegratorDummy::get);
+        let nodal_values = array![1., 1., 1., 1.];
+
+        let vec = mesh
+            .assemble_vector(&inte, &mut caches, |number, _| {
+                LoadVector(mesh.gather(number, &nodal_values).unwrap())
+            })
+            .unwrap();
+
+        let expected = [1. / 3., 1. / 6., 1. / 3., 1. / 6.];
+        for i in 0..4 {
+            assert!((vec[i] - expected[i]).abs() < 1e-14);
+        }
+    }
+
+    #[test]
+    fn wrong_lengths_are_errors() {
+        let mesh = unit_square();
+        assert!(matches!(
+            mesh.gather(0, &array![1., 1., 1.]),
+            Err(IntegrationError::DimensionMismatch {
+                expected: [4, 1],
+                found: [3, 1]
+            })
+        ));
+
+        let inte = DunavantIntegrator::new(2).unwrap();
+        let mut caches = vec![IntegratorDummy::get()];
+        let result = mesh.integrate_elements(&inte, &mut caches, |_, _| Ramp);
+        assert!(matches!(
+            result,
+            Err(IntegrationError::DimensionMismatch {
+                expected: [2, 1],
+                found: [1, 1]
+            })
+        ));
+    }
+
+    #[test]
+    fn hierarchic_caches_are_kept() {
+        let mesh = unit_square();
+        let inte = Hierarchic2DIntegrator::new(DunavantIntegrator::new(2).unwrap(), false, 1e-6);
+        let mut caches = mesh.new_caches(Hierarchic2DIntegratorData::new_cache);
+
+        let first: f64 = mesh
+            .integrate_elements(&inte, &mut caches, |_, _| Ramp)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.get())
+            .sum();
+        let sizes: Vec<usize> = caches.iter().map(|c| c.tree_size()).collect();
+        assert!(sizes.iter().all(|&size| size > 1));
+
+        let second: f64 = mesh
+            .integrate_elements(&inte, &mut caches, |_, _| Ramp)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.get())
+            .sum();
+        let sizes_after: Vec<usize> = caches.iter().map(|c| c.tree_size()).collect();
+
+        assert_eq!(sizes, sizes_after);
+        assert!((first - 0.125).abs() < 1e-5);
+        assert!((first - second).abs() < 1e-14);
+    }
+
+    #[test]
+    fn tetrahedral_mesh_volume() {
+        // Zwei Tetraeder mit gemeinsamer Fläche
+        let mesh = Mesh3D::new(
+            array![
+                [0., 1., 0., 0., 1.],
+                [0., 0., 1., 0., 1.],
+                [0., 0., 0., 1., 1.]
+            ],
+            vec![vec![0, 1, 2, 3], vec![4, 2, 1, 3]],
+        )
+        .unwrap();
+        let inte = TetrahedralIntegrator::new(1).unwrap();
+        let mut caches = mesh.new_caches(IntegratorDummy::get);
+
+        let volumes = mesh
+            .integrate_elements(&inte, &mut caches, |_, _| Constant3D)
+            .unwrap();
+
+        let total: f64 = volumes.into_iter().map(|r| r.get()).sum();
+        assert!((total - (1. / 6. + 1. / 3.)).abs() < 1e-14);
+    }
+
+    #[test]
+    fn invalid_mesh_and_degenerate_element() {
+        use crate::error::IntegrationError;
+
+        let nodes = array![[0., 1., 1., 0.], [0., 0., 1., 1.]];
+        assert!(matches!(
+            Mesh2D::new(nodes.clone(), vec![vec![0, 1, 4]]),
+            Err(IntegrationError::InvalidElement { element: 0, .. })
+        ));
+        assert!(Mesh2D::new(nodes.clone(), vec![vec![0, 1, 2], vec![0, 2]]).is_err());
+        assert!(Mesh3D::new(nodes.clone(), vec![vec![0, 1, 2, 3]]).is_err());
+
+        // Das zweite Element ist entartet, der Fehler wird zurückgegeben statt abzubrechen
+        let mesh = Mesh2D::new(nodes, vec![vec![0, 1, 2], vec![0, 2, 0]]).unwrap();
+        let inte = DunavantIntegrator::new(2).unwrap();
+        let mut caches = mesh.new_caches(IntegratorDummy::get);
+        assert!(matches!(
+            mesh.assemble_matrix(&inte, &mut caches, |_, _| MassMatrix),
+            Err(IntegrationError::DegenerateSimplex { .. })
+        ));
+    }
+}
@@ -0,0 +1,65 @@
+//! Re-exports of the simplices, traits and integrators of both dimensions.
+//!
+//! ```
+//! use simplex_subdivision_integration::prelude::*;
+//! ```
+//!
+//! The integrators which exist in both dimensions under the same name are renamed with the dimension.
+//! The former per-dimension names of the traits and the result wrapper are kept as aliases:
+//!
+//! ```
+//! use ndarray::array;
+//! use simplex_subdivision_integration::prelude::*;
+//!
+//! fn integrate<I: Simplex2DIntegrator<IntegratorDummy>>(inte: &I) -> ResultTypeWrapper2D<f64> {
+//!     let sim = Simplex2D::new_from_points(&array![0., 0.], &array![1., 0.], &array![0., 1.]);
+//!     let func = Box::new(|_: f64, _: f64, _: f64, _: &Simplex2D| 1.);
+//!     inte.integrate_simplex(&func, &sim, &mut IntegratorDummy::get()).unwrap()
+//! }
+//!
+//! fn is_result_type<T: Simplex3DResultType>(_: &T) {}
+//!
+//! let result = integrate(&DunavantIntegrator::new(1).unwrap());
+//! is_result_type(&result);
+//! assert!((result.get() - 0.5).abs() < 1e-14);
+//! ```
+
+pub use num_complex::Complex64;
+
+pub use crate::autodiff::{DualFunction, DualNum, Energy, Residual, Tangent};
+pub use crate::bisection::{BisectionIntegrator, EmbeddedBisectionIntegrator};
+pub use crate::common::IntegratorDummy;
+pub use crate::embedded::{EmbeddedIntegrator, EmbeddedResult, GrundmannMoellerIntegrator};
+pub use crate::error::IntegrationError;
+pub use crate::level_set::{AnsatzLevelSet, CutIntegrator, LevelSet};
+pub use crate::mesh::{Mesh, Mesh2D, Mesh3D};
+pub use crate::refinement::{
+    AbsoluteCriterion, GlobalRefinement, MaxNormCriterion, MixedCriterion, RefinementCriterion,
+    RelativeCriterion,
+};
+pub use crate::simplex::{
+    CompositeCache, IntegratorCache, OrientationPolicy, PhysicalFunction, ResultTypeWrapper,
+    Simplex, SimplexFunction, SimplexIntegrator, SimplexResultType,
+};
+
+pub use crate::integration_2d::domain::{
+    PhysicalFunction2D, ResultTypeWrapper2D, Simplex2D, Simplex2DFunction, Simplex2DIntegrator,
+    Simplex2DResultType,
+};
+pub use crate::integration_2d::functions::Function2DHistory;
+pub use crate::integration_2d::integrators::{
+    DunavantIntegrator, EdgeSubdivisionIntegrator as EdgeSubdivision2DIntegrator,
+    Hierarchic2DIntegrator, Hierarchic2DIntegratorData, Hybrid2DIntegrator, Hybrid2DIntegratorData,
+    LynessIntegrator, Quadrilateral2DIntegrator,
+};
+
+pub use crate::integration_3d::domain::{
+    PhysicalFunction3D, ResultTypeWrapper3D, Simplex3D, Simplex3DFunction, Simplex3DIntegrator,
+    Simplex3DResultType,
+};
+pub use crate::integration_3d::functions::Function3DHistory;
+pub use crate::integration_3d::integrators::{
+    EdgeSubdivisionIntegrator as EdgeSubdivision3DIntegrator, Hierarchic3DIntegrator,
+    Hierarchic3DIntegratorData, Hybrid3DIntegrator, Hybrid3DIntegratorData,
+    Quadrilateral3DIntegrator, TetrahedralIntegrator,
+};
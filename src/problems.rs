@@ -1,121 +1,67 @@
-use crate::{domain::Simplex2DFunction, integration_2d::domain::ResultTypeWrapper};
+use crate::integration_2d::domain::{ResultTypeWrapper, Simplex2D, Simplex2DFunction};
 
 use self::{problem_definition::phase_field::phase_field_func, shape_func::approx_func};
 
-const TOLERANCE: f64 = 1e-10;
-
 pub mod shape_func {
-    use super::TOLERANCE;
+    use crate::error::{check_barycentric, Result};
 
     /// Shape Function for the 1 Node of a 6-Node Triangular Element
     /// See theory.tex for clarificatiom
-    pub fn BarN1(xi1: f64, xi2: f64, xi3: f64) -> f64 {
-        if (xi1 + xi2 + xi3 - 1.0).abs() >= TOLERANCE {
-            panic!(
-                "Illegal Barycentric Coordinates, {},{},{} = {} > 1.0!",
-                xi1,
-                xi2,
-                xi3,
-                xi1 + xi2 + xi3
-            );
-        };
-        2.0 * xi1 * (xi1 - 0.5)
+    pub fn BarN1(xi1: f64, xi2: f64, xi3: f64) -> Result<f64> {
+        check_barycentric(&[xi1, xi2, xi3])?;
+        Ok(2.0 * xi1 * (xi1 - 0.5))
     }
     /// Shape Function for the 2 Node of a 6-Node Triangular Element
     /// See theory.tex for clarificatiom
-    pub fn BarN2(xi1: f64, xi2: f64, xi3: f64) -> f64 {
-        if (xi1 + xi2 + xi3 - 1.0).abs() >= TOLERANCE {
-            panic!(
-                "Illegal Barycentric Coordinates, {},{},{} = {} > 1.0!",
-                xi1,
-                xi2,
-                xi3,
-                xi1 + xi2 + xi3
-            );
-        };
-        2.0 * xi2 * (xi2 - 0.5)
+    pub fn BarN2(xi1: f64, xi2: f64, xi3: f64) -> Result<f64> {
+        check_barycentric(&[xi1, xi2, xi3])?;
+        Ok(2.0 * xi2 * (xi2 - 0.5))
     }
     /// Shape Function for the 3 Node of a 6-Node Triangular Element
     /// See theory.tex for clarificatiom
-    pub fn BarN3(xi1: f64, xi2: f64, xi3: f64) -> f64 {
-        if (xi1 + xi2 + xi3 - 1.0).abs() >= TOLERANCE {
-            panic!(
-                "Illegal Barycentric Coordinates, {},{},{} = {} > 1.0!",
-                xi1,
-                xi2,
-                xi3,
-                xi1 + xi2 + xi3
-            );
-        };
-        2.0 * xi3 * (xi3 - 0.5)
+    pub fn BarN3(xi1: f64, xi2: f64, xi3: f64) -> Result<f64> {
+        check_barycentric(&[xi1, xi2, xi3])?;
+        Ok(2.0 * xi3 * (xi3 - 0.5))
     }
     /// Shape Function for the 4 Node of a 6-Node Triangular Element
     /// See theory.tex for clarificatiom
-    pub fn BarN4(xi1: f64, xi2: f64, xi3: f64) -> f64 {
-        if (xi1 + xi2 + xi3 - 1.0).abs() >= TOLERANCE {
-            panic!(
-                "Illegal Barycentric Coordinates, {},{},{} = {} > 1.0!",
-                xi1,
-                xi2,
-                xi3,
-                xi1 + xi2 + xi3
-            );
-        };
-        4.0 * xi1 * xi3
+    pub fn BarN4(xi1: f64, xi2: f64, xi3: f64) -> Result<f64> {
+        check_barycentric(&[xi1, xi2, xi3])?;
+        Ok(4.0 * xi1 * xi3)
     }
     /// Shape Function for the 5 Node of a 6-Node Triangular Element
     /// See theory.tex for clarificatiom
-    pub fn BarN5(xi1: f64, xi2: f64, xi3: f64) -> f64 {
-        if (xi1 + xi2 + xi3 - 1.0).abs() >= TOLERANCE {
-            panic!(
-                "Illegal Barycentric Coordinates, {},{},{} = {} > 1.0!",
-                xi1,
-                xi2,
-                xi3,
-                xi1 + xi2 + xi3
-            );
-        };
-        4.0 * xi1 * xi2
+    pub fn BarN5(xi1: f64, xi2: f64, xi3: f64) -> Result<f64> {
+        check_barycentric(&[xi1, xi2, xi3])?;
+        Ok(4.0 * xi1 * xi2)
     }
     /// Shape Function for the 6 Node of a 6-Node Triangular Element
     /// See theory.tex for clarificatiom
-    pub fn BarN6(xi1: f64, xi2: f64, xi3: f64) -> f64 {
-        if (xi1 + xi2 + xi3 - 1.0).abs() >= TOLERANCE {
-            panic!(
-                "Illegal Barycentric Coordinates, {},{},{} = {} > 1.0!",
-                xi1,
-                xi2,
-                xi3,
-                xi1 + xi2 + xi3
-            );
-        };
-        4.0 * xi2 * xi3
+    pub fn BarN6(xi1: f64, xi2: f64, xi3: f64) -> Result<f64> {
+        check_barycentric(&[xi1, xi2, xi3])?;
+        Ok(4.0 * xi2 * xi3)
     }
 
-    pub fn approx_func(weights: [f64; 6], xi1: f64, xi2: f64, xi3: f64) -> f64 {
-        weights[0] * BarN1(xi1, xi2, xi3)
-            + weights[1] * BarN2(xi1, xi2, xi3)
-            + weights[2] * BarN3(xi1, xi2, xi3)
-            + weights[3] * BarN4(xi1, xi2, xi3)
-            + weights[4] * BarN5(xi1, xi2, xi3)
-            + weights[5] * BarN6(xi1, xi2, xi3)
+    pub fn approx_func(weights: [f64; 6], xi1: f64, xi2: f64, xi3: f64) -> Result<f64> {
+        Ok(weights[0] * BarN1(xi1, xi2, xi3)?
+            + weights[1] * BarN2(xi1, xi2, xi3)?
+            + weights[2] * BarN3(xi1, xi2, xi3)?
+            + weights[3] * BarN4(xi1, xi2, xi3)?
+            + weights[4] * BarN5(xi1, xi2, xi3)?
+            + weights[5] * BarN6(xi1, xi2, xi3)?)
     }
 }
 
 pub mod problem_definition {
-    use ndarray::{array, Array1};
-
-    use crate::integration_3d::Simplex3DFunction;
-
-    use self::{
-        phase_field::{phase_field_func, varsigma_func_diff1, varsigma_func_diff2},
-        shape_func_3d::{ansatz_function, dyadic_product_component},
-    };
-
+    /// [`varsigma_func()`](phase_field::varsigma_func) and
+    /// [`phase_field_func()`](phase_field::phase_field_func) are generic over
+    /// [`DualNum`](crate::autodiff::DualNum), so their derivatives can also be computed
+    /// automatically, see [`crate::autodiff`].
     pub mod phase_field {
+        use crate::autodiff::DualNum;
 
         // exponent of phase field
-        pub fn varsigma_func(f_base: f64, kreg: f64) -> f64 {
+        pub fn varsigma_func<T: DualNum<f64>>(f_base: T, kreg: f64) -> T {
             f_base / (f_base.powi(2) + kreg).powf(0.25)
         }
 
@@ -136,16 +82,25 @@ pub mod problem_definition {
                 / (4. * (f_base.powi(2) + kreg).powf(1.25))
         }
 
-        pub fn phase_field_func(f_base: f64, kreg: f64, l: f64) -> f64 {
+        pub fn phase_field_func<T: DualNum<f64>>(f_base: T, kreg: f64, l: f64) -> T {
             (-(varsigma_func(f_base, kreg)).abs() * 1.0 / l).exp()
         }
 
-        //pub fn phase_field_func_diff2(f_base: f64, kreg: f64, l: f64, row_index: usize, column_index: usize) -> f64 {
-        //    phase_field_func(f_base, kreg, l) * ( varsigma_func_diff1(f_base, kreg).powi(2) - varsigma_func_diff2(f_base, kreg) )
-        //}
+        // d/df exp(-|varsigma(f)| / l)
+        pub fn phase_field_func_diff1(f_base: f64, kreg: f64, l: f64) -> f64 {
+            -varsigma_func(f_base, kreg).signum() * varsigma_func_diff1(f_base, kreg) / l
+                * phase_field_func(f_base, kreg, l)
+        }
+
+        // d²/df² exp(-|varsigma(f)| / l), die Ableitung von phase_field_func_diff1
+        pub fn phase_field_func_diff2(f_base: f64, kreg: f64, l: f64) -> f64 {
+            phase_field_func(f_base, kreg, l)
+                * (varsigma_func_diff1(f_base, kreg).powi(2) / l.powi(2)
+                    - varsigma_func(f_base, kreg).signum() * varsigma_func_diff2(f_base, kreg) / l)
+        }
     }
 
-    mod shape_func_2d {
+    pub(crate) mod shape_func_2d {
         use ndarray::Array1;
 
         // Baryzentrische Lagrange Funktion [Index 0 - 2]
@@ -164,20 +119,20 @@ pub mod problem_definition {
                 * (2. * lagrange_1_function(barycentric_coordinates, index) - 1.);
         }
 
+        /// The vertices of the edges of the nodes 4 - 6, in the order of
+        /// [`BarN4()`](crate::problems::shape_func::BarN4) to
+        /// [`BarN6()`](crate::problems::shape_func::BarN6).
+        pub(crate) const EDGES: [(usize, usize); 3] = [(0, 2), (0, 1), (1, 2)];
+
         // Knoten 4 - 6 [Index 3 - 5]
         fn lagrange_2_order_edge(barycentric_coordinates: &Array1<f64>, index: usize) -> f64 {
-            if index < 3 {
+            if !(3..6).contains(&index) {
                 panic!("Illegal Index given");
             }
-            if index < 6 {
-                // 4,5,6
-                let index = index - 3;
-                return 4.
-                    * lagrange_1_function(barycentric_coordinates, index)
-                    * lagrange_1_function(barycentric_coordinates, 2 - index);
-            } else {
-                panic!("Illegal Index given")
-            }
+            let (first, second) = EDGES[index - 3];
+            return 4.
+                * lagrange_1_function(barycentric_coordinates, first)
+                * lagrange_1_function(barycentric_coordinates, second);
         }
 
         pub fn shape_function_from_index(
@@ -221,7 +176,7 @@ pub mod problem_definition {
         }
     }
 
-    mod shape_func_3d {
+    pub(crate) mod shape_func_3d {
         use ndarray::Array1;
 
         fn lagrange_1_function(barycentric_coordinates: &Array1<f64>, index: usize) -> f64 {
@@ -240,24 +195,20 @@ pub mod problem_definition {
                 * (2. * lagrange_1_function(barycentric_coordinates, index) - 1.);
         }
 
+        /// The vertices of the edges of the nodes 5 - 10, the first three on the face of the
+        /// triangle in the order of [`super::shape_func_2d::EDGES`], then the edges to vertex 4.
+        pub(crate) const EDGES: [(usize, usize); 6] =
+            [(0, 2), (0, 1), (1, 2), (0, 3), (1, 3), (2, 3)];
+
         // Knoten 5 - 10 [Index 4 - 9]
         fn lagrange_2_order_edge(barycentric_coordinates: &Array1<f64>, index: usize) -> f64 {
-            if index < 4 {
+            if !(4..10).contains(&index) {
                 panic!("Illegal Index given");
             }
-            if index < 7 {
-                // 4,5,6
-                let index = index - 4;
-                return 4.
-                    * lagrange_1_function(barycentric_coordinates, index)
-                    * lagrange_1_function(barycentric_coordinates, 3 - index);
-            } else {
-                // 7,8,9
-                let index = index - 7;
-                return 4.
-                    * lagrange_1_function(barycentric_coordinates, index)
-                    * lagrange_1_function(barycentric_coordinates, 3);
-            }
+            let (first, second) = EDGES[index - 4];
+            return 4.
+                * lagrange_1_function(barycentric_coordinates, first)
+                * lagrange_1_function(barycentric_coordinates, second);
         }
 
         pub fn shape_function_from_index(
@@ -306,11 +257,16 @@ pub mod problem_definition {
         use ndarray::Array1;
         use ndarray::Array2;
 
+        use crate::autodiff::{DualFunction, DualNum};
+        use crate::common::ordered_join;
         use crate::integration_2d::domain::ResultTypeWrapper;
         use crate::integration_2d::domain::Simplex2DFunction;
+        use crate::integration_2d::Simplex2D;
 
-        use super::phase_field::{phase_field_func, varsigma_func_diff1, varsigma_func_diff2};
-        use super::shape_func_2d::{ansatz_function, dyadic_product_component};
+        use super::phase_field::{self, phase_field_func, phase_field_func_diff1};
+        use super::shape_func_2d::{
+            all_shape_functions, ansatz_function, dyadic_product_component,
+        };
 
         // jetzt die Einzelintegranden
         pub fn phase_field_func_diff2(
@@ -322,8 +278,7 @@ pub mod problem_definition {
             barycentric_coordinates: &Array1<f64>,
         ) -> f64 {
             let f_base = ansatz_function(nodal_values, barycentric_coordinates);
-            phase_field_func(f_base, kreg, l)
-                * (varsigma_func_diff1(f_base, kreg).powi(2) - varsigma_func_diff2(f_base, kreg))
+            phase_field::phase_field_func_diff2(f_base, kreg, l)
                 * dyadic_product_component(barycentric_coordinates, row_index, column_index)
         }
 
@@ -359,19 +314,22 @@ pub mod problem_definition {
             ) -> Self::Return {
                 let barycentric = array![xi1, xi2, xi3];
 
-                let mut mat = Array2::zeros([6, 6]);
-                for i in 0..6 {
-                    for j in 0..6 {
-                        mat[[i, j]] = phase_field_func_diff2(
-                            &self.nodal_values,
-                            self.kreg,
-                            self.l,
-                            i,
-                            j,
-                            &barycentric,
-                        );
-                    }
-                }
+                // Die Zeilen sind unabhängig voneinander
+                let rows = ordered_join(6, |i| {
+                    (0..6)
+                        .map(|j| {
+                            phase_field_func_diff2(
+                                &self.nodal_values,
+                                self.kreg,
+                                self.l,
+                                i,
+                                j,
+                                &barycentric,
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                });
+                let mat = Array2::from_shape_vec([6, 6], rows.concat()).unwrap();
 
                 return ResultTypeWrapper::new(mat);
             }
@@ -423,18 +381,122 @@ pub mod problem_definition {
                 ));
             }
         }
+
+        /// The residual vector `phi'(f) N_i` of the 6 nodal values, see
+        /// [`phase_field_func_diff1()`](super::phase_field::phase_field_func_diff1).
+        pub struct PhaseFieldResidual2D {
+            nodal_values: Array1<f64>,
+            kreg: f64,
+            l: f64,
+        }
+
+        impl PhaseFieldResidual2D {
+            pub fn new(nodal_values: Array1<f64>, kreg: f64, l: f64) -> Self {
+                Self {
+                    nodal_values,
+                    kreg,
+                    l,
+                }
+            }
+        }
+
+        impl Simplex2DFunction for PhaseFieldResidual2D {
+            type Return = ResultTypeWrapper<Array1<f64>>;
+
+            fn additive_neutral_element(&self) -> Self::Return {
+                ResultTypeWrapper::new(Array1::zeros([6]))
+            }
+
+            fn function(&self, xi1: f64, xi2: f64, xi3: f64, _simplex: &Simplex2D) -> Self::Return {
+                let shape_functions = all_shape_functions(&array![xi1, xi2, xi3]);
+                let f_base = self.nodal_values.dot(&shape_functions);
+                let factor = phase_field_func_diff1(f_base, self.kreg, self.l);
+                ResultTypeWrapper::new(factor * shape_functions)
+            }
+        }
+
+        /// The phase field `phi(f)` itself, the energy of the element.
+        pub struct PhaseFieldEnergy2D {
+            nodal_values: Array1<f64>,
+            kreg: f64,
+            l: f64,
+        }
+
+        impl PhaseFieldEnergy2D {
+            pub fn new(nodal_values: Array1<f64>, kreg: f64, l: f64) -> Self {
+                Self {
+                    nodal_values,
+                    kreg,
+                    l,
+                }
+            }
+        }
+
+        impl Simplex2DFunction for PhaseFieldEnergy2D {
+            type Return = ResultTypeWrapper<f64>;
+
+            fn function(&self, xi1: f64, xi2: f64, xi3: f64, _simplex: &Simplex2D) -> Self::Return {
+                let f_base = ansatz_function(&self.nodal_values, &array![xi1, xi2, xi3]);
+                ResultTypeWrapper::new(phase_field_func(f_base, self.kreg, self.l))
+            }
+        }
+
+        /// The phase field of the quadratic ansatz as an energy density of the 6 nodal values.
+        /// Its [`Tangent`](crate::autodiff::Tangent) is the exact Hessian, the same as
+        /// [`PhaseFieldFuncMatrix2D`].
+        pub struct PhaseFieldDensity2D {
+            nodal_values: [f64; 6],
+            kreg: f64,
+            l: f64,
+        }
+
+        impl PhaseFieldDensity2D {
+            pub fn new(nodal_values: [f64; 6], kreg: f64, l: f64) -> Self {
+                Self {
+                    nodal_values,
+                    kreg,
+                    l,
+                }
+            }
+        }
+
+        impl DualFunction<2, 6> for PhaseFieldDensity2D {
+            fn nodal_values(&self) -> [f64; 6] {
+                self.nodal_values
+            }
+
+            fn density<T: DualNum<f64>>(
+                &self,
+                nodal_values: &[T; 6],
+                xi: &Array1<f64>,
+                _simplex: &Simplex2D,
+            ) -> T {
+                let f_base: T = nodal_values
+                    .iter()
+                    .zip(all_shape_functions(xi).iter())
+                    .map(|(&value, &shape)| value * shape)
+                    .sum();
+                phase_field_func(f_base, self.kreg, self.l)
+            }
+        }
     }
 
     pub mod problem_3d_definition {
 
         use ndarray::array;
         use ndarray::Array1;
+        use ndarray::Array2;
 
+        use crate::autodiff::{DualFunction, DualNum};
+        use crate::common::ordered_join;
         use crate::integration_3d::ResultTypeWrapper;
+        use crate::integration_3d::Simplex3D;
         use crate::integration_3d::Simplex3DFunction;
 
-        use super::phase_field::{phase_field_func, varsigma_func_diff1, varsigma_func_diff2};
-        use super::shape_func_3d::{ansatz_function, dyadic_product_component};
+        use super::phase_field::{self, phase_field_func, phase_field_func_diff1};
+        use super::shape_func_3d::{
+            all_shape_functions, ansatz_function, dyadic_product_component,
+        };
 
         // jetzt die Einzelintegranden
         pub fn phase_field_func_diff2(
@@ -446,11 +508,69 @@ pub mod problem_definition {
             barycentric_coordinates: &Array1<f64>,
         ) -> f64 {
             let f_base = ansatz_function(nodal_values, barycentric_coordinates);
-            phase_field_func(f_base, kreg, l)
-                * (varsigma_func_diff1(f_base, kreg).powi(2) - varsigma_func_diff2(f_base, kreg))
+            phase_field::phase_field_func_diff2(f_base, kreg, l)
                 * dyadic_product_component(barycentric_coordinates, row_index, column_index)
         }
 
+        /// The whole 10 x 10 matrix of [`phase_field_func_diff2()`].
+        /// The ansatz and the phase field are evaluated once per point and only the upper triangle
+        /// of the symmetric matrix is computed.
+        pub struct PhaseFieldFuncMatrix3D {
+            nodal_values: Array1<f64>,
+            kreg: f64,
+            l: f64,
+        }
+
+        impl PhaseFieldFuncMatrix3D {
+            pub fn new(nodal_values: Array1<f64>, kreg: f64, l: f64) -> Self {
+                return Self {
+                    nodal_values,
+                    kreg,
+                    l,
+                };
+            }
+        }
+
+        impl Simplex3DFunction for PhaseFieldFuncMatrix3D {
+            type Return = ResultTypeWrapper<Array2<f64>>;
+
+            fn additive_neutral_element(&self) -> Self::Return {
+                ResultTypeWrapper::new(Array2::zeros([10, 10]))
+            }
+
+            fn function(
+                &self,
+                xi1: f64,
+                xi2: f64,
+                xi3: f64,
+                xi4: f64,
+                _simplex: &crate::integration_3d::Simplex3D,
+            ) -> Self::Return {
+                let barycentric = array![xi1, xi2, xi3, xi4];
+                let shape_functions = all_shape_functions(&barycentric);
+
+                let f_base = self.nodal_values.dot(&shape_functions);
+                let factor = phase_field::phase_field_func_diff2(f_base, self.kreg, self.l);
+
+                // Nur die obere Dreiecksmatrix, der Rest ist symmetrisch. Die Zeilen sind
+                // unabhängig voneinander.
+                let rows = ordered_join(10, |i| {
+                    (i..10)
+                        .map(|j| factor * shape_functions[i] * shape_functions[j])
+                        .collect::<Vec<_>>()
+                });
+                let mut mat = Array2::zeros([10, 10]);
+                for (i, row) in rows.into_iter().enumerate() {
+                    for (j, value) in (i..10).zip(row) {
+                        mat[[i, j]] = value;
+                        mat[[j, i]] = value;
+                    }
+                }
+
+                return ResultTypeWrapper::new(mat);
+            }
+        }
+
         pub struct PhaseFieldFuncDiff23D {
             nodal_values: Array1<f64>,
             kreg: f64,
@@ -498,6 +618,118 @@ pub mod problem_definition {
                 ));
             }
         }
+
+        /// The residual vector `phi'(f) N_i` of the 10 nodal values, see
+        /// [`phase_field_func_diff1()`](super::phase_field::phase_field_func_diff1).
+        pub struct PhaseFieldResidual3D {
+            nodal_values: Array1<f64>,
+            kreg: f64,
+            l: f64,
+        }
+
+        impl PhaseFieldResidual3D {
+            pub fn new(nodal_values: Array1<f64>, kreg: f64, l: f64) -> Self {
+                Self {
+                    nodal_values,
+                    kreg,
+                    l,
+                }
+            }
+        }
+
+        impl Simplex3DFunction for PhaseFieldResidual3D {
+            type Return = ResultTypeWrapper<Array1<f64>>;
+
+            fn additive_neutral_element(&self) -> Self::Return {
+                ResultTypeWrapper::new(Array1::zeros([10]))
+            }
+
+            fn function(
+                &self,
+                xi1: f64,
+                xi2: f64,
+                xi3: f64,
+                xi4: f64,
+                _simplex: &Simplex3D,
+            ) -> Self::Return {
+                let shape_functions = all_shape_functions(&array![xi1, xi2, xi3, xi4]);
+                let f_base = self.nodal_values.dot(&shape_functions);
+                let factor = phase_field_func_diff1(f_base, self.kreg, self.l);
+                ResultTypeWrapper::new(factor * shape_functions)
+            }
+        }
+
+        /// The phase field `phi(f)` itself, the energy of the element.
+        pub struct PhaseFieldEnergy3D {
+            nodal_values: Array1<f64>,
+            kreg: f64,
+            l: f64,
+        }
+
+        impl PhaseFieldEnergy3D {
+            pub fn new(nodal_values: Array1<f64>, kreg: f64, l: f64) -> Self {
+                Self {
+                    nodal_values,
+                    kreg,
+                    l,
+                }
+            }
+        }
+
+        impl Simplex3DFunction for PhaseFieldEnergy3D {
+            type Return = ResultTypeWrapper<f64>;
+
+            fn function(
+                &self,
+                xi1: f64,
+                xi2: f64,
+                xi3: f64,
+                xi4: f64,
+                _simplex: &Simplex3D,
+            ) -> Self::Return {
+                let f_base = ansatz_function(&self.nodal_values, &array![xi1, xi2, xi3, xi4]);
+                ResultTypeWrapper::new(phase_field_func(f_base, self.kreg, self.l))
+            }
+        }
+
+        /// The phase field of the quadratic ansatz as an energy density of the 10 nodal values.
+        /// Its [`Tangent`](crate::autodiff::Tangent) is the exact Hessian, the same as
+        /// [`PhaseFieldFuncMatrix3D`].
+        pub struct PhaseFieldDensity3D {
+            nodal_values: [f64; 10],
+            kreg: f64,
+            l: f64,
+        }
+
+        impl PhaseFieldDensity3D {
+            pub fn new(nodal_values: [f64; 10], kreg: f64, l: f64) -> Self {
+                Self {
+                    nodal_values,
+                    kreg,
+                    l,
+                }
+            }
+        }
+
+        impl DualFunction<3, 10> for PhaseFieldDensity3D {
+            fn nodal_values(&self) -> [f64; 10] {
+                self.nodal_values
+            }
+
+            fn density<T: DualNum<f64>>(
+                &self,
+                nodal_values: &[T; 10],
+                xi: &Array1<f64>,
+                _simplex: &Simplex3D,
+            ) -> T {
+                let f_base: T = nodal_values
+                    .iter()
+                    .zip(all_shape_functions(xi).iter())
+                    .map(|(&value, &shape)| value * shape)
+                    .sum();
+                phase_field_func(f_base, self.kreg, self.l)
+            }
+        }
     }
 }
 
@@ -512,9 +744,374 @@ impl Simplex2DFunction for PhaseField2DFunction {
         xi1: f64,
         xi2: f64,
         xi3: f64,
-        _simplex: &crate::domain::Simplex2D,
+        _simplex: &Simplex2D,
     ) -> Self::Return {
-        let f_base = approx_func(self.weights, xi1, xi2, xi3);
+        // Die Quadraturpunkte sind immer gültige baryzentrische Koordinaten
+        let f_base = approx_func(self.weights, xi1, xi2, xi3)
+            .expect("quadrature points are barycentric coordinates");
         return ResultTypeWrapper::new(phase_field_func(f_base, 0.000001, 1.0));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{array, Array1, Array2};
+
+    use crate::autodiff::{Energy, Residual, Tangent};
+    use crate::error::IntegrationError;
+    use crate::integration_2d::domain::{Simplex2D, Simplex2DFunction};
+    use crate::integration_3d::domain::{Simplex3D, Simplex3DFunction, SimplexIntegrator};
+    use crate::integration_3d::integrators::{
+        Hierarchic3DIntegrator, Hierarchic3DIntegratorData, TetrahedralIntegrator,
+    };
+    use crate::simplex::SimplexFunction;
+
+    use super::problem_definition::problem_2d_definition::{
+        PhaseFieldDensity2D, PhaseFieldEnergy2D, PhaseFieldFuncMatrix2D, PhaseFieldResidual2D,
+    };
+    use super::problem_definition::problem_3d_definition::{
+        PhaseFieldDensity3D, PhaseFieldEnergy3D, PhaseFieldFuncDiff23D, PhaseFieldFuncMatrix3D,
+        PhaseFieldResidual3D,
+    };
+    use super::shape_func::{approx_func, BarN1};
+
+    fn tetrahedron() -> Simplex3D {
+        Simplex3D::new_from_points(
+            &array![0., 0., 0.],
+            &array![1., 0., 0.],
+            &array![0., 1., 0.],
+            &array![0., 0., 1.],
+        )
+    }
+
+    #[test]
+    fn shape_functions_check_barycentric_input() {
+        assert!(matches!(
+            BarN1(0.5, 0.5, 0.5),
+            Err(IntegrationError::InvalidBarycentric { .. })
+        ));
+        // Die Formfunktionen sind eine Zerlegung der Eins
+        let sum = approx_func([1.; 6], 0.2, 0.3, 0.5).unwrap();
+        assert!((sum - 1.).abs() < 1e-14);
+    }
+
+    #[test]
+    fn quadratic_shape_functions() {
+        use super::problem_definition::{shape_func_2d, shape_func_3d};
+        use super::shape_func::{BarN2, BarN3, BarN4, BarN5, BarN6};
+
+        // Zerlegung der Eins im Inneren
+        let sum = shape_func_2d::all_shape_functions(&array![0.2, 0.3, 0.5]).sum();
+        assert!((sum - 1.).abs() < 1e-14, "{}", sum);
+        let sum = shape_func_3d::all_shape_functions(&array![0.1, 0.2, 0.3, 0.4]).sum();
+        assert!((sum - 1.).abs() < 1e-14, "{}", sum);
+
+        // Kronecker-Delta an den Ecken und Kantenmitten
+        let nodes = |vertices: usize, edges: &[(usize, usize)]| -> Vec<Array1<f64>> {
+            let unit = |i: usize| Array1::from_shape_fn(vertices, |j| (i == j) as u8 as f64);
+            let midpoints = edges.iter().map(|&(a, b)| (unit(a) + unit(b)) / 2.);
+            (0..vertices).map(unit).chain(midpoints).collect()
+        };
+        for (i, node) in nodes(3, &shape_func_2d::EDGES).iter().enumerate() {
+            let delta = Array1::from_shape_fn(6, |j| (i == j) as u8 as f64);
+            assert_eq!(shape_func_2d::all_shape_functions(node), delta, "{}", i);
+        }
+        for (i, node) in nodes(4, &shape_func_3d::EDGES).iter().enumerate() {
+            let delta = Array1::from_shape_fn(10, |j| (i == j) as u8 as f64);
+            assert_eq!(shape_func_3d::all_shape_functions(node), delta, "{}", i);
+        }
+
+        // Dieselbe Reihenfolge wie BarN1 - BarN6
+        let (xi1, xi2, xi3) = (0.2, 0.3, 0.5);
+        let bar = [BarN1, BarN2, BarN3, BarN4, BarN5, BarN6].map(|n| n(xi1, xi2, xi3).unwrap());
+        let shape_functions = shape_func_2d::all_shape_functions(&array![xi1, xi2, xi3]);
+        for (value, expected) in shape_functions.iter().zip(bar) {
+            assert!((value - expected).abs() < 1e-15);
+        }
+    }
+
+    #[test]
+    fn tangent_is_the_derivative_of_the_residual() {
+        // Vorzeichenwechsel im Ansatz und l != 1, der Punkt liegt nicht auf dem Knick
+        let (kreg, l, h) = (1e-2, 0.5, 1e-6);
+        let sim = Simplex2D::new_from_points(&array![0., 0.], &array![1., 0.], &array![0., 1.]);
+        let nodal_values = array![1.0, -0.5, 2.0, 0.8, -1.2, 0.3];
+        let residual = |values: &Array1<f64>| {
+            PhaseFieldResidual2D::new(values.clone(), kreg, l)
+                .function(0.2, 0.3, 0.5, &sim)
+                .get()
+        };
+        let tangent = PhaseFieldFuncMatrix2D::new(nodal_values.clone(), kreg, l)
+            .function(0.2, 0.3, 0.5, &sim)
+            .get();
+        for j in 0..6 {
+            let mut plus = nodal_values.clone();
+            plus[j] += h;
+            let mut minus = nodal_values.clone();
+            minus[j] -= h;
+            let difference = residual(&plus) - residual(&minus);
+            for i in 0..6 {
+                let expected = 2. * h * tangent[[i, j]];
+                assert!((difference[i] - expected).abs() < 1e-9);
+            }
+        }
+
+        let sim = tetrahedron();
+        let nodal_values = array![1.0, -0.5, 2.0, 0.8, -1.2, 0.3, -0.9, 1.5, 0.7, -1.1];
+        let residual = |values: &Array1<f64>| {
+            PhaseFieldResidual3D::new(values.clone(), kreg, l)
+                .function(0.1, 0.2, 0.3, 0.4, &sim)
+                .get()
+        };
+        for j in 0..10 {
+            let mut plus = nodal_values.clone();
+            plus[j] += h;
+            let mut minus = nodal_values.clone();
+            minus[j] -= h;
+            let difference = residual(&plus) - residual(&minus);
+            for i in 0..10 {
+                let entry = PhaseFieldFuncDiff23D::new(nodal_values.clone(), kreg, l, j, i)
+                    .function(0.1, 0.2, 0.3, 0.4, &sim)
+                    .get();
+                assert!((difference[i] - 2. * h * entry).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn matrix_3d_matches_entries() {
+        let sim = tetrahedron();
+        let nodal_values = array![1.0, 1.0, 1.0, -1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+        let matrix = PhaseFieldFuncMatrix3D::new(nodal_values.clone(), 1e-6, 1.);
+        let mat = matrix.function(0.1, 0.2, 0.3, 0.4, &sim).get();
+        for i in 0..10 {
+            for j in 0..10 {
+                let entry = PhaseFieldFuncDiff23D::new(nodal_values.clone(), 1e-6, 1., j, i)
+                    .function(0.1, 0.2, 0.3, 0.4, &sim)
+                    .get();
+                assert!((mat[[i, j]] - entry).abs() < 1e-12 * entry.abs().max(1.));
+                assert_eq!(mat[[i, j]], mat[[j, i]]);
+            }
+        }
+    }
+
+    #[test]
+    fn matrix_3d_matches_a_reference_basis() {
+        use num_dual::Dual2_64;
+
+        use super::problem_definition::phase_field::phase_field_func;
+
+        // Die zehn Formfunktionen ausgeschrieben, Kanten (1,3), (1,2), (2,3), (1,4), (2,4), (3,4)
+        let reference_basis = |x: [f64; 4]| {
+            [
+                x[0] * (2. * x[0] - 1.),
+                x[1] * (2. * x[1] - 1.),
+                x[2] * (2. * x[2] - 1.),
+                x[3] * (2. * x[3] - 1.),
+                4. * x[0] * x[2],
+                4. * x[0] * x[1],
+                4. * x[1] * x[2],
+                4. * x[0] * x[3],
+                4. * x[1] * x[3],
+                4. * x[2] * x[3],
+            ]
+        };
+        let sim = tetrahedron();
+        let (kreg, l) = (1e-2, 0.5);
+        let nodal_values = [1.0, -0.5, 2.0, 0.8, -1.2, 0.3, -0.9, 1.5, 0.7, -1.1];
+        let xi = [0.1, 0.2, 0.3, 0.4];
+        let mat = PhaseFieldFuncMatrix3D::new(nodal_values.to_vec().into(), kreg, l)
+            .function(xi[0], xi[1], xi[2], xi[3], &sim)
+            .get();
+
+        let shape_functions = reference_basis(xi);
+        let f_base: f64 = nodal_values
+            .iter()
+            .zip(shape_functions)
+            .map(|(u, n)| u * n)
+            .sum();
+        // Die zweite Ableitung des Phasenfelds mit dualen Zahlen statt von Hand
+        let phi = phase_field_func(Dual2_64::from_re(f_base).derive(), kreg, l);
+        let second = phi.v2[(0, 0)];
+        for i in 0..10 {
+            for j in 0..10 {
+                let entry = second * shape_functions[i] * shape_functions[j];
+                assert!((mat[[i, j]] - entry).abs() < 1e-12 * entry.abs().max(1.));
+            }
+        }
+    }
+
+    #[test]
+    fn hierarchic_matrix_3d_shares_one_tree() {
+        let sim = tetrahedron();
+        let nodal_values = array![1.0, 1.0, 1.0, -1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+        let inte = Hierarchic3DIntegrator::new(TetrahedralIntegrator::new(2).unwrap(), false, 1e-1);
+        let mut cache = Hierarchic3DIntegratorData::new_cache();
+
+        let func = Box::new(PhaseFieldFuncMatrix3D::new(nodal_values.clone(), 1e-6, 1.));
+        let mat: Array2<f64> = inte
+            .integrate_simplex(&func, &sim, &mut cache)
+            .unwrap()
+            .get();
+        assert_eq!(mat.shape(), [10, 10]);
+        assert!(cache.tree_size() > 1);
+
+        // Auf dem gemeinsamen Baum ergeben die Einzeleinträge dieselbe Matrix
+        let consolidated =
+            Hierarchic3DIntegrator::new(TetrahedralIntegrator::new(2).unwrap(), true, 1e-1);
+        for (i, j) in [(0, 0), (3, 3), (0, 3), (4, 9)] {
+            let entry = Box::new(PhaseFieldFuncDiff23D::new(
+                nodal_values.clone(),
+                1e-6,
+                1.,
+                j,
+                i,
+            ));
+            let value = consolidated
+                .integrate_simplex(&entry, &sim, &mut cache)
+                .unwrap()
+                .get();
+            assert!(
+                (mat[[i, j]] - value).abs() < 1e-12 * value.abs().max(1.),
+                "Entry ({},{}), Expected: {}, Actual: {}",
+                i,
+                j,
+                value,
+                mat[[i, j]]
+            );
+        }
+    }
+
+    #[test]
+    fn dual_tangent_matches_hand_derived_matrix() {
+        // Auch mit Vorzeichenwechsel im Ansatzfeld und l != 1
+        let nodal_values = [1.0, -0.5, 2.0, 0.8, -1.2, 0.3, 0.9, 1.5, 0.7, 1.1];
+        let xi = array![0.1, 0.2, 0.3, 0.4];
+        let sim = tetrahedron();
+
+        let tangent = Tangent::new(PhaseFieldDensity3D::new(nodal_values, 1e-6, 0.5))
+            .function_vec(&xi, &sim)
+            .get();
+        let mat = PhaseFieldFuncMatrix3D::new(nodal_values.to_vec().into(), 1e-6, 0.5)
+            .function(0.1, 0.2, 0.3, 0.4, &sim)
+            .get();
+        assert!((&tangent - &mat).iter().all(|d| d.abs() < 1e-12));
+
+        // Das Residuum im Vergleich mit zentralen Differenzen der Energie
+        let residual = Residual::new(PhaseFieldDensity3D::new(nodal_values, 1e-6, 0.5))
+            .function_vec(&xi, &sim)
+            .get();
+        let h = 1e-6;
+        for i in 0..10 {
+            let mut plus = nodal_values;
+            plus[i] += h;
+            let mut minus = nodal_values;
+            minus[i] -= h;
+            let difference = (Energy::new(PhaseFieldDensity3D::new(plus, 1e-6, 0.5))
+                .function_vec(&xi, &sim)
+                .get()
+                - Energy::new(PhaseFieldDensity3D::new(minus, 1e-6, 0.5))
+                    .function_vec(&xi, &sim)
+                    .get())
+                / (2. * h);
+            assert!((residual[i] - difference).abs() < 1e-8);
+        }
+
+        let sim = Simplex2D::new_from_points(&array![0., 0.], &array![1., 0.], &array![0., 1.]);
+        let nodal_values = [1.0, -0.5, 2.0, 0.8, -1.2, 0.3];
+        let tangent = Tangent::new(PhaseFieldDensity2D::new(nodal_values, 1e-6, 0.5))
+            .function_vec(&array![0.2, 0.3, 0.5], &sim)
+            .get();
+        let mat = PhaseFieldFuncMatrix2D::new(nodal_values.to_vec().into(), 1e-6, 0.5)
+            .function(0.2, 0.3, 0.5, &sim)
+            .get();
+        assert!((&tangent - &mat).iter().all(|d| d.abs() < 1e-12));
+    }
+
+    #[test]
+    fn residual_and_energy_match_dual_density() {
+        // Mit Vorzeichenwechsel im Ansatz und l != 1
+        let nodal_values = [1.0, -0.5, 2.0, 0.8, -1.2, 0.3];
+        let sim = Simplex2D::new_from_points(&array![0., 0.], &array![1., 0.], &array![0., 1.]);
+        let xi = array![0.2, 0.3, 0.5];
+        let residual = PhaseFieldResidual2D::new(nodal_values.to_vec().into(), 1e-6, 0.5)
+            .function(0.2, 0.3, 0.5, &sim)
+            .get();
+        let dual = Residual::new(PhaseFieldDensity2D::new(nodal_values, 1e-6, 0.5))
+            .function_vec(&xi, &sim)
+            .get();
+        assert!((&residual - &dual).iter().all(|d| d.abs() < 1e-12));
+        let energy = PhaseFieldEnergy2D::new(nodal_values.to_vec().into(), 1e-6, 0.5)
+            .function(0.2, 0.3, 0.5, &sim)
+            .get();
+        let dual = Energy::new(PhaseFieldDensity2D::new(nodal_values, 1e-6, 0.5))
+            .function_vec(&xi, &sim)
+            .get();
+        assert!((energy - dual).abs() < 1e-14);
+    }
+
+    #[test]
+    fn hierarchic_matrix_3d_matches_the_serial_reference() {
+        // Die Werte stammen aus dem Build ohne `parallel`, beide Builds müssen sie bitgenau treffen
+        let sim = tetrahedron();
+        let nodal_values = array![1.0, 1.0, 1.0, -1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+        let inte = Hierarchic3DIntegrator::new(TetrahedralIntegrator::new(2).unwrap(), false, 1e-1);
+        let mut cache = Hierarchic3DIntegratorData::new_cache();
+        let func = Box::new(PhaseFieldFuncMatrix3D::new(nodal_values, 1e-6, 1.));
+        let mat: Array2<f64> = inte
+            .integrate_simplex(&func, &sim, &mut cache)
+            .unwrap()
+            .get();
+        let reference = [
+            ((0, 0), 0x3fb00a083a133173),
+            ((3, 3), 0x3fb35f9435b2bddf),
+            ((0, 3), 0x3f967b630e963a90),
+            ((4, 9), 0x3fdca5dc6e5c4cae),
+        ];
+        for ((i, j), bits) in reference {
+            assert_eq!(mat[[i, j]].to_bits(), bits);
+        }
+        assert_eq!(cache.tree_size(), 311);
+    }
+
+    #[test]
+    fn energy_residual_and_tangent_share_one_tree() {
+        let sim = tetrahedron();
+        let nodal_values = array![1.0, 1.0, 1.0, -1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+        let inte = Hierarchic3DIntegrator::new(TetrahedralIntegrator::new(2).unwrap(), false, 1e-1);
+        let mut cache = Hierarchic3DIntegratorData::new_cache();
+        let tangent = Box::new(PhaseFieldFuncMatrix3D::new(nodal_values.clone(), 1e-6, 1.));
+        inte.integrate_simplex(&tangent, &sim, &mut cache).unwrap();
+        let tree_size = cache.tree_size();
+
+        let consolidated =
+            Hierarchic3DIntegrator::new(TetrahedralIntegrator::new(2).unwrap(), true, 1e-1);
+        let residual = Box::new(PhaseFieldResidual3D::new(nodal_values.clone(), 1e-6, 1.));
+        let residual = consolidated
+            .integrate_simplex(&residual, &sim, &mut cache)
+            .unwrap()
+            .get();
+        assert_eq!(residual.len(), 10);
+        let energy = Box::new(PhaseFieldEnergy3D::new(nodal_values.clone(), 1e-6, 1.));
+        let energy = consolidated
+            .integrate_simplex(&energy, &sim, &mut cache)
+            .unwrap()
+            .get();
+        assert_eq!(cache.tree_size(), tree_size);
+
+        // Auf demselben Baum stimmen sie mit den automatischen Ableitungen überein
+        let values = nodal_values.to_vec().try_into().unwrap();
+        let dual = Box::new(Residual::new(PhaseFieldDensity3D::new(values, 1e-6, 1.)));
+        let dual = consolidated
+            .integrate_simplex(&dual, &sim, &mut cache)
+            .unwrap()
+            .get();
+        assert!((&residual - &dual).iter().all(|d| d.abs() < 1e-12));
+        let dual = Box::new(Energy::new(PhaseFieldDensity3D::new(values, 1e-6, 1.)));
+        let dual = consolidated
+            .integrate_simplex(&dual, &sim, &mut cache)
+            .unwrap()
+            .get();
+        assert!((energy - dual).abs() < 1e-12);
+    }
+}
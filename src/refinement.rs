@@ -0,0 +1,249 @@
+use std::cmp::Ordering;
+
+use indextree::NodeId;
+
+use crate::common::MaybeSync;
+use crate::simplex::SimplexResultType;
+
+/// Leafs of the hierarchic integrators on this level are not refined any further, even if the
+/// criterion asks for it. Their subdomains are scaled by `2^-MAX_REFINEMENT_LEVEL`.
+pub const MAX_REFINEMENT_LEVEL: usize = 20;
+
+/// Decides whether a leaf of a hierarchic integrator has to be refined.
+/// `coarse` is the integral over the leaf, `fine` the integral over its children and `total` the
+/// current estimate of the integral over the whole simplex.
+pub trait RefinementCriterion: Clone + MaybeSync {
+    fn needs_refinement<R: SimplexResultType>(&self, coarse: &R, fine: &R, total: &R) -> bool;
+}
+
+/// The absolute differences of the components, missing components (of an empty neutral element)
+/// count as zero.
+fn component_differences<R: SimplexResultType>(coarse: &R, fine: &R) -> Vec<f64> {
+    let coarse = coarse.components();
+    let fine = fine.components();
+    let len = coarse.len().max(fine.len());
+    (0..len)
+        .map(|i| (coarse.get(i).copied().unwrap_or(0.) - fine.get(i).copied().unwrap_or(0.)).abs())
+        .collect()
+}
+
+/// Refines if the [`SimplexResultType::distance()`] exceeds an absolute threshold.
+/// This is the criterion of [`Hierarchic2DIntegrator::new()`](crate::integration_2d::integrators::Hierarchic2DIntegrator::new).
+#[derive(Clone, Copy, Debug)]
+pub struct AbsoluteCriterion {
+    pub atol: f64,
+}
+
+impl AbsoluteCriterion {
+    pub fn new(atol: f64) -> Self {
+        Self { atol }
+    }
+}
+
+impl RefinementCriterion for AbsoluteCriterion {
+    fn needs_refinement<R: SimplexResultType>(&self, coarse: &R, fine: &R, _total: &R) -> bool {
+        coarse.distance(fine) > self.atol
+    }
+}
+
+/// Refines if the distance exceeds `rtol` times the norm of the total.
+/// If the total vanishes, `rtol` is the absolute tolerance. A total which is zero only up to
+/// rounding still refines every difference, see [`MixedCriterion`] and [`MAX_REFINEMENT_LEVEL`].
+#[derive(Clone, Copy, Debug)]
+pub struct RelativeCriterion {
+    pub rtol: f64,
+}
+
+impl RelativeCriterion {
+    pub fn new(rtol: f64) -> Self {
+        Self { rtol }
+    }
+}
+
+impl RefinementCriterion for RelativeCriterion {
+    fn needs_refinement<R: SimplexResultType>(&self, coarse: &R, fine: &R, total: &R) -> bool {
+        let total_norm = total
+            .components()
+            .iter()
+            .map(|f| f.powi(2))
+            .sum::<f64>()
+            .sqrt();
+        if total_norm == 0. {
+            return coarse.distance(fine) > self.rtol;
+        }
+        coarse.distance(fine) > self.rtol * total_norm
+    }
+}
+
+/// Refines if one component differs by more than `atol`.
+/// Unlike the Frobenius distance, small entries are not dominated by large ones.
+#[derive(Clone, Copy, Debug)]
+pub struct MaxNormCriterion {
+    pub atol: f64,
+}
+
+impl MaxNormCriterion {
+    pub fn new(atol: f64) -> Self {
+        Self { atol }
+    }
+}
+
+impl RefinementCriterion for MaxNormCriterion {
+    fn needs_refinement<R: SimplexResultType>(&self, coarse: &R, fine: &R, _total: &R) -> bool {
+        component_differences(coarse, fine)
+            .into_iter()
+            .any(|diff| diff > self.atol)
+    }
+}
+
+/// Refines if one component `i` violates `|coarse_i - fine_i| <= atol + rtol * |total_i|`.
+#[derive(Clone, Copy, Debug)]
+pub struct MixedCriterion {
+    pub atol: f64,
+    pub rtol: f64,
+}
+
+impl MixedCriterion {
+    pub fn new(atol: f64, rtol: f64) -> Self {
+        Self { atol, rtol }
+    }
+}
+
+impl RefinementCriterion for MixedCriterion {
+    fn needs_refinement<R: SimplexResultType>(&self, coarse: &R, fine: &R, total: &R) -> bool {
+        let total = total.components();
+        component_differences(coarse, fine)
+            .into_iter()
+            .enumerate()
+            .any(|(i, diff)| {
+                diff > self.atol + self.rtol * total.get(i).copied().unwrap_or(0.).abs()
+            })
+    }
+}
+
+/// Globally adaptive mode of the hierarchic integrators (as in QUADPACK).
+/// The leaf with the largest error estimate is split until the summed estimate is at most
+/// `tolerance` or the next split would exceed `max_evaluations`.
+/// One evaluation is one call of the base integrator, in 3D an octahedron costs four.
+#[derive(Clone, Copy, Debug)]
+pub struct GlobalRefinement {
+    pub tolerance: f64,
+    pub max_evaluations: usize,
+}
+
+impl GlobalRefinement {
+    pub fn new(tolerance: f64, max_evaluations: usize) -> Self {
+        Self {
+            tolerance,
+            max_evaluations,
+        }
+    }
+}
+
+/// The calls of the base integrator in the global mode, counted against `max_evaluations`.
+pub(crate) struct EvaluationBudget {
+    evaluations: usize,
+    max_evaluations: usize,
+}
+
+impl EvaluationBudget {
+    pub fn new(global: &GlobalRefinement) -> Self {
+        Self {
+            evaluations: 0,
+            max_evaluations: global.max_evaluations,
+        }
+    }
+
+    /// Counts calls which are made regardless of the budget.
+    pub fn spend(&mut self, evaluations: usize) {
+        self.evaluations += evaluations;
+    }
+
+    /// Counts the calls, if they fit into the budget.
+    pub fn try_spend(&mut self, evaluations: usize) -> bool {
+        if self.evaluations + evaluations > self.max_evaluations {
+            return false;
+        }
+        self.evaluations += evaluations;
+        true
+    }
+}
+
+/// Entry of the priority queue of the global mode, the largest error is popped first.
+/// Equal errors are ordered by the node, so the refinement is deterministic.
+pub(crate) struct LeafError {
+    pub error: f64,
+    pub id: NodeId,
+}
+
+impl PartialEq for LeafError {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for LeafError {}
+
+impl PartialOrd for LeafError {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for LeafError {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.error
+            .total_cmp(&other.error)
+            .then_with(|| other.id.cmp(&self.id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{array, Array2};
+
+    use crate::simplex::ResultTypeWrapper;
+
+    use super::{
+        AbsoluteCriterion, MaxNormCriterion, MixedCriterion, RefinementCriterion, RelativeCriterion,
+    };
+
+    #[test]
+    fn criteria_on_matrices() {
+        // Ein großer und ein kleiner Eintrag, beide mit 10% Fehler
+        let coarse = ResultTypeWrapper::new(array![[100., 0.], [0., 1e-3]]);
+        let fine = ResultTypeWrapper::new(array![[110., 0.], [0., 1.1e-3]]);
+        let total = ResultTypeWrapper::new(array![[1000., 0.], [0., 1e-3]]);
+
+        assert!(AbsoluteCriterion::new(1.).needs_refinement(&coarse, &fine, &total));
+        assert!(!AbsoluteCriterion::new(20.).needs_refinement(&coarse, &fine, &total));
+
+        assert!(RelativeCriterion::new(1e-3).needs_refinement(&coarse, &fine, &total));
+        assert!(!RelativeCriterion::new(1e-1).needs_refinement(&coarse, &fine, &total));
+
+        assert!(MaxNormCriterion::new(5.).needs_refinement(&coarse, &fine, &total));
+        assert!(!MaxNormCriterion::new(20.).needs_refinement(&coarse, &fine, &total));
+
+        // Der große Eintrag ist relativ genau genug, der kleine nicht
+        let mixed = MixedCriterion::new(1e-8, 2e-2);
+        assert!(mixed.needs_refinement(&coarse, &fine, &total));
+        let fine = ResultTypeWrapper::new(array![[110., 0.], [0., 1e-3]]);
+        assert!(!mixed.needs_refinement(&coarse, &fine, &total));
+    }
+
+    #[test]
+    fn relative_to_a_vanishing_total() {
+        let zero = ResultTypeWrapper::new(0.);
+        let coarse = ResultTypeWrapper::new(1e-4);
+        assert!(!RelativeCriterion::new(1e-3).needs_refinement(&coarse, &zero, &zero));
+        assert!(RelativeCriterion::new(1e-5).needs_refinement(&coarse, &zero, &zero));
+    }
+
+    #[test]
+    fn empty_neutral_element_counts_as_zero() {
+        let coarse = ResultTypeWrapper::new(array![[1., 2.], [3., 4.]]);
+        let empty = ResultTypeWrapper::new(Array2::<f64>::zeros([0, 0]));
+        assert!(MaxNormCriterion::new(3.5).needs_refinement(&coarse, &empty, &coarse));
+        assert!(!MaxNormCriterion::new(4.5).needs_refinement(&empty, &coarse, &coarse));
+    }
+}
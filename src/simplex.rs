@@ -0,0 +1,786 @@
+use std::ops::{AddAssign, MulAssign};
+
+use ndarray::{array, Array, Array1, Array2, Dimension};
+use num_complex::Complex64;
+
+use crate::common::{determinant, IntegratorDummy, MaybeSend, MaybeSync};
+use crate::embedded::EmbeddedResult;
+use crate::error::{check_shape, IntegrationError, Result};
+
+/// Simplices with a larger [`Simplex::get_aspect_ratio()`] are considered degenerate.
+pub const MAX_ASPECT_RATIO: f64 = 1e12;
+
+/// How a negatively oriented simplex, i.e. [`Simplex::get_volume()`] `< 0`, is treated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OrientationPolicy {
+    /// The simplex is kept, its integrals have the sign of the volume.
+    #[default]
+    Signed,
+    /// The first two vertices are swapped, so the simplex is positively oriented.
+    Reorder,
+    /// The simplex is rejected with [`IntegrationError::InvertedSimplex`].
+    Reject,
+}
+
+/// A simplex with `D + 1` vertices in the Euclidean space of dimension `D`.
+/// The vertices are stored as the columns of a `D x (D+1)` matrix.
+pub struct Simplex<const D: usize> {
+    points: Array2<f64>,
+}
+
+impl<const D: usize> Simplex<D> {
+    /// The points must be a `D x (D+1)` matrix, otherwise [`IntegrationError::DimensionMismatch`].
+    pub fn new_from_array(points: Array2<f64>) -> Result<Self> {
+        check_shape(&points, [D, D + 1])?;
+        Ok(Self { points })
+    }
+
+    /// Creates the simplex from a list of exactly `D + 1` vertices.
+    pub fn new_from_point_list(points: &[Array1<f64>]) -> Result<Self> {
+        if points.len() != D + 1 || points.iter().any(|point| point.len() != D) {
+            return Err(IntegrationError::DimensionMismatch {
+                expected: [D, D + 1],
+                found: [
+                    points.iter().map(|point| point.len()).max().unwrap_or(0),
+                    points.len(),
+                ],
+            });
+        }
+        let mut array = Array2::zeros([D, D + 1]);
+        for (j, point) in points.iter().enumerate() {
+            for i in 0..D {
+                array[[i, j]] = point[i];
+            }
+        }
+        Ok(Self { points: array })
+    }
+
+    /// Like [`Self::new_from_array()`], but degenerate simplices are rejected and the orientation
+    /// is handled by the `policy`.
+    pub fn new_checked(points: Array2<f64>, policy: OrientationPolicy) -> Result<Self> {
+        let mut simplex = Self::new_from_array(points)?;
+        simplex.check_quality()?;
+        let volume = simplex.get_volume();
+        if volume < 0. {
+            match policy {
+                OrientationPolicy::Signed => {}
+                OrientationPolicy::Reorder => {
+                    for i in 0..D {
+                        simplex.points.swap([i, 0], [i, 1]);
+                    }
+                }
+                OrientationPolicy::Reject => {
+                    return Err(IntegrationError::InvertedSimplex { volume })
+                }
+            }
+        }
+        Ok(simplex)
+    }
+
+    pub fn get_points(&self) -> Array2<f64> {
+        return self.points.clone();
+    }
+
+    /// The vertices as columns, without the copy of [`Self::get_points()`].
+    pub fn get_points_borrow(&self) -> &Array2<f64> {
+        &self.points
+    }
+
+    /// Maps the barycentric coordinates `xi` to the physical point `x`.
+    pub fn get_physical_point(&self, xi: &Array1<f64>) -> [f64; D] {
+        let mut x = [0.; D];
+        for (i, x_i) in x.iter_mut().enumerate() {
+            *x_i = self.points.row(i).dot(xi);
+        }
+        x
+    }
+
+    /// The signed volume, positive if the vertices are oriented counterclockwise.
+    pub fn get_volume(&self) -> f64 {
+        let po = &self.points;
+        let mut edges = Array2::zeros([D, D]);
+        for j in 0..D {
+            for i in 0..D {
+                edges[[i, j]] = po[[i, j + 1]] - po[[i, 0]];
+            }
+        }
+        let factorial: f64 = (1..=D).map(|i| i as f64).product();
+        let det = determinant(&edges).expect("the edges are a D x D matrix");
+        return det / factorial;
+    }
+
+    /// The longest edge to the power `D` relative to the volume, scaled such that the regular
+    /// simplex has the aspect ratio 1. Flat simplices have an infinite aspect ratio.
+    pub fn get_aspect_ratio(&self) -> f64 {
+        let po = &self.points;
+        let mut longest_edge: f64 = 0.;
+        for j in 0..=D {
+            for k in 0..j {
+                let edge = (&po.column(j) - &po.column(k))
+                    .mapv(|x| x.powi(2))
+                    .sum()
+                    .sqrt();
+                longest_edge = longest_edge.max(edge);
+            }
+        }
+        // Das Volumen des regulären Simplex mit der Kantenlänge a ist a^D / D! * sqrt((D+1) / 2^D)
+        let factorial: f64 = (1..=D).map(|i| i as f64).product();
+        let regular_volume = longest_edge.powi(D as i32) / factorial
+            * ((D + 1) as f64 / 2_f64.powi(D as i32)).sqrt();
+        regular_volume / self.get_volume().abs()
+    }
+
+    /// Whether the volume is (almost) zero, i.e. the aspect ratio exceeds [`MAX_ASPECT_RATIO`].
+    pub fn is_degenerate(&self) -> bool {
+        let aspect_ratio = self.get_aspect_ratio();
+        !aspect_ratio.is_finite() || aspect_ratio > MAX_ASPECT_RATIO
+    }
+
+    /// [`IntegrationError::DegenerateSimplex`] if the simplex [`Self::is_degenerate()`].
+    pub fn check_quality(&self) -> Result<()> {
+        if self.is_degenerate() {
+            return Err(IntegrationError::DegenerateSimplex {
+                volume: self.get_volume(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Simplex<2> {
+    pub fn new_from_points(p1: &Array1<f64>, p2: &Array1<f64>, p3: &Array1<f64>) -> Self {
+        Self {
+            points: array![[p1[0], p2[0], p3[0]], [p1[1], p2[1], p3[1]]],
+        }
+    }
+
+    pub fn get_area(&self) -> f64 {
+        self.get_volume()
+    }
+}
+
+impl Simplex<3> {
+    pub fn new_from_points(
+        p1: &Array1<f64>,
+        p2: &Array1<f64>,
+        p3: &Array1<f64>,
+        p4: &Array1<f64>,
+    ) -> Self {
+        Self {
+            points: array![
+                [p1[0], p2[0], p3[0], p4[0]],
+                [p1[1], p2[1], p3[1], p4[1]],
+                [p1[2], p2[2], p3[2], p4[2]]
+            ],
+        }
+    }
+}
+
+pub trait SimplexResultType: MulAssign<f64> + AddAssign<f64> + MaybeSend {
+    fn add_assign(&mut self, other: &Self);
+
+    fn distance(&self, other: &Self) -> f64;
+
+    fn additive_neutral_element() -> Self;
+
+    /// The real components of the result, e.g. the entries of a matrix.
+    /// Used by the [`RefinementCriterion`](crate::refinement::RefinementCriterion)s which
+    /// compare entry by entry.
+    fn components(&self) -> Vec<f64>;
+}
+
+#[derive(Debug)]
+pub struct ResultTypeWrapper<T>(T);
+
+impl<T> ResultTypeWrapper<T> {
+    pub fn new(t: T) -> Self {
+        Self(t)
+    }
+
+    pub fn get(self) -> T {
+        self.0
+    }
+
+    pub fn get_borrow(&self) -> &T {
+        &self.0
+    }
+}
+
+impl MulAssign<f64> for ResultTypeWrapper<f64> {
+    fn mul_assign(&mut self, rhs: f64) {
+        self.0 *= rhs
+    }
+}
+
+impl AddAssign<f64> for ResultTypeWrapper<f64> {
+    fn add_assign(&mut self, rhs: f64) {
+        self.0 += rhs
+    }
+}
+
+impl SimplexResultType for ResultTypeWrapper<f64> {
+    fn add_assign(&mut self, other: &Self) {
+        self.0 += other.0
+    }
+
+    fn distance(&self, other: &Self) -> f64 {
+        (self.0 - other.0).abs()
+    }
+
+    fn additive_neutral_element() -> Self {
+        Self(0.)
+    }
+
+    fn components(&self) -> Vec<f64> {
+        vec![self.0]
+    }
+}
+
+impl<I: Dimension> MulAssign<f64> for ResultTypeWrapper<Array<f64, I>> {
+    fn mul_assign(&mut self, rhs: f64) {
+        self.0 *= rhs
+    }
+}
+
+impl<I: Dimension> AddAssign<f64> for ResultTypeWrapper<Array<f64, I>> {
+    fn add_assign(&mut self, rhs: f64) {
+        self.0 += rhs
+    }
+}
+
+/// Vectors, matrices and arrays of any dimension ([`ArrayD`](ndarray::ArrayD)).
+/// The neutral element is the empty array, it takes the shape of the first array added to it,
+/// so the shape is given by the values of the function.
+/// Functions which know their shape may still override
+/// [`SimplexFunction::additive_neutral_element()`].
+impl<I: Dimension> SimplexResultType for ResultTypeWrapper<Array<f64, I>> {
+    fn add_assign(&mut self, other: &Self) {
+        if self.0.is_empty() {
+            self.0 = other.0.clone()
+        } else {
+            self.0 = &self.0 + &other.0
+        }
+    }
+
+    /// The Euclidean (for matrices the Frobenius) norm of the difference, so the hierarchic
+    /// integrators refine the whole array at once.
+    fn distance(&self, other: &Self) -> f64 {
+        if self.0.is_empty() || other.0.is_empty() {
+            return self
+                .0
+                .iter()
+                .chain(other.0.iter())
+                .map(|f| f.powi(2))
+                .sum::<f64>()
+                .sqrt();
+        }
+        let diff = &self.0 - &other.0;
+        diff.iter().map(|f| f.powi(2)).sum::<f64>().sqrt()
+    }
+
+    fn additive_neutral_element() -> Self {
+        Self(Array::zeros(I::zeros(I::NDIM.unwrap_or(1))))
+    }
+
+    fn components(&self) -> Vec<f64> {
+        self.0.iter().copied().collect()
+    }
+}
+
+impl MulAssign<f64> for ResultTypeWrapper<Complex64> {
+    fn mul_assign(&mut self, rhs: f64) {
+        self.0 *= rhs
+    }
+}
+
+/// Adds to the real part.
+impl AddAssign<f64> for ResultTypeWrapper<Complex64> {
+    fn add_assign(&mut self, rhs: f64) {
+        self.0 += rhs
+    }
+}
+
+/// The distance is the modulus of the difference, the components are the real and the
+/// imaginary part.
+impl SimplexResultType for ResultTypeWrapper<Complex64> {
+    fn add_assign(&mut self, other: &Self) {
+        self.0 += other.0
+    }
+
+    fn distance(&self, other: &Self) -> f64 {
+        (self.0 - other.0).norm()
+    }
+
+    fn additive_neutral_element() -> Self {
+        Self(Complex64::new(0., 0.))
+    }
+
+    fn components(&self) -> Vec<f64> {
+        vec![self.0.re, self.0.im]
+    }
+}
+
+impl<I: Dimension> MulAssign<f64> for ResultTypeWrapper<Array<Complex64, I>> {
+    fn mul_assign(&mut self, rhs: f64) {
+        self.0.mapv_inplace(|f| f * rhs)
+    }
+}
+
+impl<I: Dimension> AddAssign<f64> for ResultTypeWrapper<Array<Complex64, I>> {
+    fn add_assign(&mut self, rhs: f64) {
+        self.0.mapv_inplace(|f| f + rhs)
+    }
+}
+
+/// Like the real arrays, with the moduli of the entries in the distance.
+/// The components are the real and imaginary parts of all entries.
+impl<I: Dimension> SimplexResultType for ResultTypeWrapper<Array<Complex64, I>> {
+    fn add_assign(&mut self, other: &Self) {
+        if self.0.is_empty() {
+            self.0 = other.0.clone()
+        } else {
+            self.0 = &self.0 + &other.0
+        }
+    }
+
+    fn distance(&self, other: &Self) -> f64 {
+        if self.0.is_empty() || other.0.is_empty() {
+            return self
+                .0
+                .iter()
+                .chain(other.0.iter())
+                .map(|f| f.norm_sqr())
+                .sum::<f64>()
+                .sqrt();
+        }
+        let diff = &self.0 - &other.0;
+        diff.iter().map(|f| f.norm_sqr()).sum::<f64>().sqrt()
+    }
+
+    fn additive_neutral_element() -> Self {
+        Self(Array::zeros(I::zeros(I::NDIM.unwrap_or(1))))
+    }
+
+    fn components(&self) -> Vec<f64> {
+        self.0.iter().flat_map(|f| [f.re, f.im]).collect()
+    }
+}
+
+impl<const N: usize> MulAssign<f64> for ResultTypeWrapper<[f64; N]> {
+    fn mul_assign(&mut self, rhs: f64) {
+        self.0.iter_mut().for_each(|f| *f *= rhs)
+    }
+}
+
+impl<const N: usize> AddAssign<f64> for ResultTypeWrapper<[f64; N]> {
+    fn add_assign(&mut self, rhs: f64) {
+        self.0.iter_mut().for_each(|f| *f += rhs)
+    }
+}
+
+/// A vector of fixed length, the neutral element is the zero vector.
+impl<const N: usize> SimplexResultType for ResultTypeWrapper<[f64; N]> {
+    fn add_assign(&mut self, other: &Self) {
+        for (f, g) in self.0.iter_mut().zip(other.0.iter()) {
+            *f += g
+        }
+    }
+
+    fn distance(&self, other: &Self) -> f64 {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .map(|(f, g)| (f - g).powi(2))
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    fn additive_neutral_element() -> Self {
+        Self([0.; N])
+    }
+
+    fn components(&self) -> Vec<f64> {
+        self.0.to_vec()
+    }
+}
+
+impl<const R: usize, const C: usize> MulAssign<f64> for ResultTypeWrapper<[[f64; C]; R]> {
+    fn mul_assign(&mut self, rhs: f64) {
+        self.0.iter_mut().flatten().for_each(|f| *f *= rhs)
+    }
+}
+
+impl<const R: usize, const C: usize> AddAssign<f64> for ResultTypeWrapper<[[f64; C]; R]> {
+    fn add_assign(&mut self, rhs: f64) {
+        self.0.iter_mut().flatten().for_each(|f| *f += rhs)
+    }
+}
+
+/// A fixed `R x C` matrix, stored row by row. The neutral element is the zero matrix.
+impl<const R: usize, const C: usize> SimplexResultType for ResultTypeWrapper<[[f64; C]; R]> {
+    fn add_assign(&mut self, other: &Self) {
+        for (f, g) in self.0.iter_mut().flatten().zip(other.0.iter().flatten()) {
+            *f += g
+        }
+    }
+
+    /// The Frobenius norm of the difference.
+    fn distance(&self, other: &Self) -> f64 {
+        self.0
+            .iter()
+            .flatten()
+            .zip(other.0.iter().flatten())
+            .map(|(f, g)| (f - g).powi(2))
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    fn additive_neutral_element() -> Self {
+        Self([[0.; C]; R])
+    }
+
+    fn components(&self) -> Vec<f64> {
+        self.0.iter().flatten().copied().collect()
+    }
+}
+
+/// A general trait implemented by types which supply a function to integrate over a simplex of
+/// dimension `D`.
+/// Inputs must be expressed in the `D + 1` barycentric coordinates.
+///
+/// For triangles and tetrahedra it is usually implemented through
+/// [`Simplex2DFunction`](crate::integration_2d::Simplex2DFunction) and
+/// [`Simplex3DFunction`](crate::integration_3d::Simplex3DFunction).
+/// With the feature `parallel` the function must be `Sync`, as it is evaluated from several threads.
+pub trait SimplexFunction<const D: usize>: MaybeSync {
+    type Return: SimplexResultType;
+
+    /// The function over the Simplex.
+    fn function_vec(&self, xi: &Array1<f64>, simplex: &Simplex<D>) -> Self::Return;
+
+    /// The start of every sum, the integrators always take it from the function.
+    /// A function whose result shape is known, e.g. an element matrix, may return the zero array.
+    fn additive_neutral_element(&self) -> Self::Return {
+        Self::Return::additive_neutral_element()
+    }
+}
+
+/// Adapts a function of the physical coordinates `x`, e.g. a closure `|x: &[f64; 2]| x[0] * x[1]`,
+/// to a [`SimplexFunction`]. Each barycentric point is mapped through the simplex once, the
+/// result `T` is wrapped into a [`ResultTypeWrapper`].
+pub struct PhysicalFunction<const D: usize, F> {
+    func: F,
+}
+
+impl<const D: usize, F> PhysicalFunction<D, F> {
+    pub fn new<T>(func: F) -> Self
+    where
+        F: Fn(&[f64; D]) -> T,
+    {
+        Self { func }
+    }
+}
+
+impl<const D: usize, F, T> SimplexFunction<D> for PhysicalFunction<D, F>
+where
+    F: Fn(&[f64; D]) -> T + MaybeSync,
+    ResultTypeWrapper<T>: SimplexResultType,
+{
+    type Return = ResultTypeWrapper<T>;
+
+    fn function_vec(&self, xi: &Array1<f64>, simplex: &Simplex<D>) -> Self::Return {
+        ResultTypeWrapper::new((self.func)(&simplex.get_physical_point(xi)))
+    }
+}
+
+/// The cache of an integrator, which can be created without the integrator, e.g. for each
+/// child of a [`CompositeCache`].
+pub trait IntegratorCache: MaybeSend {
+    fn new_cache() -> Self;
+}
+
+impl IntegratorCache for IntegratorDummy {
+    fn new_cache() -> Self {
+        Self::get()
+    }
+}
+
+/// The cache of an integrator which divides the simplex into fixed subsimplices, one cache of
+/// the wrapped integrator per subsimplex. So the wrapped integrator keeps its state per child.
+#[derive(Debug)]
+pub struct CompositeCache<C> {
+    pub(crate) sub_caches: Vec<C>,
+}
+
+impl<C: IntegratorCache> CompositeCache<C> {
+    /// The caches of the children are created by the first integration.
+    pub fn new_cache() -> Self {
+        Self {
+            sub_caches: Vec::new(),
+        }
+    }
+
+    /// The caches of the children, in the order of the subdivision.
+    pub fn sub_caches(&self) -> &[C] {
+        &self.sub_caches
+    }
+
+    /// The caches of `count` children. If the number does not match, e.g. because the order of
+    /// the subdivision changed, the caches are created anew.
+    pub(crate) fn sub_caches_mut(&mut self, count: usize) -> &mut [C] {
+        if self.sub_caches.len() != count {
+            self.sub_caches = (0..count).map(|_| C::new_cache()).collect();
+        }
+        &mut self.sub_caches
+    }
+}
+
+impl<C: IntegratorCache> IntegratorCache for CompositeCache<C> {
+    fn new_cache() -> Self {
+        Self::new_cache()
+    }
+}
+
+/// A general trait implemented by types which supply an integration scheme for a single simplex
+/// of dimension `D`, with the cache type `C`.
+/// Allows for easy substitution of simplex integration schemes.
+/// An integrator may support several cache types, e.g. a subdivision integrator uses a
+/// [`CompositeCache`] of the cache type of the integrator it wraps.
+///
+/// All integrators share the sign convention of [`Simplex::get_volume()`]: the integral over a
+/// negatively oriented simplex is the negative integral over the same point set, unless
+/// [`OrientationPolicy::Reorder`] is used.
+pub trait SimplexIntegrator<const D: usize, C>: MaybeSync {
+    fn dupe(&self) -> Self;
+
+    /// This function will be called on a single simplex, given in the second argument.
+    /// Same as [`Self::integrate_simplex_oriented()`] with [`OrientationPolicy::Signed`].
+    fn integrate_simplex<T: SimplexFunction<D>>(
+        &self,
+        func: &Box<T>,
+        simplex: &Simplex<D>,
+        cache_data: &mut C,
+    ) -> Result<T::Return> {
+        self.integrate_simplex_oriented(func, simplex, cache_data, OrientationPolicy::Signed)
+    }
+
+    /// Degenerate simplices are rejected with [`IntegrationError::DegenerateSimplex`], negatively
+    /// oriented ones are handled by the `policy`.
+    /// With [`OrientationPolicy::Reorder`] the function still gets the barycentric coordinates
+    /// in the original vertex order.
+    fn integrate_simplex_oriented<T: SimplexFunction<D>>(
+        &self,
+        func: &Box<T>,
+        simplex: &Simplex<D>,
+        cache_data: &mut C,
+        policy: OrientationPolicy,
+    ) -> Result<T::Return> {
+        simplex.check_quality()?;
+        let mut transformation = Array2::eye(D + 1);
+        let volume = simplex.get_volume();
+        if volume < 0. {
+            match policy {
+                OrientationPolicy::Signed => {}
+                // Die Teildomäne mit vertauschten Ecken 1 und 2 ist positiv orientiert
+                OrientationPolicy::Reorder => {
+                    transformation[[0, 0]] = 0.;
+                    transformation[[1, 1]] = 0.;
+                    transformation[[0, 1]] = 1.;
+                    transformation[[1, 0]] = 1.;
+                }
+                OrientationPolicy::Reject => {
+                    return Err(IntegrationError::InvertedSimplex { volume })
+                }
+            }
+        }
+        self.integrate_over_domain(&transformation, func, simplex, cache_data)
+    }
+
+    /// A more general function which takes a transformation matrix to map to the initial subdomain of the simplex.
+    /// The transformation must be a `(D+1) x (D+1)` matrix, otherwise
+    /// [`IntegrationError::DimensionMismatch`].
+    fn integrate_over_domain<T: SimplexFunction<D>>(
+        &self,
+        transformation: &Array2<f64>,
+        func: &Box<T>,
+        simplex: &Simplex<D>,
+        cache_data: &mut C,
+    ) -> Result<T::Return>;
+
+    /// Like [`Self::integrate_over_domain()`], together with the integral of an embedded rule of
+    /// lower degree from the same function evaluations. `None` if the integrator has none, an
+    /// [`EmbeddedIntegrator`](crate::embedded::EmbeddedIntegrator) returns its embedded rule.
+    fn integrate_over_domain_embedded<T: SimplexFunction<D>>(
+        &self,
+        _transformation: &Array2<f64>,
+        _func: &Box<T>,
+        _simplex: &Simplex<D>,
+        _cache_data: &mut C,
+    ) -> Result<Option<EmbeddedResult<T::Return>>> {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{array, Array2, ArrayD, IxDyn};
+    use num_complex::Complex64;
+
+    use super::{OrientationPolicy, ResultTypeWrapper, Simplex, SimplexResultType};
+    use crate::error::IntegrationError;
+
+    #[test]
+    fn volume_in_all_dimensions() {
+        let line = Simplex::<1>::new_from_point_list(&[array![1.], array![3.5]]).unwrap();
+        assert!((line.get_volume() - 2.5).abs() < 1e-14);
+
+        let triangle =
+            Simplex::<2>::new_from_points(&array![1., 1.], &array![3., 1.], &array![1., 2.]);
+        assert!((triangle.get_volume() - 1.).abs() < 1e-14);
+
+        let tetrahedron = Simplex::<3>::new_from_points(
+            &array![0., 0., 0.],
+            &array![2., 0., 0.],
+            &array![0., 3., 0.],
+            &array![0., 0., 1.],
+        );
+        assert!((tetrahedron.get_volume() - 1.).abs() < 1e-14);
+
+        // Ein Raum-Zeit-Simplex mit dem Volumen 1/4!
+        let pentatope = Simplex::<4>::new_from_point_list(&[
+            array![0., 0., 0., 0.],
+            array![1., 0., 0., 0.],
+            array![0., 1., 0., 0.],
+            array![0., 0., 1., 0.],
+            array![0., 0., 0., 1.],
+        ])
+        .unwrap();
+        assert!((pentatope.get_volume() - 1. / 24.).abs() < 1e-14);
+    }
+
+    #[test]
+    fn orientation_changes_sign() {
+        let triangle =
+            Simplex::<2>::new_from_points(&array![1., 1.], &array![1., 2.], &array![3., 1.]);
+        assert!(triangle.get_volume() < 0.);
+    }
+
+    #[test]
+    fn wrong_number_of_points() {
+        assert!(matches!(
+            Simplex::<2>::new_from_array(Array2::zeros([3, 3])),
+            Err(IntegrationError::DimensionMismatch {
+                expected: [2, 3],
+                found: [3, 3]
+            })
+        ));
+        assert!(Simplex::<2>::new_from_point_list(&[array![0., 0.], array![1., 0.]]).is_err());
+        assert!(Simplex::<2>::new_from_point_list(&[
+            array![0., 0.],
+            array![1., 0.],
+            array![0., 1., 0.]
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn aspect_ratio_and_degeneracy() {
+        let equilateral = Simplex::<2>::new_from_points(
+            &array![0., 0.],
+            &array![1., 0.],
+            &array![0.5, 3_f64.sqrt() / 2.],
+        );
+        assert!((equilateral.get_aspect_ratio() - 1.).abs() < 1e-14);
+        let regular = Simplex::<3>::new_from_points(
+            &array![1., 1., 1.],
+            &array![1., -1., -1.],
+            &array![-1., 1., -1.],
+            &array![-1., -1., 1.],
+        );
+        assert!((regular.get_aspect_ratio() - 1.).abs() < 1e-14);
+
+        let needle =
+            Simplex::<2>::new_from_points(&array![0., 0.], &array![1., 0.], &array![0.5, 1e-6]);
+        assert!(needle.get_aspect_ratio() > 1e5);
+        assert!(!needle.is_degenerate());
+
+        let flat =
+            Simplex::<2>::new_from_points(&array![0., 0.], &array![1., 0.], &array![0.5, 1e-14]);
+        assert!(flat.is_degenerate());
+        assert!(matches!(
+            flat.check_quality(),
+            Err(IntegrationError::DegenerateSimplex { .. })
+        ));
+        let point =
+            Simplex::<2>::new_from_points(&array![1., 1.], &array![1., 1.], &array![1., 1.]);
+        assert!(point.is_degenerate());
+    }
+
+    #[test]
+    fn checked_construction() {
+        let clockwise = array![[0., 0., 1.], [0., 1., 0.]];
+        let signed =
+            Simplex::<2>::new_checked(clockwise.clone(), OrientationPolicy::Signed).unwrap();
+        assert!((signed.get_volume() + 0.5).abs() < 1e-14);
+
+        let reordered =
+            Simplex::<2>::new_checked(clockwise.clone(), OrientationPolicy::Reorder).unwrap();
+        assert!((reordered.get_volume() - 0.5).abs() < 1e-14);
+        assert_eq!(reordered.get_points(), array![[0., 0., 1.], [1., 0., 0.]]);
+
+        assert!(matches!(
+            Simplex::<2>::new_checked(clockwise, OrientationPolicy::Reject),
+            Err(IntegrationError::InvertedSimplex { .. })
+        ));
+        assert!(matches!(
+            Simplex::<2>::new_checked(
+                array![[0., 1., 2.], [0., 1., 2.]],
+                OrientationPolicy::Reorder
+            ),
+            Err(IntegrationError::DegenerateSimplex { .. })
+        ));
+    }
+
+    #[test]
+    fn result_types_of_any_shape() {
+        let mut sum = ResultTypeWrapper::<ArrayD<f64>>::additive_neutral_element();
+        let value = ResultTypeWrapper::new(ArrayD::from_elem(IxDyn(&[2, 3, 4]), 1.5));
+        assert_eq!(sum.distance(&value), (24. * 1.5_f64.powi(2)).sqrt());
+        SimplexResultType::add_assign(&mut sum, &value);
+        SimplexResultType::add_assign(&mut sum, &value);
+        assert_eq!(sum.get_borrow().shape(), [2, 3, 4]);
+        assert!(sum.components().iter().all(|&f| f == 3.));
+
+        let mut vector = ResultTypeWrapper::<[f64; 3]>::additive_neutral_element();
+        SimplexResultType::add_assign(&mut vector, &ResultTypeWrapper::new([1., 2., 2.]));
+        vector *= 2.;
+        assert_eq!(vector.get_borrow(), &[2., 4., 4.]);
+        assert_eq!(vector.distance(&ResultTypeWrapper::new([0.; 3])), 6.);
+
+        let mut matrix = ResultTypeWrapper::<[[f64; 2]; 3]>::additive_neutral_element();
+        SimplexResultType::add_assign(
+            &mut matrix,
+            &ResultTypeWrapper::new([[1., 2.], [3., 4.], [5., 6.]]),
+        );
+        matrix += 1.;
+        assert_eq!(matrix.components(), vec![2., 3., 4., 5., 6., 7.]);
+        let identity = ResultTypeWrapper::new([[1., 0.], [0., 1.], [0., 0.]]);
+        assert_eq!(
+            matrix.distance(&identity),
+            (1. + 9. + 16. + 16. + 36. + 49_f64).sqrt()
+        );
+
+        // Der Abstand ist der Betrag der Differenz
+        let complex = ResultTypeWrapper::new(Complex64::new(3., 4.));
+        assert_eq!(
+            complex.distance(&ResultTypeWrapper::additive_neutral_element()),
+            5.
+        );
+        assert_eq!(complex.components(), vec![3., 4.]);
+        let array = ResultTypeWrapper::new(array![Complex64::new(3., 4.), Complex64::new(0., 12.)]);
+        assert_eq!(
+            array.distance(&ResultTypeWrapper::additive_neutral_element()),
+            13.
+        );
+    }
+}
@@ -0,0 +1,289 @@
+//! Storage formats of the refinement trees of the hierarchic integrators.
+//!
+//! There are three encodings of the same tree:
+//! * the vec tree of magic numbers: the node numbers in DFS order, the children of a node are
+//!   enclosed by [`OPEN`] and [`CLOSE`]. The checked flags are lost.
+//! * a compact binary file: [`MAGIC`], the [`VERSION`] (u32, little endian), the dimension and then
+//!   for every node in DFS order its number, its checked flag and the number of its children.
+//! * a readable JSON file with the version, the dimension and the nested nodes.
+
+use std::io::{Read, Write};
+
+use indextree::{Arena, NodeId};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{IntegrationError, Result};
+
+/// Opens the children of a node in a vec tree.
+pub(crate) const OPEN: u8 = 20;
+/// Closes the children of a node in a vec tree.
+pub(crate) const CLOSE: u8 = 21;
+/// The first bytes of every binary tree file.
+pub(crate) const MAGIC: &[u8; 4] = b"SSIT";
+/// The version of the binary and the JSON format.
+pub(crate) const VERSION: u32 = 1;
+const FORMAT_NAME: &str = "simplex-subdivision-integration tree";
+
+/// The node data of a hierarchic tree, which is stored.
+pub(crate) trait TreeData: Sized {
+    /// 2 or 3
+    const DIMENSION: u8;
+
+    fn new_root() -> Self;
+    fn new_child(&self, number: u8) -> Self;
+    fn number(&self) -> u8;
+    fn checked(&self) -> bool;
+    fn set_checked(&mut self, checked: bool);
+    /// The numbers of the children of a subdomain, only complete refinements are valid.
+    fn child_numbers(number: u8) -> Vec<u8>;
+}
+
+/// A tree independent of the arena, the root has the number 0.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct TreeNode {
+    pub number: u8,
+    pub checked: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TreeNode>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonTree {
+    format: String,
+    version: u32,
+    dimension: u8,
+    root: TreeNode,
+}
+
+fn invalid_data(message: String) -> IntegrationError {
+    IntegrationError::MalformedTree(message)
+}
+
+impl TreeNode {
+    pub(crate) fn from_arena<N: TreeData>(arena: &Arena<N>, id: NodeId) -> Self {
+        Self {
+            number: arena[id].get().number(),
+            checked: arena[id].get().checked(),
+            children: id
+                .children(arena)
+                .map(|child| Self::from_arena(arena, child))
+                .collect(),
+        }
+    }
+
+    /// Builds the arena, the children of every node are checked against [`TreeData::child_numbers()`].
+    pub(crate) fn to_arena<N: TreeData>(&self) -> Result<(Arena<N>, NodeId)> {
+        if self.number != 0 {
+            return Err(invalid_data(format!(
+                "the root has the number {} instead of 0",
+                self.number
+            )));
+        }
+        let mut arena = Arena::new();
+        let mut root = N::new_root();
+        root.set_checked(self.checked);
+        let root_id = arena.new_node(root);
+        self.append_children(&mut arena, root_id)?;
+        Ok((arena, root_id))
+    }
+
+    fn append_children<N: TreeData>(&self, arena: &mut Arena<N>, id: NodeId) -> Result<()> {
+        if self.children.is_empty() {
+            return Ok(());
+        }
+        let numbers: Vec<u8> = self.children.iter().map(|child| child.number).collect();
+        if numbers != N::child_numbers(self.number) {
+            return Err(invalid_data(format!(
+                "the subdomain {} can not have the children {:?}",
+                self.number, numbers
+            )));
+        }
+        for child in self.children.iter() {
+            let mut data = arena[id].get().new_child(child.number);
+            data.set_checked(child.checked);
+            let child_id = arena.new_node(data);
+            id.append(child_id, arena);
+            child.append_children(arena, child_id)?;
+        }
+        Ok(())
+    }
+
+    /// The root is always followed by [`OPEN`] and [`CLOSE`], even without children.
+    pub(crate) fn to_vec_tree(&self) -> Vec<u8> {
+        let mut vec_tree = vec![self.number, OPEN];
+        for child in self.children.iter() {
+            child.push_vec_tree(&mut vec_tree);
+        }
+        vec_tree.push(CLOSE);
+        vec_tree
+    }
+
+    fn push_vec_tree(&self, vec_tree: &mut Vec<u8>) {
+        vec_tree.push(self.number);
+        if !self.children.is_empty() {
+            vec_tree.push(OPEN);
+            for child in self.children.iter() {
+                child.push_vec_tree(vec_tree);
+            }
+            vec_tree.push(CLOSE);
+        }
+    }
+
+    pub(crate) fn from_vec_tree(vec_tree: &[u8]) -> Result<Self> {
+        let mut index = 0;
+        let root = Self::parse_vec_tree(vec_tree, &mut index)?;
+        if index != vec_tree.len() {
+            return Err(invalid_data(format!(
+                "unexpected token {} at position {}",
+                vec_tree[index], index
+            )));
+        }
+        Ok(root)
+    }
+
+    fn parse_vec_tree(vec_tree: &[u8], index: &mut usize) -> Result<Self> {
+        let number = match vec_tree.get(*index) {
+            Some(&number) if number < OPEN => number,
+            Some(&token) => {
+                return Err(invalid_data(format!(
+                    "expected a node number at position {}, found {}",
+                    index, token
+                )))
+            }
+            None => return Err(invalid_data("the vec tree ends too early".to_string())),
+        };
+        *index += 1;
+        let mut children = Vec::new();
+        if vec_tree.get(*index) == Some(&OPEN) {
+            *index += 1;
+            while vec_tree.get(*index) != Some(&CLOSE) {
+                children.push(Self::parse_vec_tree(vec_tree, index)?);
+            }
+            *index += 1;
+        }
+        Ok(Self {
+            number,
+            checked: false,
+            children,
+        })
+    }
+
+    pub(crate) fn write_binary<W: Write>(&self, dimension: u8, mut writer: W) -> Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&[dimension])?;
+        self.write_binary_nodes(&mut writer)
+    }
+
+    fn write_binary_nodes<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let num_children = self.children.len() as u8;
+        writer.write_all(&[self.number, self.checked as u8, num_children])?;
+        for child in self.children.iter() {
+            child.write_binary_nodes(writer)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn read_binary<R: Read>(dimension: u8, mut reader: R) -> Result<Self> {
+        let mut header = [0; 9];
+        reader.read_exact(&mut header)?;
+        if &header[0..4] != MAGIC {
+            return Err(invalid_data("this is not a binary tree file".to_string()));
+        }
+        let version = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        check_header(version, header[8], dimension)?;
+        Self::read_binary_nodes(&mut reader)
+    }
+
+    fn read_binary_nodes<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut node = [0; 3];
+        reader.read_exact(&mut node)?;
+        let checked = match node[1] {
+            0 => false,
+            1 => true,
+            flag => return Err(invalid_data(format!("invalid checked flag {}", flag))),
+        };
+        let children = (0..node[2])
+            .map(|_| Self::read_binary_nodes(reader))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            number: node[0],
+            checked,
+            children,
+        })
+    }
+
+    pub(crate) fn write_json<W: Write>(&self, dimension: u8, writer: W) -> Result<()> {
+        let tree = JsonTree {
+            format: FORMAT_NAME.to_string(),
+            version: VERSION,
+            dimension,
+            root: self.clone(),
+        };
+        serde_json::to_writer_pretty(writer, &tree)
+            .map_err(|error| IntegrationError::Io(error.into()))
+    }
+
+    pub(crate) fn read_json<R: Read>(dimension: u8, reader: R) -> Result<Self> {
+        let tree: JsonTree =
+            serde_json::from_reader(reader).map_err(|error| invalid_data(error.to_string()))?;
+        if tree.format != FORMAT_NAME {
+            return Err(invalid_data(format!("unknown format {}", tree.format)));
+        }
+        check_header(tree.version, tree.dimension, dimension)?;
+        Ok(tree.root)
+    }
+}
+
+fn check_header(version: u32, file_dimension: u8, dimension: u8) -> Result<()> {
+    if version != VERSION {
+        return Err(invalid_data(format!(
+            "version {} is not supported, expected {}",
+            version, VERSION
+        )));
+    }
+    if file_dimension != dimension {
+        return Err(invalid_data(format!(
+            "the tree is {}D, expected {}D",
+            file_dimension, dimension
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TreeNode, VERSION};
+
+    #[test]
+    fn vec_tree_round_trip() {
+        let vec_tree = vec![0, 20, 1, 20, 1, 2, 3, 4, 21, 2, 3, 4, 21];
+        let root = TreeNode::from_vec_tree(&vec_tree).unwrap();
+        assert_eq!(root.children.len(), 4);
+        assert_eq!(root.children[0].children.len(), 4);
+        assert_eq!(root.to_vec_tree(), vec_tree);
+        // Ein Blatt als Wurzel
+        assert_eq!(
+            TreeNode::from_vec_tree(&[0]).unwrap().to_vec_tree(),
+            vec![0, 20, 21]
+        );
+
+        assert!(TreeNode::from_vec_tree(&[0, 20, 1, 2]).is_err());
+        assert!(TreeNode::from_vec_tree(&[0, 20, 21, 21]).is_err());
+        assert!(TreeNode::from_vec_tree(&[0, 20, 22, 21]).is_err());
+    }
+
+    #[test]
+    fn binary_header_is_checked() {
+        let root = TreeNode::from_vec_tree(&[0, 20, 1, 2, 3, 4, 21]).unwrap();
+        let mut bytes = Vec::new();
+        root.write_binary(2, &mut bytes).unwrap();
+        assert_eq!(bytes.len(), 9 + 5 * 3);
+        assert_eq!(TreeNode::read_binary(2, bytes.as_slice()).unwrap(), root);
+        assert!(TreeNode::read_binary(3, bytes.as_slice()).is_err());
+
+        bytes[4..8].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert!(TreeNode::read_binary(2, bytes.as_slice()).is_err());
+        assert!(TreeNode::read_binary(2, &b"nope"[..]).is_err());
+    }
+}